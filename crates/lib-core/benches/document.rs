@@ -0,0 +1,48 @@
+use divan::Bencher;
+use gen_lsp_types::Range;
+use lib_core::{document::Document, text_buffer_conversions::TextBufferConversions};
+use std::{fs, path::PathBuf};
+
+fn main() {
+    divan::main();
+}
+
+/// The parser's large benchmark file, with the position of a word typed in
+/// the middle of it and the content after typing it.
+fn large_document() -> (Document, Range, String) {
+    let content = fs::read_to_string("../lib-parser/benches/test_large.md").unwrap();
+    let document = Document::new(PathBuf::from("/bench/test_large.md"), &content, 0).unwrap();
+
+    let offset = content[..content.len() / 2]
+        .rfind("\n\n")
+        .map_or(0, |i| i + 2);
+    let position = document.content.slice(..).byte_offset_to_position(offset);
+
+    let mut edited = content;
+    edited.insert_str(offset, "edit ");
+    (document, Range::new(position, position), edited)
+}
+
+#[divan::bench]
+fn update_large_document_after_edit(bencher: Bencher) {
+    let (document, _, edited) = large_document();
+
+    bencher
+        .with_inputs(|| document.clone())
+        .bench_local_values(|mut document| {
+            document.update(&edited, 1).unwrap();
+            document
+        });
+}
+
+#[divan::bench]
+fn edit_large_document(bencher: Bencher) {
+    let (document, range, _) = large_document();
+
+    bencher
+        .with_inputs(|| document.clone())
+        .bench_local_values(|mut document| {
+            document.edit(range, "edit ", 1).unwrap();
+            document
+        });
+}
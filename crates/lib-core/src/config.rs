@@ -1,3 +1,4 @@
+use lib_parser::ParseOptions;
 use miette::{IntoDiagnostic, Result, WrapErr};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -14,6 +15,15 @@ pub struct Config {
     pub diagnostics: DiagnosticsConfig,
     /// Link resolution settings
     pub links: LinkConfig,
+    /// Task list settings
+    #[serde(default)]
+    pub tasks: TasksConfig,
+    /// Document formatting settings
+    #[serde(default)]
+    pub formatting: FormattingConfig,
+    /// Inlay hint settings
+    #[serde(default)]
+    pub inlay_hints: InlayHintsConfig,
 }
 
 /// Server-specific configuration
@@ -27,11 +37,16 @@ pub struct ServerConfig {
 
 /// Markdown parsing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MarkdownConfig {
     /// Enable frontmatter parsing
     pub enable_frontmatter: bool,
     /// Enable link validation
     pub validate_links: bool,
+    /// Include nested tags like `#project/alpha` when finding references to `#project`
+    pub nested_tag_references: bool,
+    /// Treat purely numeric tags like `#123` as tags instead of issue numbers
+    pub numeric_tags: bool,
 }
 
 impl Default for MarkdownConfig {
@@ -39,6 +54,17 @@ impl Default for MarkdownConfig {
         Self {
             enable_frontmatter: true,
             validate_links: true,
+            nested_tag_references: true,
+            numeric_tags: false,
+        }
+    }
+}
+
+impl MarkdownConfig {
+    /// Grammar options for the parser
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            numeric_tags: self.numeric_tags,
         }
     }
 }
@@ -75,11 +101,14 @@ pub enum LinkGenerationStyle {
 
 /// Diagnostics configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DiagnosticsConfig {
     /// Enable diagnostics for broken links
     pub enable_broken_links: bool,
     /// Enable diagnostics for missing frontmatter
     pub enable_missing_frontmatter: bool,
+    /// Page documenting the diagnostic codes, diagnostics link to `{docs_url}#{code}`
+    pub docs_url: Option<String>,
 }
 
 impl Default for DiagnosticsConfig {
@@ -87,6 +116,102 @@ impl Default for DiagnosticsConfig {
         Self {
             enable_broken_links: true,
             enable_missing_frontmatter: false,
+            docs_url: None,
+        }
+    }
+}
+
+/// Task list configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TasksConfig {
+    /// Append a `✅ YYYY-MM-DD` completion date when a task is marked done
+    pub append_completion_date: bool,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            append_completion_date: true,
+        }
+    }
+}
+
+/// Document formatting configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormattingConfig {
+    /// Bullet used for unordered list items
+    pub list_marker: ListMarker,
+    /// Spaces of indentation per list nesting level
+    pub list_indent: usize,
+    /// Most consecutive blank lines kept between blocks
+    pub max_blank_lines: usize,
+    /// Separate headers from the surrounding blocks with a blank line
+    pub blank_lines_around_headers: bool,
+    /// Remove trailing whitespace, except two-space hard line breaks
+    pub trim_trailing_whitespace: bool,
+    /// End the document with a line break
+    pub insert_final_newline: bool,
+    /// Remove blank lines at the end of the document
+    pub trim_final_newlines: bool,
+    /// Pad table cells so the columns line up
+    pub align_tables: bool,
+    /// Frontmatter keys moved to the top in this order, other keys keep theirs
+    pub frontmatter_key_order: Vec<String>,
+}
+
+impl Default for FormattingConfig {
+    fn default() -> Self {
+        Self {
+            list_marker: ListMarker::Dash,
+            list_indent: 2,
+            max_blank_lines: 1,
+            blank_lines_around_headers: true,
+            trim_trailing_whitespace: true,
+            insert_final_newline: true,
+            trim_final_newlines: true,
+            align_tables: true,
+            frontmatter_key_order: Vec::new(),
+        }
+    }
+}
+
+/// Inlay hint configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InlayHintsConfig {
+    /// Show the title of the linked note after links
+    pub link_titles: bool,
+    /// Show how many links point to a header after it
+    pub backlink_counts: bool,
+}
+
+impl Default for InlayHintsConfig {
+    fn default() -> Self {
+        Self {
+            link_titles: true,
+            backlink_counts: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ListMarker {
+    #[serde(rename = "-")]
+    Dash,
+    #[serde(rename = "*")]
+    Asterisk,
+    #[serde(rename = "+")]
+    Plus,
+}
+
+impl ListMarker {
+    pub fn as_char(&self) -> char {
+        match self {
+            ListMarker::Dash => '-',
+            ListMarker::Asterisk => '*',
+            ListMarker::Plus => '+',
         }
     }
 }
@@ -97,12 +222,18 @@ impl Config {
         markdown: MarkdownConfig,
         diagnostics: DiagnosticsConfig,
         links: LinkConfig,
+        tasks: TasksConfig,
+        formatting: FormattingConfig,
+        inlay_hints: InlayHintsConfig,
     ) -> Self {
         Self {
             server,
             markdown,
             diagnostics,
             links,
+            tasks,
+            formatting,
+            inlay_hints,
         }
     }
 
@@ -133,3 +264,17 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_tasks_table_uses_defaults() {
+        let tasks: TasksConfig = toml::from_str("").unwrap();
+        assert!(tasks.append_completion_date);
+
+        let tasks: TasksConfig = toml::from_str("append_completion_date = false").unwrap();
+        assert!(!tasks.append_completion_date);
+    }
+}
@@ -0,0 +1,397 @@
+use gen_lsp_types::{Position, Range};
+use lib_parser::{Parser, blocks_parser, incremental::Reparse};
+use miette::Result;
+use ropey::Rope;
+
+use crate::{
+    document::{
+        Document, ParseIssue, metadata::MetadataField, references::Reference, tasks::Task,
+        windows::window_input,
+    },
+    text_buffer_conversions::TextBufferConversions,
+};
+
+impl Document {
+    /// Replaces `range` with `text` and reparses only the blocks the edit can affect
+    ///
+    /// A range outside the document is clamped to it, as the LSP specification asks for
+    /// positions past the end of a line, and the whole document is reparsed.
+    pub fn edit(&mut self, range: Range, text: &str, version: i32) -> Result<()> {
+        let slice = self.content.slice(..);
+        let exact = slice
+            .try_position_to_byte_offset(range.start)
+            .zip(slice.try_position_to_byte_offset(range.end))
+            .filter(|(start, end)| start <= end)
+            .map(|(start, end)| start..end);
+        let Some(edit) = exact else {
+            tracing::warn!("Clamping edit range {:?} in {:?}", range, self.path);
+            let (start, end) = (
+                clamp_position(&self.content, range.start),
+                clamp_position(&self.content, range.end),
+            );
+            self.replace(start.min(end)..start.max(end), text);
+            self.version = version;
+            return self.parse_and_analyze();
+        };
+
+        let reparse = Reparse::new(&self.blocks, edit.clone(), text.len(), slice.len_bytes());
+
+        // Positions before the edit are needed to move everything after the window
+        let old_content = self.content.clone();
+        self.replace(edit, text);
+        self.version = version;
+
+        match reparse {
+            // The start of the document is also checked for broken frontmatter
+            Some(reparse) if reparse.new.start > 0 => self.reparse(reparse, &old_content),
+            _ => self.parse_and_analyze(),
+        }
+    }
+
+    fn replace(&mut self, edit: std::ops::Range<usize>, text: &str) {
+        let start = self.content.byte_to_char(edit.start);
+        let end = self.content.byte_to_char(edit.end);
+        self.content.remove(start..end);
+        self.content.insert(start, text);
+    }
+
+    fn reparse(&mut self, mut reparse: Reparse, old_content: &Rope) -> Result<()> {
+        let content = self.content.clone();
+        let doc_content_slice = content.slice(..);
+
+        let mut buffer = String::new();
+
+        loop {
+            let input = window_input(doc_content_slice, reparse.new.clone(), &mut buffer);
+            let (body, errors) = blocks_parser(self.parse_options)
+                .parse(input)
+                .into_output_errors();
+
+            // A full parse stops where the window parse stops, if it does not reach the end
+            let parsed_to_end = body.as_ref().is_some_and(|body| {
+                let end = body.last().map_or(0, |block| block.1.end);
+                input[end..].trim().is_empty()
+            });
+            let Some(body) = body.filter(|_| parsed_to_end) else {
+                return self.parse_and_analyze();
+            };
+
+            // Analyze the window on its own and splice the results into the old ones
+            let references = std::mem::take(&mut self.references);
+            let tasks = std::mem::take(&mut self.tasks);
+            let parse_errors = std::mem::take(&mut self.parse_errors);
+            let metadata = std::mem::take(self.metadata.fields_mut());
+
+            self.push_parse_errors(doc_content_slice, input, reparse.new.start, errors);
+            let blocks = self.analyze_blocks(doc_content_slice, input, reparse.new.start, body);
+
+            let ends_at_resync = blocks.last().is_none_or(|block| block.resync);
+            if !ends_at_resync && reparse.extend(&self.blocks, old_content.len_bytes()) {
+                self.references = references;
+                self.tasks = tasks;
+                self.parse_errors = parse_errors;
+                *self.metadata.fields_mut() = metadata;
+                continue;
+            }
+
+            let window = Window {
+                start: doc_content_slice.byte_offset_to_position(reparse.new.start),
+                old_end: old_content
+                    .slice(..)
+                    .byte_offset_to_position(reparse.old.end),
+                line_delta: i64::from(
+                    doc_content_slice
+                        .byte_offset_to_position(reparse.new.end)
+                        .line,
+                ) - i64::from(
+                    old_content
+                        .slice(..)
+                        .byte_offset_to_position(reparse.old.end)
+                        .line,
+                ),
+            };
+
+            self.references = window.splice(references, std::mem::take(&mut self.references));
+            self.tasks = window.splice(tasks, std::mem::take(&mut self.tasks));
+            self.parse_errors = window.splice(parse_errors, std::mem::take(&mut self.parse_errors));
+            let fields = window.splice(metadata, std::mem::take(self.metadata.fields_mut()));
+            *self.metadata.fields_mut() = fields;
+
+            reparse.splice(&mut self.blocks, blocks);
+            self.link_subtasks();
+
+            return Ok(());
+        }
+    }
+}
+
+/// Reparsed part of the document in LSP positions
+struct Window {
+    start: Position,
+    /// End of the window before the edit
+    old_end: Position,
+    /// Lines the window grew by
+    line_delta: i64,
+}
+
+impl Window {
+    /// Replaces the items inside the window with `new_items`, and moves the
+    /// items after it by the lines the window grew or shrank by.
+    fn splice<T: Located>(&self, old_items: Vec<T>, new_items: Vec<T>) -> Vec<T> {
+        let mut items = Vec::with_capacity(old_items.len() + new_items.len());
+        let mut insert_at = None;
+
+        for mut item in old_items {
+            let start = item.start();
+            if start < self.start {
+                items.push(item);
+                continue;
+            }
+
+            insert_at.get_or_insert(items.len());
+            if start >= self.old_end {
+                item.shift_lines(self.line_delta);
+                items.push(item);
+            }
+        }
+
+        let insert_at = insert_at.unwrap_or(items.len());
+        items.splice(insert_at..insert_at, new_items);
+        items
+    }
+}
+
+/// Items derived from a block that can be moved with it
+trait Located {
+    fn start(&self) -> Position;
+    fn shift_lines(&mut self, delta: i64);
+}
+
+fn shift_range(range: &mut Range, delta: i64) {
+    for position in [&mut range.start, &mut range.end] {
+        position.line = (i64::from(position.line) + delta) as u32;
+    }
+}
+
+impl Located for Reference {
+    fn start(&self) -> Position {
+        self.range.start
+    }
+
+    fn shift_lines(&mut self, delta: i64) {
+        shift_range(&mut self.range, delta);
+    }
+}
+
+impl Located for Task {
+    fn start(&self) -> Position {
+        self.range.start
+    }
+
+    fn shift_lines(&mut self, delta: i64) {
+        shift_range(&mut self.range, delta);
+        shift_range(&mut self.checkbox_range, delta);
+    }
+}
+
+impl Located for MetadataField {
+    fn start(&self) -> Position {
+        self.range.start
+    }
+
+    fn shift_lines(&mut self, delta: i64) {
+        shift_range(&mut self.range, delta);
+        if let Some(block_range) = &mut self.block_range {
+            shift_range(block_range, delta);
+        }
+    }
+}
+
+impl Located for ParseIssue {
+    fn start(&self) -> Position {
+        self.range.start
+    }
+
+    fn shift_lines(&mut self, delta: i64) {
+        shift_range(&mut self.range, delta);
+        if let Some(opening) = &mut self.opening {
+            shift_range(opening, delta);
+        }
+    }
+}
+
+/// Byte offset of `position`, moved back to the end of its line or of the document
+fn clamp_position(content: &Rope, position: Position) -> usize {
+    let line = position.line as usize;
+    if line >= content.len_lines() {
+        return content.len_bytes();
+    }
+
+    let text = content.line(line).to_string();
+    let length = text.trim_end_matches(['\n', '\r']).chars().count();
+    let character = (position.character as usize).min(length);
+    content.char_to_byte(content.line_to_char(line) + character)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lib_parser::error::ParseErrorKind;
+
+    use super::*;
+
+    const SOURCE: &str = "---\ntags: [a]\n---\n# Title\n\nIntro [[Link]] #tag\nstatus:: draft\n\n\
+- [ ] Task one 📅 2026-01-01\n- [x] Task two [due:: 2026-02-01]\n\n## Section\n\nPara [[Other]]\n\n\
+[^1]: Note [[Foot]]\n";
+
+    fn assert_same_as_full_parse(document: &Document) {
+        let full = Document::new(document.path.clone(), &document.content.to_string(), 0).unwrap();
+
+        assert_eq!(
+            format!("{:?}", document.references),
+            format!("{:?}", full.references)
+        );
+        assert_eq!(format!("{:?}", document.tasks), format!("{:?}", full.tasks));
+        assert_eq!(document.metadata, full.metadata);
+        assert_eq!(document.parse_errors, full.parse_errors);
+        assert_eq!(document.blocks, full.blocks);
+    }
+
+    /// Replaces the first `old` after `after` with `new`
+    fn edit(document: &mut Document, after: &str, old: &str, new: &str) {
+        let content = document.content.to_string();
+        let from = content.find(after).unwrap();
+        let start = from + content[from..].find(old).unwrap();
+        let slice = document.content.slice(..);
+        let range = slice.byte_to_lsp_range(&(start..start + old.len()));
+
+        document.edit(range, new, document.version + 1).unwrap();
+        assert_eq!(
+            document.content.to_string(),
+            format!(
+                "{}{new}{}",
+                &content[..start],
+                &content[start + old.len()..]
+            )
+        );
+    }
+
+    #[test]
+    fn test_edits_match_full_parse() {
+        let edits = [
+            ("Intro", "Link", "Linked"),
+            ("Task two", "\n", "\n- [ ] Inserted [[New]]\n"),
+            ("#tag", "\n\n", "\n"),
+            ("## Section", "## ", ""),
+            ("Task one", "- [ ] ", "Not a task "),
+            ("tags", "a", "a, b"),
+            ("Para", "Para", "Para\n\n# Header [[H]]\n\nMore #text"),
+            ("[^1]", "Foot", "Foot]] and [[Bar"),
+            ("Para", "[[Other]]", "[[Other"),
+            ("# Title", "Title\n\n", ""),
+            ("", "\n", "\n\n\n"),
+        ];
+
+        let mut document = Document::new(PathBuf::from("/test.md"), SOURCE, 0).unwrap();
+        for (after, old, new) in edits {
+            edit(&mut document, after, old, new);
+            assert_same_as_full_parse(&document);
+        }
+
+        // Appending at the end and deleting everything
+        let end = document
+            .content
+            .slice(..)
+            .byte_offset_to_position(document.content.len_bytes());
+        document
+            .edit(Range::new(end, end), "\n- [ ] Last", 20)
+            .unwrap();
+        assert_same_as_full_parse(&document);
+
+        document
+            .edit(
+                Range::new(Position::new(0, 0), Position::new(end.line + 1, 10)),
+                "",
+                21,
+            )
+            .unwrap();
+        assert!(document.content.len_bytes() == 0);
+        assert_same_as_full_parse(&document);
+    }
+
+    #[test]
+    fn test_edit_moves_following_items() {
+        let mut document = Document::new(PathBuf::from("/test.md"), SOURCE, 0).unwrap();
+        let other = |document: &Document| {
+            document
+                .references
+                .iter()
+                .find(|reference| reference.range.start.line > 10)
+                .unwrap()
+                .range
+        };
+        let before = other(&document);
+
+        edit(&mut document, "Intro", "Intro", "Intro\nmore\nlines");
+        assert_eq!(other(&document).start.line, before.start.line + 2);
+        assert_eq!(other(&document).start.character, before.start.character);
+        assert_same_as_full_parse(&document);
+    }
+
+    #[test]
+    fn test_out_of_range_edits_are_clamped() {
+        let mut document = Document::new(PathBuf::from("/test.md"), "# One\n\nText\n", 0).unwrap();
+
+        document
+            .edit(
+                Range::new(Position::new(0, 40), Position::new(0, 40)),
+                " [[A]]",
+                1,
+            )
+            .unwrap();
+        document
+            .edit(
+                Range::new(Position::new(100, 0), Position::new(100, 1)),
+                "#tag",
+                2,
+            )
+            .unwrap();
+        document
+            .edit(
+                Range::new(Position::new(2, 4), Position::new(2, 0)),
+                "More",
+                3,
+            )
+            .unwrap();
+
+        assert_eq!(document.content.to_string(), "# One [[A]]\n\nMore\n#tag");
+        assert_eq!(document.version, 3);
+        assert_same_as_full_parse(&document);
+    }
+
+    #[test]
+    fn test_parse_errors_follow_edits() {
+        let mut document =
+            Document::new(PathBuf::from("/test.md"), "Intro\n\nSee [[Note\n\nEnd", 0).unwrap();
+        let [error] = document.parse_errors.as_slice() else {
+            panic!("{:?}", document.parse_errors);
+        };
+        assert_eq!(error.kind, ParseErrorKind::UnclosedWikiLink);
+        assert_eq!(
+            error.opening,
+            Some(Range::new(Position::new(2, 4), Position::new(2, 6)))
+        );
+
+        let at = error.range.start;
+        document.edit(Range::new(at, at), "]]", 1).unwrap();
+        assert!(document.parse_errors.is_empty());
+
+        edit(&mut document, "", "Intro", "---\nIntro");
+        assert_eq!(
+            document.parse_errors[0].kind,
+            ParseErrorKind::MalformedFrontmatter
+        );
+        assert_same_as_full_parse(&document);
+    }
+}
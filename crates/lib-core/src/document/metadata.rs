@@ -1,5 +1,8 @@
+use gen_lsp_types::{Position, Range};
 use lib_parser::yaml::Yaml;
 
+use crate::text_buffer_conversions::range_contains_position;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FrontmatterValue {
     String(String),
@@ -39,3 +42,172 @@ impl From<Yaml<'_>> for FrontmatterValue {
         }
     }
 }
+
+/// Where a metadata field was declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataSource {
+    /// A key in the YAML frontmatter
+    Frontmatter,
+    /// A Dataview-style `key:: value` or `[key:: value]` field in the body
+    Inline,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataField {
+    pub key: String,
+    pub value: FrontmatterValue,
+    pub source: MetadataSource,
+    /// Range of the whole field declaration
+    pub range: Range,
+    /// Range of the block (paragraph, list item) the field was declared in.
+    /// `None` for frontmatter, which belongs to the whole document.
+    pub block_range: Option<Range>,
+}
+
+/// Unified view over frontmatter and inline fields of a document.
+///
+/// Keys are matched case-insensitively, the same way Dataview does.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    fields: Vec<MetadataField>,
+}
+
+impl Metadata {
+    pub fn push(&mut self, field: MetadataField) {
+        self.fields.push(field);
+    }
+
+    pub(crate) fn fields_mut(&mut self) -> &mut Vec<MetadataField> {
+        &mut self.fields
+    }
+
+    pub fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MetadataField> {
+        self.fields.iter()
+    }
+
+    /// First declaration of `key`, frontmatter taking precedence over inline fields
+    pub fn get(&self, key: &str) -> Option<&MetadataField> {
+        let mut fields = self
+            .fields
+            .iter()
+            .filter(|field| field.key.eq_ignore_ascii_case(key));
+
+        let first = fields.next()?;
+        if first.source == MetadataSource::Frontmatter {
+            return Some(first);
+        }
+
+        fields
+            .find(|field| field.source == MetadataSource::Frontmatter)
+            .or(Some(first))
+    }
+
+    /// Every declaration of `key` in document order
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a MetadataField> {
+        self.fields
+            .iter()
+            .filter(move |field| field.key.eq_ignore_ascii_case(key))
+    }
+
+    /// Whether any declaration of `key` has `value`, or contains it for lists
+    pub fn has_value(&self, key: &str, value: &str) -> bool {
+        self.get_all(key).any(|field| {
+            field
+                .value
+                .to_string_list()
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(value))
+        })
+    }
+
+    /// Unique keys in the order they were first declared
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for field in &self.fields {
+            if !keys.iter().any(|key| key.eq_ignore_ascii_case(&field.key)) {
+                keys.push(&field.key);
+            }
+        }
+        keys
+    }
+
+    /// Fields declared inside the block that contains `position`
+    pub fn block_fields(&self, position: Position) -> impl Iterator<Item = &MetadataField> {
+        self.fields.iter().filter(move |field| {
+            field
+                .block_range
+                .is_some_and(|range| range_contains_position(&range, position))
+        })
+    }
+
+    /// The field declaration under `position`, if any
+    pub fn field_at_position(&self, position: Position) -> Option<&MetadataField> {
+        self.fields
+            .iter()
+            .find(|field| range_contains_position(&field.range, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use gen_lsp_types::Position;
+
+    use super::*;
+    use crate::document::Document;
+
+    const INPUT: &str = "---\nstatus: draft\n---\n\nIntro text\nStatus:: review\n\n- [ ] Ship it [due:: 2026-01-01]\n";
+
+    #[test]
+    fn test_frontmatter_and_inline_fields_are_merged() {
+        let document = Document::new(PathBuf::from("/test.md"), INPUT, 0).unwrap();
+        let metadata = &document.metadata;
+
+        assert_eq!(metadata.keys(), vec!["status", "due"]);
+
+        let status = metadata.get("STATUS").unwrap();
+        assert_eq!(status.source, MetadataSource::Frontmatter);
+        assert_eq!(status.value.as_string(), Some("draft"));
+        assert_eq!(status.range.start, Position::new(1, 0));
+
+        let inline = metadata.get_all("status").nth(1).unwrap();
+        assert_eq!(inline.source, MetadataSource::Inline);
+        assert_eq!(inline.value.as_string(), Some("review"));
+        assert_eq!(inline.range.start, Position::new(5, 0));
+    }
+
+    #[test]
+    fn test_block_fields() {
+        let document = Document::new(PathBuf::from("/test.md"), INPUT, 0).unwrap();
+
+        let fields: Vec<_> = document
+            .metadata
+            .block_fields(Position::new(7, 4))
+            .collect();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].key, "due");
+
+        let field = document
+            .metadata
+            .field_at_position(Position::new(7, 16))
+            .unwrap();
+        assert_eq!(field.value.as_string(), Some("2026-01-01"));
+    }
+
+    #[test]
+    fn test_inline_field_values_are_unescaped() {
+        let document = Document::new(PathBuf::from("/test.md"), "key:: a \\# b\n", 0).unwrap();
+
+        let field = document.metadata.get("key").unwrap();
+        assert_eq!(field.value.as_string(), Some("a # b"));
+    }
+}
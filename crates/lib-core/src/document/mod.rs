@@ -1,37 +1,90 @@
 use std::{collections::HashMap, fmt::Debug, path::PathBuf};
 
-use gen_lsp_types::{Diagnostic, DiagnosticSeverity, Position};
-use lib_parser::{InlineMarkdownNode, LinkType, MarkdownNode, Parser, Spanned, markdown_parser};
+use gen_lsp_types::{Position, Range};
+use lib_parser::{
+    InlineMarkdownNode, LinkType, MarkdownNode, MarkdownText, ParseOptions, ParsedMarkdown, Parser,
+    SimpleSpan, Spanned, blocks_parser,
+    error::{MarkdownError, ParseErrorKind},
+    incremental::BlockSpan,
+    markdown_parser_with, slice_span, unescape,
+    visit::{Visitor, walk_text},
+    yaml::{Frontmatter, Yaml, yaml_parser},
+};
 use miette::Result;
 use references::{Reference, ReferenceKind};
-use ropey::Rope;
+use ropey::{Rope, RopeSlice};
 
-use crate::{document::metadata::FrontmatterValue, text_buffer_conversions::TextBufferConversions};
+use crate::{
+    document::{
+        metadata::{FrontmatterValue, Metadata, MetadataField, MetadataSource},
+        tasks::{Task, TaskPriority, TaskState, indent_width, is_iso_date, parse_task_signifiers},
+    },
+    text_buffer_conversions::TextBufferConversions,
+};
+use windows::{MIN_WINDOW_LEN, ends_at_resync, window_end, window_input};
 
+mod incremental;
 pub mod metadata;
 pub mod references;
+pub mod tasks;
+mod windows;
 
 #[derive(Debug, Clone)]
 pub struct Document {
     pub path: PathBuf,
     pub frontmatter: HashMap<String, FrontmatterValue>,
+    /// From the opening to the closing `---` of the frontmatter, if it parsed
+    pub frontmatter_range: Option<Range>,
+    /// Frontmatter and inline fields, with the ranges they were declared at
+    pub metadata: Metadata,
+    /// Task list items, in document order
+    pub tasks: Vec<Task>,
     pub version: i32,
     pub content: Rope,
     pub references: Vec<Reference>,
-    pub diagnostics: Vec<Diagnostic>,
+    /// Errors of the last parse, in document order
+    pub parse_errors: Vec<ParseIssue>,
     pub is_open: bool,
+    /// Grammar options the document is parsed with
+    pub parse_options: ParseOptions,
+    /// Blocks of the last parse, to find what an edit has to reparse
+    blocks: Vec<BlockSpan>,
+}
+
+/// A parse error located in the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseIssue {
+    pub kind: ParseErrorKind,
+    pub message: String,
+    pub range: Range,
+    /// Delimiter that opened the construct the error is about, e.g. the `[[` of an unclosed wikilink
+    pub opening: Option<Range>,
 }
 
 impl Document {
     pub fn new(path: PathBuf, content: &str, version: i32) -> Result<Self> {
+        Self::with_options(path, content, version, ParseOptions::default())
+    }
+
+    pub fn with_options(
+        path: PathBuf,
+        content: &str,
+        version: i32,
+        parse_options: ParseOptions,
+    ) -> Result<Self> {
         let mut s = Self {
             path,
             version,
             content: Rope::from_str(content),
             references: Vec::new(),
-            diagnostics: Vec::new(),
+            parse_errors: Vec::new(),
             is_open: false,
             frontmatter: HashMap::new(),
+            frontmatter_range: None,
+            metadata: Metadata::default(),
+            tasks: Vec::new(),
+            parse_options,
+            blocks: Vec::new(),
         };
         s.parse_and_analyze()?;
 
@@ -45,6 +98,34 @@ impl Document {
         Ok(())
     }
 
+    /// Reparses the document if `parse_options` differ from the current ones
+    pub fn set_parse_options(&mut self, parse_options: ParseOptions) -> Result<()> {
+        if self.parse_options == parse_options {
+            return Ok(());
+        }
+
+        self.parse_options = parse_options;
+        self.parse_and_analyze()
+    }
+
+    /// The `title` frontmatter field or the first H1
+    pub fn title(&self) -> Option<String> {
+        if let Some(title) = self
+            .frontmatter
+            .get("title")
+            .and_then(|title| title.as_string())
+        {
+            return Some(title.to_string());
+        }
+
+        self.references
+            .iter()
+            .find_map(|reference| match &reference.kind {
+                ReferenceKind::Header { level: 1, content } => Some(content.clone()),
+                _ => None,
+            })
+    }
+
     pub fn get_reference_at_position(&self, position: Position) -> Option<&Reference> {
         self.references
             .iter()
@@ -53,145 +134,353 @@ impl Document {
 
     fn parse_and_analyze(&mut self) -> Result<()> {
         self.references.clear();
-        self.diagnostics.clear();
+        self.parse_errors.clear();
+        self.frontmatter.clear();
+        self.frontmatter_range = None;
+        self.metadata.clear();
+        self.tasks.clear();
+        self.blocks.clear();
+
+        // Rope clones are cheap and let us hold a slice while mutating `self`
+        let content = self.content.clone();
+        let doc_content_slice = content.slice(..);
+        let mut buffer = String::new();
+
+        let mut start = 0;
+        loop {
+            start = self.parse_window(doc_content_slice, start, &mut buffer);
+            if start >= doc_content_slice.len_bytes() {
+                self.link_subtasks();
+                return Ok(());
+            }
+        }
+    }
 
-        let doc_content_slice = self.content.slice(..);
-        let input = doc_content_slice.to_string();
+    /// Parses and analyzes the window of blocks starting at byte `start`, the
+    /// first one with the frontmatter. Returns where the next window starts.
+    fn parse_window(
+        &mut self,
+        doc_content_slice: RopeSlice,
+        start: usize,
+        buffer: &mut String,
+    ) -> usize {
+        let len = doc_content_slice.len_bytes();
+        let mut min_len = MIN_WINDOW_LEN;
+
+        loop {
+            let end = window_end(doc_content_slice, start, min_len);
+            let input = window_input(doc_content_slice, start..end, buffer);
+            let (parsed_markdown, errors) = if start == 0 {
+                markdown_parser_with(self.parse_options)
+                    .parse(input)
+                    .into_output_errors()
+            } else {
+                blocks_parser(self.parse_options)
+                    .map(|body| ParsedMarkdown {
+                        frontmatter: None,
+                        body,
+                    })
+                    .parse(input)
+                    .into_output_errors()
+            };
+
+            // Frontmatter can contain blank lines, the window may have cut it off
+            let cut_frontmatter = errors
+                .iter()
+                .any(|err| err.kind() == ParseErrorKind::MalformedFrontmatter);
+            let complete = parsed_markdown.as_ref().is_some_and(|parsed| {
+                !cut_frontmatter && ends_at_resync(doc_content_slice, input, start, &parsed.body)
+            });
+            if !complete && end < len {
+                min_len = (end - start) * 2;
+                continue;
+            }
+
+            self.push_parse_errors(doc_content_slice, input, start, errors);
+            let Some(parsed_markdown) = parsed_markdown else {
+                tracing::debug!("Failed to parse");
+                return end;
+            };
+
+            if let Some(frontmatter) = parsed_markdown.frontmatter {
+                self.analyze_frontmatter(doc_content_slice, input, frontmatter);
+            }
+            let blocks = self.analyze_blocks(doc_content_slice, input, start, parsed_markdown.body);
+            self.blocks.extend(blocks);
+
+            return end;
+        }
+    }
+
+    /// Indexes the frontmatter fields, `input` is the text of the first window
+    fn analyze_frontmatter(
+        &mut self,
+        doc_content_slice: RopeSlice,
+        input: &str,
+        frontmatter: Frontmatter,
+    ) {
+        let span = yaml_parser()
+            .map_with(|_, e| e.span())
+            .lazy()
+            .parse(input)
+            .into_output();
+        self.frontmatter_range = span
+            .and_then(|span| input[..span.end].rfind("---"))
+            .map(|close| doc_content_slice.byte_to_lsp_range(&(0..close + 3)));
+
+        for (key, val) in frontmatter.0 {
+            let value_end = match &val {
+                Yaml::String(value) => slice_span(input, value),
+                Yaml::List(items) => items.last().and_then(|item| slice_span(input, item)),
+            };
+            if let Some(key_span) = slice_span(input, key) {
+                let end = value_end.map_or(key_span.end, |span| span.end);
+                self.metadata.push(MetadataField {
+                    key: key.to_string(),
+                    value: FrontmatterValue::from(val.clone()),
+                    source: MetadataSource::Frontmatter,
+                    range: doc_content_slice.byte_to_lsp_range(&(key_span.start..end)),
+                    block_range: None,
+                });
+            }
+
+            self.frontmatter
+                .insert(key.to_string(), FrontmatterValue::from(val));
+        }
+    }
+
+    /// Records parser errors in document order, `input` is the parsed text and
+    /// `offset` where it starts
+    fn push_parse_errors(
+        &mut self,
+        doc_content_slice: RopeSlice,
+        input: &str,
+        offset: usize,
+        mut errors: Vec<MarkdownError>,
+    ) {
+        // Errors found while validating, e.g. of the frontmatter, come last
+        errors.sort_by_key(|err| err.span().start);
+        let to_range = |span: std::ops::Range<usize>| {
+            doc_content_slice.byte_to_lsp_range(&(span.start + offset..span.end + offset))
+        };
 
-        let (parsed_markdown, errors) = markdown_parser().parse(&input).into_output_errors();
         for err in errors {
-            self.diagnostics.push(Diagnostic {
-                range: doc_content_slice.byte_to_lsp_range(&err.span().into_range()),
-                severity: Some(DiagnosticSeverity::Warning),
-                code: None,
-                code_description: None,
-                source: Some("parser".to_string()),
-                message: err.reason().to_string(),
-                related_information: None,
-                tags: None,
-                data: None,
+            let kind = err.kind();
+            let message = match kind {
+                ParseErrorKind::Unexpected => err.reason().to_string(),
+                kind => kind.to_string(),
+            };
+            self.parse_errors.push(ParseIssue {
+                kind,
+                message,
+                range: to_range(err.span().into_range()),
+                opening: kind
+                    .opening_span(input, *err.span())
+                    .map(|span| to_range(span.into_range())),
             });
         }
+    }
 
-        let Some(parsed_markdown) = parsed_markdown else {
-            tracing::debug!("Failed to parse");
-            return Ok(());
+    /// Collects references, tasks and inline fields from parsed blocks.
+    ///
+    /// `input` is the parsed text, which starts at byte `offset` of the document.
+    fn analyze_blocks(
+        &mut self,
+        doc_content_slice: RopeSlice,
+        input: &str,
+        offset: usize,
+        body: Vec<Spanned<MarkdownNode>>,
+    ) -> Vec<BlockSpan> {
+        let mut blocks = Vec::with_capacity(body.len());
+        let mut analyzer = Analyzer {
+            document: self,
+            doc_content_slice,
+            input,
+            offset,
+            block_range: Range::default(),
         };
 
-        let frontmatter = parsed_markdown.frontmatter;
-        if let Some(frontmatter) = frontmatter {
-            for (key, val) in frontmatter.0 {
-                self.frontmatter
-                    .insert(key.to_string(), FrontmatterValue::from(val));
-            }
+        for block in &body {
+            let span = block.1.start + offset..block.1.end + offset;
+            let following = doc_content_slice.byte_to_char(span.end);
+            let following = doc_content_slice
+                .slice(following..(following + 2).min(doc_content_slice.len_chars()))
+                .to_string();
+            blocks.push(BlockSpan::new(&block.0, span, &following));
+
+            analyzer.block_range = analyzer.range(block.1);
+            analyzer.visit_block(block);
         }
 
-        let body = parsed_markdown.body;
-        body.into_iter().for_each(|spanned| {
-            let Spanned(markdown, span) = spanned;
-            match markdown {
-                MarkdownNode::Header { level, content } => {
-                    let reference = Reference {
-                        kind: ReferenceKind::Header {
-                            level,
-                            content: content.to_string(),
-                        },
-                        range: doc_content_slice.byte_to_lsp_range(&span.into_range()),
-                    };
-                    self.references.push(reference);
-                }
-                MarkdownNode::Paragraph(inlines) => {
-                    for inline in inlines {
-                        let Spanned(inline_markdown, inline_span) = inline;
-
-                        if let InlineMarkdownNode::Link(link) = inline_markdown {
-                            match link {
-                                LinkType::InlineLink { text, uri, header } => {
-                                    let reference = Reference {
-                                        kind: ReferenceKind::Link {
-                                            target: uri.to_string(),
-                                            alt_text: text.to_string(),
-                                            title: None,
-                                            header: header.map(|x| x.to_string()),
-                                        },
-                                        range: doc_content_slice
-                                            .byte_to_lsp_range(&inline_span.into_range()),
-                                    };
-                                    self.references.push(reference);
-                                }
-                                LinkType::WikiLink {
-                                    target,
-                                    display_text,
-                                    header,
-                                } => {
-                                    let reference = Reference {
-                                        kind: ReferenceKind::WikiLink {
-                                            target: target.to_string(),
-                                            alias: display_text.map(|d| d.to_string()),
-                                            header: header.map(|x| x.to_string()),
-                                        },
-                                        range: doc_content_slice
-                                            .byte_to_lsp_range(&inline_span.into_range()),
-                                    };
-                                    self.references.push(reference);
-                                }
-                                LinkType::ImageLink { .. } => {
-                                    tracing::debug!("Not currently supporting images")
-                                }
-                            }
-                        }
-                    }
-                }
-                MarkdownNode::ListItem {
-                    checkbox: _,
-                    content: list_content,
-                } => {
-                    // Process links inside list item content (same as paragraph)
-                    for inline in list_content {
-                        let Spanned(inline_markdown, inline_span) = inline;
-
-                        if let InlineMarkdownNode::Link(link) = inline_markdown {
-                            match link {
-                                LinkType::InlineLink { text, uri, header } => {
-                                    let reference = Reference {
-                                        kind: ReferenceKind::Link {
-                                            target: uri.to_string(),
-                                            alt_text: text.to_string(),
-                                            title: None,
-                                            header: header.map(|x| x.to_string()),
-                                        },
-                                        range: doc_content_slice
-                                            .byte_to_lsp_range(&inline_span.into_range()),
-                                    };
-                                    self.references.push(reference);
-                                }
-                                LinkType::WikiLink {
-                                    target,
-                                    display_text,
-                                    header,
-                                } => {
-                                    let reference = Reference {
-                                        kind: ReferenceKind::WikiLink {
-                                            target: target.to_string(),
-                                            alias: display_text.map(|d| d.to_string()),
-                                            header: header.map(|x| x.to_string()),
-                                        },
-                                        range: doc_content_slice
-                                            .byte_to_lsp_range(&inline_span.into_range()),
-                                    };
-                                    self.references.push(reference);
-                                }
-                                LinkType::ImageLink { .. } => {
-                                    tracing::debug!("Not currently supporting images")
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {}
+        blocks
+    }
+
+    /// Builds a [`Task`] from a checkbox list item. Must run after the item's
+    /// inline fields were collected, as they can also carry task dates.
+    fn analyze_task(
+        &mut self,
+        state: TaskState,
+        text: &str,
+        tags: Vec<String>,
+        range: Range,
+        checkbox_range: Range,
+    ) {
+        let signifiers = parse_task_signifiers(text);
+
+        let field = |keys: &[&str]| {
+            self.metadata
+                .iter()
+                .filter(|field| field.block_range == Some(range))
+                .find(|field| keys.iter().any(|k| field.key.eq_ignore_ascii_case(k)))
+                .and_then(|field| field.value.as_string())
+                .map(str::to_string)
+        };
+        let date_field = |keys: &[&str]| field(keys).filter(|date| is_iso_date(date));
+
+        let task = Task {
+            state,
+            description: signifiers.description,
+            range,
+            checkbox_range,
+            priority: signifiers
+                .priority
+                .or_else(|| field(&["priority"]).and_then(|name| TaskPriority::from_name(&name))),
+            due: signifiers.due.or_else(|| date_field(&["due"])),
+            scheduled: signifiers.scheduled.or_else(|| date_field(&["scheduled"])),
+            start: signifiers.start.or_else(|| date_field(&["start"])),
+            created: signifiers.created.or_else(|| date_field(&["created"])),
+            done: signifiers
+                .done
+                .or_else(|| date_field(&["completion", "done"])),
+            recurrence: signifiers.recurrence.or_else(|| field(&["repeat"])),
+            tags,
+            indent: indent_width(self.content.line(range.start.line as usize)),
+            parent: None,
+        };
+
+        self.tasks.push(task);
+    }
+}
+
+/// Collects what a [`Document`] indexes from the blocks it visits
+struct Analyzer<'d, 's> {
+    document: &'d mut Document,
+    doc_content_slice: RopeSlice<'s>,
+    /// Parsed text, starting at byte `offset` of the document
+    input: &'s str,
+    offset: usize,
+    /// Range of the block being visited
+    block_range: Range,
+}
+
+impl Analyzer<'_, '_> {
+    fn range(&self, span: SimpleSpan) -> Range {
+        self.doc_content_slice
+            .byte_to_lsp_range(&(span.start + self.offset..span.end + self.offset))
+    }
+}
+
+impl<'a> Visitor<'a> for Analyzer<'_, '_> {
+    fn visit_header(&mut self, level: usize, content: &'a str, _span: SimpleSpan) {
+        self.document.references.push(Reference {
+            kind: ReferenceKind::Header {
+                level,
+                content: unescape(content).into_owned(),
+            },
+            range: self.block_range,
+        });
+    }
+
+    fn visit_list_item(
+        &mut self,
+        checkbox: Option<&Spanned<TaskState>>,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text(self, content);
+
+        if let Some(Spanned(state, checkbox_span)) = checkbox {
+            let (text, tags) = task_text(self.input, content);
+            let checkbox_range = self.range(*checkbox_span);
+            self.document
+                .analyze_task(*state, &text, tags, self.block_range, checkbox_range);
+        }
+    }
+
+    fn visit_link(&mut self, link: &LinkType<'a>, span: SimpleSpan) {
+        let kind = match link {
+            LinkType::InlineLink { text, uri, header } => ReferenceKind::Link {
+                target: unescape(uri).into_owned(),
+                alt_text: unescape(text).into_owned(),
+                title: None,
+                header: header.map(|x| unescape(x).into_owned()),
+            },
+            LinkType::WikiLink {
+                target,
+                display_text,
+                header,
+            } => ReferenceKind::WikiLink {
+                target: unescape(target).into_owned(),
+                alias: display_text.map(|d| unescape(d).into_owned()),
+                header: header.map(|x| unescape(x).into_owned()),
+            },
+            LinkType::ImageLink { .. } => {
+                tracing::debug!("Not currently supporting images");
+                return;
             }
+        };
+
+        let range = self.range(span);
+        self.document.references.push(Reference { kind, range });
+    }
+
+    fn visit_tag(&mut self, name: &'a str, span: SimpleSpan) {
+        let range = self.range(span);
+        self.document.references.push(Reference {
+            kind: ReferenceKind::Tag {
+                name: unescape(name).into_owned(),
+            },
+            range,
         });
+    }
 
-        Ok(())
+    fn visit_inline_field(
+        &mut self,
+        key: &'a str,
+        value: &'a str,
+        content: &MarkdownText<'a>,
+        span: SimpleSpan,
+    ) {
+        let range = self.range(span);
+        self.document.metadata.push(MetadataField {
+            key: key.to_string(),
+            value: FrontmatterValue::String(unescape(value).into_owned()),
+            source: MetadataSource::Inline,
+            range,
+            block_range: Some(self.block_range),
+        });
+
+        // Links inside field values are regular references
+        walk_text(self, content);
     }
 }
+
+/// Source text of a task without its inline fields, and the tags it contains
+fn task_text(input: &str, content: &MarkdownText) -> (String, Vec<String>) {
+    let mut text = String::new();
+    let mut tags = Vec::new();
+
+    for Spanned(node, span) in content {
+        match node {
+            InlineMarkdownNode::InlineField { .. } => text.push(' '),
+            InlineMarkdownNode::Tag(tag) => {
+                tags.push(tag.to_string());
+                text.push_str(&input[span.into_range()]);
+            }
+            InlineMarkdownNode::PlainText(plain) => text.push_str(&unescape(plain)),
+            _ => text.push_str(&input[span.into_range()]),
+        }
+    }
+
+    (text, tags)
+}
@@ -1,4 +1,11 @@
 use gen_lsp_types::{Position, Range};
+use lib_parser::{
+    InlineMarkdownNode, LinkType, Parser,
+    markdown::{link_parser, wikilink_parser},
+    slice_span,
+};
+
+use crate::text_buffer_conversions::range_contains_position;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Reference {
@@ -8,20 +15,7 @@ pub struct Reference {
 
 impl Reference {
     pub fn contains_position(&self, position: Position) -> bool {
-        if position.line < self.range.start.line || position.line > self.range.end.line {
-            return false;
-        }
-
-        if position.line == self.range.start.line && position.character < self.range.start.character
-        {
-            return false;
-        }
-
-        if position.line == self.range.end.line && position.character >= self.range.end.character {
-            return false;
-        }
-
-        true
+        range_contains_position(&self.range, position)
     }
 
     pub fn to_file_text(&self) -> String {
@@ -58,10 +52,46 @@ impl Reference {
             ReferenceKind::Header { level, content } => {
                 format!("{} {}", "#".repeat(*level), content)
             }
+            ReferenceKind::Tag { name } => format!("#{name}"),
         }
     }
 }
 
+/// The link written as `raw` in the source, pointing to `target` instead.
+///
+/// References hold unescaped text, so edits start from the source slice and
+/// only swap the target: escapes in the alias, header or link text are kept
+/// exactly as written.
+pub fn retarget_link(raw: &str, target: &str) -> Option<String> {
+    let node = wikilink_parser()
+        .or(link_parser())
+        .parse(raw)
+        .into_output()?;
+    let InlineMarkdownNode::Link(link) = node else {
+        return None;
+    };
+    let old_target = match link {
+        LinkType::WikiLink { target, .. } => target,
+        LinkType::InlineLink { uri, .. } => uri,
+        LinkType::ImageLink { .. } => return None,
+    };
+    let span = slice_span(raw, old_target)?;
+
+    let mut escaped = String::with_capacity(target.len());
+    for c in target.chars() {
+        if matches!(c, '\\' | '#' | '|' | '[' | ']' | '(' | ')') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    Some(format!(
+        "{}{escaped}{}",
+        &raw[..span.start],
+        &raw[span.end..]
+    ))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ReferenceKind {
     Header {
@@ -85,6 +115,10 @@ pub enum ReferenceKind {
         /// Specific header in another markdown file
         header: Option<String>,
     },
+    Tag {
+        /// Tag path without the `#`, e.g. `project/alpha`
+        name: String,
+    },
 }
 
 impl ReferenceKind {
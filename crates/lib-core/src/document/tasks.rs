@@ -0,0 +1,465 @@
+use gen_lsp_types::Range;
+use ropey::RopeSlice;
+
+pub use lib_parser::TaskState;
+
+use crate::{document::Document, vault::tags::is_descendant_tag};
+
+/// Priority of a task, using the Obsidian Tasks emoji signifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaskPriority {
+    /// `⏬`
+    Lowest,
+    /// `🔽`
+    Low,
+    /// `🔼`
+    Medium,
+    /// `⏫`
+    High,
+    /// `🔺`
+    Highest,
+}
+
+impl TaskPriority {
+    fn from_signifier(c: char) -> Option<Self> {
+        match c {
+            '⏬' => Some(TaskPriority::Lowest),
+            '🔽' => Some(TaskPriority::Low),
+            '🔼' => Some(TaskPriority::Medium),
+            '⏫' => Some(TaskPriority::High),
+            '🔺' => Some(TaskPriority::Highest),
+            _ => None,
+        }
+    }
+
+    /// Parses Dataview style `[priority:: high]` values
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "lowest" => Some(TaskPriority::Lowest),
+            "low" => Some(TaskPriority::Low),
+            "medium" => Some(TaskPriority::Medium),
+            "high" => Some(TaskPriority::High),
+            "highest" => Some(TaskPriority::Highest),
+            _ => None,
+        }
+    }
+}
+
+/// A task list item (`- [ ] ...`) with its parsed metadata.
+///
+/// Dates are kept as `YYYY-MM-DD` strings, which compare in chronological order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub state: TaskState,
+    /// Task text with the checkbox, dates and priority removed
+    pub description: String,
+    /// Range of the whole list item
+    pub range: Range,
+    /// Range of the `[ ]` checkbox
+    pub checkbox_range: Range,
+    pub priority: Option<TaskPriority>,
+    /// `📅` or `[due:: ]`
+    pub due: Option<String>,
+    /// `⏳` or `[scheduled:: ]`
+    pub scheduled: Option<String>,
+    /// `🛫` or `[start:: ]`
+    pub start: Option<String>,
+    /// `➕` or `[created:: ]`
+    pub created: Option<String>,
+    /// `✅` or `[completion:: ]`
+    pub done: Option<String>,
+    /// `🔁` recurrence rule, e.g. `every week`
+    pub recurrence: Option<String>,
+    pub tags: Vec<String>,
+    /// Indentation of the list item, see [`indent_width`]
+    pub indent: u32,
+    /// Index in [`Document::tasks`] of the task this one is nested under
+    pub parent: Option<usize>,
+}
+
+impl Document {
+    /// The task whose list item `task` is nested under, if that item is a task itself
+    pub fn parent_task(&self, task: &Task) -> Option<&Task> {
+        task.parent.and_then(|parent| self.tasks.get(parent))
+    }
+
+    /// Tasks directly nested under `task`
+    pub fn subtasks<'a>(&'a self, task: &'a Task) -> impl Iterator<Item = &'a Task> {
+        let index = self
+            .tasks
+            .iter()
+            .position(|other| std::ptr::eq(other, task));
+        self.tasks
+            .iter()
+            .filter(move |child| index.is_some() && child.parent == index)
+    }
+
+    /// Sets [`Task::parent`] of every task in one pass over the lines. The
+    /// parent is the closest line above that is less indented, if it is a
+    /// task, and a blank line ends the search.
+    pub(crate) fn link_subtasks(&mut self) {
+        // Lines of strictly increasing indentation, with their task
+        let mut stack: Vec<(u32, Option<usize>)> = Vec::new();
+        let mut next_task = 0;
+
+        for (line_idx, line) in self.content.lines().enumerate() {
+            if line.chars().all(char::is_whitespace) {
+                stack.clear();
+                continue;
+            }
+
+            let indent = indent_width(line);
+            while stack.last().is_some_and(|(above, _)| *above >= indent) {
+                stack.pop();
+            }
+
+            let task = (next_task < self.tasks.len()
+                && self.tasks[next_task].range.start.line as usize == line_idx)
+                .then_some(next_task);
+            if let Some(task) = task {
+                self.tasks[task].parent = stack.last().and_then(|(_, parent)| *parent);
+                next_task += 1;
+            }
+            stack.push((indent, task));
+        }
+    }
+}
+
+/// Width of the leading whitespace of `line`, with tabs advancing to the
+/// next multiple of 4 as in CommonMark
+pub fn indent_width(line: RopeSlice) -> u32 {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => break,
+        }
+    }
+    width
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`, used for `✅` completion dates
+pub fn today() -> String {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86_400)
+        .unwrap_or_default();
+
+    date_from_unix_days(days as i64)
+}
+
+/// Converts days since 1970-01-01 to a `YYYY-MM-DD` date (proleptic Gregorian calendar)
+fn date_from_unix_days(days: i64) -> String {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Filter over indexed tasks. Empty fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    /// Only tasks in one of these states
+    pub states: Vec<TaskState>,
+    /// Only tasks with this tag, either on the task or in the document's `tags` frontmatter
+    pub tag: Option<String>,
+    /// Only tasks due on or after this `YYYY-MM-DD` date
+    pub due_after: Option<String>,
+    /// Only tasks due on or before this `YYYY-MM-DD` date
+    pub due_before: Option<String>,
+    /// Only tasks whose document has all of these `key: value` metadata fields
+    pub metadata: Vec<(String, String)>,
+}
+
+impl TaskQuery {
+    pub fn matches(&self, document: &Document, task: &Task) -> bool {
+        if !self.states.is_empty() && !self.states.contains(&task.state) {
+            return false;
+        }
+
+        if let Some(tag) = &self.tag {
+            // Nested tags match their ancestors, `#project` finds `#project/x`
+            let has_tag = task.tags.iter().any(|t| is_descendant_tag(t, tag))
+                || document.metadata.get_all("tags").any(|field| {
+                    field
+                        .value
+                        .to_string_list()
+                        .iter()
+                        .any(|value| is_descendant_tag(value, tag))
+                });
+            if !has_tag {
+                return false;
+            }
+        }
+
+        if self.due_after.is_some() || self.due_before.is_some() {
+            let Some(due) = task.due.as_deref() else {
+                return false;
+            };
+            if self.due_after.as_deref().is_some_and(|after| due < after)
+                || self
+                    .due_before
+                    .as_deref()
+                    .is_some_and(|before| due > before)
+            {
+                return false;
+            }
+        }
+
+        self.metadata
+            .iter()
+            .all(|(key, value)| document.metadata.has_value(key, value))
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TaskSignifiers {
+    pub description: String,
+    pub priority: Option<TaskPriority>,
+    pub due: Option<String>,
+    pub scheduled: Option<String>,
+    pub start: Option<String>,
+    pub created: Option<String>,
+    pub done: Option<String>,
+    pub recurrence: Option<String>,
+}
+
+const DATE_SIGNIFIERS: [char; 5] = ['📅', '⏳', '🛫', '➕', '✅'];
+const RECURRENCE_SIGNIFIER: char = '🔁';
+const VARIATION_SELECTOR: char = '\u{fe0f}';
+
+fn is_signifier(c: char) -> bool {
+    DATE_SIGNIFIERS.contains(&c)
+        || c == RECURRENCE_SIGNIFIER
+        || TaskPriority::from_signifier(c).is_some()
+}
+
+/// Returns `true` for strings shaped like `YYYY-MM-DD`
+pub fn is_iso_date(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// Extracts Obsidian Tasks emoji signifiers from the text of a task.
+pub(crate) fn parse_task_signifiers(text: &str) -> TaskSignifiers {
+    let mut signifiers = TaskSignifiers::default();
+    let mut description = String::with_capacity(text.len());
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+
+        if !is_signifier(c) {
+            description.push(c);
+            continue;
+        }
+
+        rest = rest.strip_prefix(VARIATION_SELECTOR).unwrap_or(rest);
+
+        if let Some(priority) = TaskPriority::from_signifier(c) {
+            signifiers.priority = Some(priority);
+            continue;
+        }
+
+        let value_text = rest.trim_start();
+        if c == RECURRENCE_SIGNIFIER {
+            let end = value_text.find(is_signifier).unwrap_or(value_text.len());
+            let rule = value_text[..end].trim();
+            if !rule.is_empty() {
+                signifiers.recurrence = Some(rule.to_string());
+            }
+            rest = &value_text[end..];
+            continue;
+        }
+
+        let Some(date) = value_text.get(..10).filter(|date| is_iso_date(date)) else {
+            // Not followed by a date, keep the emoji as text
+            description.push(c);
+            continue;
+        };
+        rest = &value_text[10..];
+
+        let slot = match c {
+            '📅' => &mut signifiers.due,
+            '⏳' => &mut signifiers.scheduled,
+            '🛫' => &mut signifiers.start,
+            '➕' => &mut signifiers.created,
+            _ => &mut signifiers.done,
+        };
+        *slot = Some(date.to_string());
+    }
+
+    signifiers.description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    signifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use gen_lsp_types::Position;
+
+    use super::*;
+    use crate::document::references::ReferenceKind;
+
+    #[test]
+    fn test_parse_task_signifiers() {
+        let signifiers = parse_task_signifiers(
+            "Write report ⏫ 📅 2026-10-20 ⏳2026-10-18 🔁 every week ✅ 2026-10-19",
+        );
+
+        assert_eq!(signifiers.description, "Write report");
+        assert_eq!(signifiers.priority, Some(TaskPriority::High));
+        assert_eq!(signifiers.due.as_deref(), Some("2026-10-20"));
+        assert_eq!(signifiers.scheduled.as_deref(), Some("2026-10-18"));
+        assert_eq!(signifiers.recurrence.as_deref(), Some("every week"));
+        assert_eq!(signifiers.done.as_deref(), Some("2026-10-19"));
+    }
+
+    #[test]
+    fn test_signifier_without_date_is_kept() {
+        let signifiers = parse_task_signifiers("Plan 📅 soon");
+        assert_eq!(signifiers.description, "Plan 📅 soon");
+        assert_eq!(signifiers.due, None);
+    }
+
+    #[test]
+    fn test_document_tasks() {
+        let input = "# Tasks\n\n- [ ] Write report 📅 2026-10-20 #work\n  - [/] Draft [priority:: high]\n- [-] Old idea\n- Not a task\n";
+        let document = Document::new(PathBuf::from("/tasks.md"), input, 0).unwrap();
+
+        assert_eq!(document.tasks.len(), 3);
+
+        let report = &document.tasks[0];
+        assert_eq!(report.state, TaskState::Todo);
+        assert_eq!(report.description, "Write report #work");
+        assert_eq!(report.due.as_deref(), Some("2026-10-20"));
+        assert_eq!(report.tags, vec!["work".to_string()]);
+        assert_eq!(report.checkbox_range.start, Position::new(2, 2));
+        assert_eq!(report.checkbox_range.end, Position::new(2, 5));
+
+        let draft = &document.tasks[1];
+        assert_eq!(draft.state, TaskState::InProgress);
+        assert_eq!(draft.priority, Some(TaskPriority::High));
+        assert_eq!(draft.indent, 2);
+
+        assert_eq!(document.tasks[2].state, TaskState::Cancelled);
+    }
+
+    #[test]
+    fn test_escaped_task_text() {
+        let input = "- [ ] Fix \\#12 &amp; [[C&#35; notes]]\n";
+        let document = Document::new(PathBuf::from("/tasks.md"), input, 0).unwrap();
+
+        assert_eq!(document.tasks[0].description, "Fix #12 & [[C&#35; notes]]");
+        assert!(document.tasks[0].tags.is_empty());
+        assert!(matches!(
+            &document.references[0].kind,
+            ReferenceKind::WikiLink { target, .. } if target == "C# notes"
+        ));
+    }
+
+    #[test]
+    fn test_task_hierarchy() {
+        let input = "- [ ] Parent\n  - [ ] Child\n    - Plain item\n  - [x] Sibling\n- [ ] Other\n\n  - [ ] Detached\n";
+        let document = Document::new(PathBuf::from("/tasks.md"), input, 0).unwrap();
+        let [parent, child, sibling, other, detached] = &document.tasks[..] else {
+            panic!("Expected 5 tasks, got {:?}", document.tasks);
+        };
+
+        assert_eq!(document.parent_task(child), Some(parent));
+        assert_eq!(document.parent_task(sibling), Some(parent));
+        assert_eq!(document.parent_task(other), None);
+        assert_eq!(document.parent_task(detached), None);
+        assert_eq!(
+            document.subtasks(parent).collect::<Vec<_>>(),
+            vec![child, sibling]
+        );
+
+        // A tab is as deep as four spaces
+        let input = "- [ ] Parent\n\t- [ ] Tab child\n  - [ ] Space child\n    - [ ] Grandchild\n";
+        let document = Document::new(PathBuf::from("/tasks.md"), input, 0).unwrap();
+        let [parent, tab_child, space_child, grandchild] = &document.tasks[..] else {
+            panic!("Expected 4 tasks, got {:?}", document.tasks);
+        };
+        assert_eq!(tab_child.indent, 4);
+        assert_eq!(document.parent_task(tab_child), Some(parent));
+        assert_eq!(document.parent_task(space_child), Some(parent));
+        assert_eq!(document.parent_task(grandchild), Some(space_child));
+    }
+
+    #[test]
+    fn test_date_from_unix_days() {
+        assert_eq!(date_from_unix_days(0), "1970-01-01");
+        assert_eq!(date_from_unix_days(11_016), "2000-02-29");
+        assert_eq!(date_from_unix_days(20_744), "2026-10-18");
+        assert!(is_iso_date(&today()));
+    }
+
+    #[test]
+    fn test_task_query() {
+        let input = "---\ntags:\n  - project\n---\n- [ ] Plan 📅 2026-10-20\n- [x] Kickoff 📅 2026-10-01 #meeting/weekly\n- [ ] Someday\n";
+        let document = Document::new(PathBuf::from("/tasks.md"), input, 0).unwrap();
+
+        let query = |query: TaskQuery| {
+            document
+                .tasks
+                .iter()
+                .filter(|task| query.matches(&document, task))
+                .map(|task| task.description.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            query(TaskQuery {
+                states: vec![TaskState::Todo],
+                ..Default::default()
+            }),
+            vec!["Plan", "Someday"]
+        );
+        assert_eq!(
+            query(TaskQuery {
+                due_after: Some("2026-10-10".to_string()),
+                due_before: Some("2026-10-31".to_string()),
+                ..Default::default()
+            }),
+            vec!["Plan"]
+        );
+        assert_eq!(
+            query(TaskQuery {
+                tag: Some("#meeting".to_string()),
+                ..Default::default()
+            }),
+            vec!["Kickoff #meeting/weekly"]
+        );
+        assert_eq!(
+            query(TaskQuery {
+                tag: Some("#meet".to_string()),
+                ..Default::default()
+            }),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            query(TaskQuery {
+                tag: Some("project".to_string()),
+                metadata: vec![("tags".to_string(), "other".to_string())],
+                ..Default::default()
+            }),
+            Vec::<&str>::new()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Splitting a document into windows of blocks that parse independently.
+//!
+//! The parser borrows its input as `&str`, while the content lives in the
+//! chunks of a [`RopeSlice`], about 1 KiB each. Instead of copying the whole
+//! document, it is parsed a window at a time, so only one window is ever copied
+//! into a buffer reused across windows. The blocks an edit reparses in
+//! [`incremental`](super::incremental), or a document shorter than a chunk,
+//! usually lie in a single chunk and are parsed in place. Windows end after a
+//! blank line, where a block starts no matter what precedes it, the same resync
+//! points the incremental reparse stops at.
+
+use std::ops::Range;
+
+use lib_parser::{MarkdownNode, Spanned, incremental::BlockSpan};
+use ropey::RopeSlice;
+
+/// Windows are grown to at least this many bytes, so a document is not parsed
+/// one short block at a time. This spans several chunks, so the windows of a
+/// full parse are always copied.
+pub(super) const MIN_WINDOW_LEN: usize = 4 * 1024;
+
+/// End of the first window starting at `start` that is at least `min_len` bytes
+/// long, right before the block following a blank line, or the end of `slice`
+pub(super) fn window_end(slice: RopeSlice, start: usize, min_len: usize) -> usize {
+    let len = slice.len_bytes();
+    let from = start.saturating_add(min_len);
+    if from >= len {
+        return len;
+    }
+
+    let mut previous = None;
+    let mut bytes = slice.bytes_at(from).enumerate();
+    let blank_line = bytes.find_map(|(i, byte)| {
+        let found = previous == Some(b'\n') && byte == b'\n';
+        previous = Some(byte);
+        found.then_some(from + i + 1)
+    });
+
+    blank_line.map_or(len, |end| next_block_start(slice, end))
+}
+
+/// Skips the whitespace the parser skips between blocks
+fn next_block_start(slice: RopeSlice, from: usize) -> usize {
+    let char_index = slice.byte_to_char(from);
+    let whitespace: usize = slice
+        .chars_at(char_index)
+        .take_while(|c| c.is_whitespace())
+        .map(char::len_utf8)
+        .sum();
+
+    from + whitespace
+}
+
+/// Text of `range`, borrowed from the rope if it lies in one chunk
+pub(super) fn window_input<'r>(
+    slice: RopeSlice<'r>,
+    range: Range<usize>,
+    buffer: &'r mut String,
+) -> &'r str {
+    let window = slice.byte_slice(range);
+    if let Some(input) = window.as_str() {
+        return input;
+    }
+
+    buffer.clear();
+    buffer.extend(window.chunks());
+    buffer
+}
+
+/// Whether `body`, parsed from `input` at byte `offset` of `slice`, ends the way
+/// it would in a parse of the whole document: the window was parsed to its end
+/// and its last block cannot continue past it.
+pub(super) fn ends_at_resync(
+    slice: RopeSlice,
+    input: &str,
+    offset: usize,
+    body: &[Spanned<MarkdownNode>],
+) -> bool {
+    let Some(Spanned(last, span)) = body.last() else {
+        return input.trim().is_empty();
+    };
+    if !input[span.end..].trim().is_empty() {
+        return false;
+    }
+
+    let end = offset + span.end;
+    let following = slice.byte_to_char(end);
+    let following = slice
+        .slice(following..(following + 2).min(slice.len_chars()))
+        .to_string();
+
+    BlockSpan::new(last, end..end, &following).resync
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lib_parser::{Parser, markdown_parser};
+    use ropey::Rope;
+
+    use super::*;
+    use crate::document::Document;
+
+    const SECTION: &str = "# Header\n\nParagraph [[Link]] #tag\ncontinued\n\n\
+- [ ] Task\n- Item [[Item]]\n\n[^1]: Note [[Foot]]\n\nSee [[Open\n\n";
+
+    #[test]
+    fn test_window_end() {
+        let rope = Rope::from_str("aaaa\n\n  bbbb\ncccc\n\ndddd");
+        let slice = rope.slice(..);
+
+        assert_eq!(window_end(slice, 0, 1), 8);
+        assert_eq!(window_end(slice, 8, 1), 19);
+        assert_eq!(window_end(slice, 8, 100), 23);
+    }
+
+    #[test]
+    fn test_window_input_borrows_within_a_chunk() {
+        let content = SECTION.repeat(200);
+        let rope = Rope::from_str(&content);
+        let slice = rope.slice(..);
+        let mut buffer = String::new();
+
+        let input = window_input(slice, 0..SECTION.len(), &mut buffer).to_string();
+        assert_eq!(input, SECTION);
+        assert!(buffer.is_empty());
+
+        // A full-parse window spans chunks and is copied, reusing the buffer
+        let end = window_end(slice, 0, MIN_WINDOW_LEN);
+        let next = window_end(slice, end, MIN_WINDOW_LEN);
+        let input = window_input(slice, end..next, &mut buffer).to_string();
+        assert_eq!(input, content[end..next]);
+        let allocation = buffer.as_ptr();
+
+        let input = window_input(slice, 0..end, &mut buffer).to_string();
+        assert_eq!(input, content[..end]);
+        assert_eq!(buffer.len(), end);
+        assert_eq!(buffer.as_ptr(), allocation);
+    }
+
+    #[test]
+    fn test_windows_match_whole_parse() {
+        let content = format!("---\ntitle: Windows\n---\n{}", SECTION.repeat(200));
+        assert!(content.len() > 4 * MIN_WINDOW_LEN);
+
+        let document = Document::new(PathBuf::from("/windows.md"), &content, 0).unwrap();
+        let (parsed, errors) = markdown_parser().parse(&content).into_output_errors();
+        let spans: Vec<_> = parsed
+            .unwrap()
+            .body
+            .iter()
+            .map(|block| block.1.into_range())
+            .collect();
+
+        let blocks: Vec<_> = document.blocks.iter().map(|b| b.range.clone()).collect();
+        assert_eq!(blocks, spans);
+        assert_eq!(document.parse_errors.len(), errors.len());
+        assert_eq!(document.references.len(), 200 * 5);
+        assert_eq!(document.tasks.len(), 200);
+        assert!(document.frontmatter.contains_key("title"));
+    }
+}
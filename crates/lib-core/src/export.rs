@@ -0,0 +1,343 @@
+//! Export of a vault as a static HTML site.
+//!
+//! Every note under the root is written to the same relative path in the
+//! output directory with an `.html` extension, so the relative links the
+//! [`HtmlRenderer`] produces keep pointing at the right pages.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lib_parser::{LinkType, Parser, SimpleSpan, markdown_parser_with, unescape, visit::Visitor};
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+use crate::{
+    config::LinkConfig,
+    document::Document,
+    path::{combine_and_normalize, find_relative_path, slug::header_slug},
+    render::{HtmlRenderer, encode_path, escape_html, is_external},
+    vault::Vault,
+};
+
+/// Name of the generated tag index page, at the root of the output
+pub const TAG_INDEX_PAGE: &str = "tags.html";
+
+/// What an export wrote
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub pages: usize,
+    pub images: usize,
+}
+
+/// Writes the notes of a vault below `root` as HTML pages to `out`
+pub struct SiteExporter<'v> {
+    vault: &'v Vault,
+    links: &'v LinkConfig,
+    root: &'v Path,
+    out: &'v Path,
+}
+
+impl<'v> SiteExporter<'v> {
+    pub fn new(vault: &'v Vault, links: &'v LinkConfig, root: &'v Path, out: &'v Path) -> Self {
+        Self {
+            vault,
+            links,
+            root,
+            out,
+        }
+    }
+
+    pub fn export(&self) -> Result<ExportSummary> {
+        let renderer = HtmlRenderer::new(self.vault, self.links)
+            .workspace_root(self.root)
+            .link_extension("html");
+        let backlinks = self.backlinks();
+        let mut summary = ExportSummary::default();
+
+        let mut documents: Vec<_> = self
+            .vault
+            .iter()
+            .filter(|document| document.path.starts_with(self.root))
+            .collect();
+        documents.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for document in documents {
+            let mut body = renderer.render_document(document);
+            if let Some(sources) = backlinks.get(document.path.as_path()) {
+                body.push_str(&self.backlinks_section(document, sources));
+            }
+
+            let page = self.page(document, &document_title(document), &body);
+            write_file(&self.output_path(&document.path), &page)?;
+            summary.pages += 1;
+
+            summary.images += self.copy_images(document)?;
+        }
+
+        let tag_index = self.tag_index_page();
+        write_file(&self.out.join(TAG_INDEX_PAGE), &tag_index)?;
+
+        Ok(summary)
+    }
+
+    /// Where the page of the note at `path` is written
+    fn output_path(&self, path: &Path) -> PathBuf {
+        let relative = path.strip_prefix(self.root).unwrap_or(path);
+        self.out.join(relative).with_extension("html")
+    }
+
+    /// `href` from the page of `from` to the page of `to`
+    fn page_href(&self, from: &Path, to: &Path) -> String {
+        let from = self.output_path(from);
+        let href =
+            find_relative_path(&from, to).unwrap_or_else(|_| to.to_string_lossy().into_owned());
+        encode_path(&href)
+    }
+
+    /// Notes linking to each note, by the path of the linked note
+    fn backlinks(&self) -> BTreeMap<&'v Path, BTreeSet<&'v Path>> {
+        let mut backlinks: BTreeMap<&Path, BTreeSet<&Path>> = BTreeMap::new();
+
+        for source in self.vault.iter() {
+            for reference in &source.references {
+                let Some(target) = reference.kind.get_target().filter(|t| !t.is_empty()) else {
+                    continue;
+                };
+                let Ok(resolved) =
+                    self.vault
+                        .resolve_link(target, &source.path, self.links, Some(self.root))
+                else {
+                    continue;
+                };
+                let target = self
+                    .vault
+                    .get_document(&resolved)
+                    .or_else(|| self.vault.get_document(&resolved.with_extension("md")));
+
+                if let Some(target) = target.filter(|target| target.path != source.path) {
+                    backlinks
+                        .entry(target.path.as_path())
+                        .or_default()
+                        .insert(source.path.as_path());
+                }
+            }
+        }
+
+        backlinks
+    }
+
+    fn backlinks_section(&self, document: &Document, sources: &BTreeSet<&Path>) -> String {
+        let mut html = String::from("<section class=\"backlinks\">\n<h2>Backlinks</h2>\n<ul>\n");
+        for source in sources {
+            let Some(source_document) = self.vault.get_document(source) else {
+                continue;
+            };
+            let _ = writeln!(
+                html,
+                "<li><a href=\"{}\">{}</a></li>",
+                escape_html(&self.page_href(&document.path, &self.output_path(source))),
+                escape_html(&document_title(source_document))
+            );
+        }
+        html.push_str("</ul>\n</section>\n");
+        html
+    }
+
+    fn tag_index_page(&self) -> String {
+        let index = self.vault.tag_index();
+        let index_path = self.root.join(TAG_INDEX_PAGE);
+        let mut body = String::from("<h1>Tags</h1>\n");
+
+        for tag in index.tags() {
+            let _ = writeln!(
+                body,
+                "<h2 id=\"tag-{}\">#{}</h2>\n<ul>",
+                header_slug(tag),
+                escape_html(tag)
+            );
+
+            let paths: BTreeSet<&Path> = index
+                .get(tag)
+                .iter()
+                .map(|use_| use_.path.as_path())
+                .collect();
+            for path in paths {
+                let Some(document) = self.vault.get_document(path) else {
+                    continue;
+                };
+                let _ = writeln!(
+                    body,
+                    "<li><a href=\"{}\">{}</a></li>",
+                    escape_html(&self.page_href(&index_path, &self.output_path(path))),
+                    escape_html(&document_title(document))
+                );
+            }
+            body.push_str("</ul>\n");
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Tags</title>\n\
+</head>\n<body>\n<main>\n{body}</main>\n</body>\n</html>\n"
+        )
+    }
+
+    fn page(&self, document: &Document, title: &str, body: &str) -> String {
+        let tags_href = self.page_href(&document.path, &self.out.join(TAG_INDEX_PAGE));
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+</head>\n<body>\n<nav><a href=\"{}\">Tags</a></nav>\n<main>\n{body}</main>\n</body>\n</html>\n",
+            escape_html(title),
+            escape_html(&tags_href)
+        )
+    }
+
+    /// Copies the local images `document` embeds next to its page, returns how many
+    fn copy_images(&self, document: &Document) -> Result<usize> {
+        let source = document.content.to_string();
+        let Some(parsed) = markdown_parser_with(document.parse_options)
+            .parse(&source)
+            .into_output()
+        else {
+            return Ok(0);
+        };
+
+        let mut images = Images::default();
+        images.visit_markdown(&parsed);
+
+        let mut copied = 0;
+        for uri in images.0 {
+            let Ok(image) = combine_and_normalize(&document.path, &uri) else {
+                continue;
+            };
+            let Ok(relative) = image.strip_prefix(self.root) else {
+                tracing::warn!("Not copying image outside the vault: {:?}", image);
+                continue;
+            };
+            if !image.is_file() {
+                tracing::warn!("Image not found: {:?}", image);
+                continue;
+            }
+
+            let target = self.out.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).into_diagnostic()?;
+            }
+            fs::copy(&image, &target)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Could not copy {}", image.display()))?;
+            copied += 1;
+        }
+
+        Ok(copied)
+    }
+}
+
+/// Local image paths embedded in a document
+#[derive(Default)]
+struct Images(BTreeSet<String>);
+
+impl<'a> Visitor<'a> for Images {
+    fn visit_link(&mut self, link: &LinkType<'a>, _span: SimpleSpan) {
+        if let LinkType::ImageLink { uri, .. } = link
+            && !is_external(uri)
+        {
+            self.0.insert(unescape(uri).into_owned());
+        }
+    }
+}
+
+/// The [`Document::title`] or the file name
+pub fn document_title(document: &Document) -> String {
+    document
+        .title()
+        .or_else(|| crate::path::extract_filename_stem(&document.path))
+        .unwrap_or_default()
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).into_diagnostic()?;
+    }
+
+    fs::write(path, contents)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_vault() {
+        let dir = std::env::temp_dir().join(format!("markdown-lsp-export-{}", std::process::id()));
+        let root = dir.join("vault");
+        let out = dir.join("site");
+        fs::create_dir_all(root.join("img")).unwrap();
+        fs::write(root.join("img/pic.png"), b"png").unwrap();
+
+        let mut vault = Vault::default();
+        vault
+            .create_document(
+                root.join("index.md"),
+                0,
+                "# Home\n\nSee [[Other Note#Part]] ![pic](img/pic.png) #topic\n",
+            )
+            .unwrap();
+        vault
+            .create_document(
+                root.join("notes/Other Note.md"),
+                0,
+                "---\ntitle: Other\n---\n## Part\n\nBack to [home](../index.md) #topic/sub\n",
+            )
+            .unwrap();
+
+        let links = LinkConfig::default();
+        let summary = SiteExporter::new(&vault, &links, &root, &out)
+            .export()
+            .unwrap();
+        assert_eq!(
+            summary,
+            ExportSummary {
+                pages: 2,
+                images: 1
+            }
+        );
+
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(index.contains("<title>Home</title>"), "{index}");
+        assert!(
+            index.contains("href=\"./notes/Other%20Note.html#part\""),
+            "{index}"
+        );
+        assert!(
+            index.contains("<a href=\"./notes/Other%20Note.html\">Other</a>"),
+            "{index}"
+        );
+        assert!(index.contains("<nav><a href=\"./tags.html\">Tags</a></nav>"));
+
+        let other = fs::read_to_string(out.join("notes/Other Note.html")).unwrap();
+        assert!(other.contains("href=\"../index.html\""), "{other}");
+        assert!(
+            other.contains("<a href=\"../index.html\">Home</a>"),
+            "{other}"
+        );
+
+        let tags = fs::read_to_string(out.join(TAG_INDEX_PAGE)).unwrap();
+        assert!(
+            tags.contains("<h2 id=\"tag-topic-sub\">#topic/sub</h2>"),
+            "{tags}"
+        );
+        assert!(
+            tags.contains("<li><a href=\"./notes/Other%20Note.html\">Other</a></li>"),
+            "{tags}"
+        );
+        assert_eq!(fs::read(out.join("img/pic.png")).unwrap(), b"png");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,756 @@
+//! Markdown formatting.
+//!
+//! Works on lines rather than the parsed tree, so text the parser does not know,
+//! like fenced code blocks, is kept as written. Only frontmatter is left to the
+//! parser, so `---` lines are not mistaken for it. The output is built from
+//! [`Segment`]s that each replace whole source lines, which lets a range of a
+//! document be formatted on its own.
+
+use std::ops::Range;
+
+use lib_parser::{Parser, yaml::yaml_parser};
+
+use crate::config::FormattingConfig;
+
+/// Formatted text replacing the source bytes `range`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+pub fn format_document(source: &str, config: &FormattingConfig) -> String {
+    Formatter::new(source, config)
+        .run()
+        .into_iter()
+        .map(|segment| segment.text)
+        .collect()
+}
+
+/// Formats the blocks overlapping the bytes `range`, returns the lines they
+/// cover with their formatted text
+pub fn format_range(
+    source: &str,
+    range: Range<usize>,
+    config: &FormattingConfig,
+) -> Option<Segment> {
+    let end = range.end.max(range.start + 1);
+    let mut segments = Formatter::new(source, config)
+        .run()
+        .into_iter()
+        .filter(|segment| segment.range.start < end && range.start < segment.range.end);
+
+    let mut merged = segments.next()?;
+    for segment in segments {
+        merged.range.end = segment.range.end;
+        merged.text.push_str(&segment.text);
+    }
+    Some(merged)
+}
+
+/// What the output ends with so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Last {
+    Start,
+    Frontmatter,
+    Blank,
+    Content,
+}
+
+struct Line<'s> {
+    range: Range<usize>,
+    /// Without the line break
+    text: &'s str,
+}
+
+/// A list item that later lines can be nested in
+struct ListLevel {
+    /// Indentation of the item in the source
+    indent: usize,
+    /// Column its content starts at in the source
+    content: usize,
+    /// Indentation of the formatted item
+    output: usize,
+    /// Width of the formatted marker, with the space after it
+    width: usize,
+}
+
+struct Formatter<'s, 'c> {
+    source: &'s str,
+    config: &'c FormattingConfig,
+    lines: Vec<Line<'s>>,
+    newline: &'static str,
+    segments: Vec<Segment>,
+    last: Last,
+    /// The list items each nesting level was opened with
+    list_levels: Vec<ListLevel>,
+}
+
+impl<'s, 'c> Formatter<'s, 'c> {
+    fn new(source: &'s str, config: &'c FormattingConfig) -> Self {
+        let mut start = 0;
+        let lines = source
+            .split_inclusive('\n')
+            .map(|raw| {
+                let range = start..start + raw.len();
+                start = range.end;
+                Line {
+                    range,
+                    text: raw.trim_end_matches('\n').trim_end_matches('\r'),
+                }
+            })
+            .collect();
+
+        Self {
+            source,
+            config,
+            lines,
+            newline: if source.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+            segments: Vec::new(),
+            last: Last::Start,
+            list_levels: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Vec<Segment> {
+        let mut i = 0;
+        if let Some(end) = self.frontmatter_end() {
+            self.frontmatter(end);
+            i = end + 1;
+        }
+
+        while i < self.lines.len() {
+            let text = self.lines[i].text;
+
+            let end = if text.trim().is_empty() {
+                self.blank_lines(i)
+            } else if let Some(fence) = self.item_fence_opening(text) {
+                self.fenced_block(i, fence)
+            } else if let Some(fence) = fence_opening(text) {
+                self.list_levels.clear();
+                self.fenced_block(i, fence)
+            } else if self.config.align_tables && self.is_table_start(i) {
+                self.table(i)
+            } else if is_header(text) {
+                self.header(i);
+                i + 1
+            } else if let Some(item) = list_item(text) {
+                self.list_item(i, item);
+                i + 1
+            } else {
+                self.text_line(i);
+                i + 1
+            };
+            i = end;
+        }
+
+        // Every formatted line ends with a line break, the last one only if asked to
+        if !self.config.insert_final_newline
+            && !self.source.ends_with('\n')
+            && let Some(last) = self.segments.last_mut()
+        {
+            let len = last.text.trim_end_matches(['\n', '\r']).len();
+            last.text.truncate(len);
+        }
+
+        self.segments
+    }
+
+    fn emit(&mut self, lines: Range<usize>, text: String, last: Last) {
+        let range = self.lines[lines.start].range.start..self.lines[lines.end - 1].range.end;
+        self.segments.push(Segment { range, text });
+        if last != Last::Blank || self.last != Last::Start {
+            self.last = last;
+        }
+    }
+
+    fn is_blank(&self, i: usize) -> bool {
+        self.lines
+            .get(i)
+            .is_some_and(|line| line.text.trim().is_empty())
+    }
+
+    /// Line of the closing `---`, if the parser reads the start of the
+    /// document as frontmatter
+    fn frontmatter_end(&self) -> Option<usize> {
+        let span = yaml_parser()
+            .map_with(|_, e| e.span())
+            .lazy()
+            .parse(self.source)
+            .into_output()?;
+        (1..self.lines.len())
+            .take_while(|&i| self.lines[i].range.start < span.end)
+            .filter(|&i| self.lines[i].text.trim() == "---")
+            .last()
+    }
+
+    fn frontmatter(&mut self, end: usize) {
+        // Each key owns the comments above it and its indented or list lines,
+        // blank lines stay where they are
+        let mut preamble = Vec::new();
+        let mut entries: Vec<(&str, Vec<&str>)> = Vec::new();
+        let mut pending = Vec::new();
+
+        let body = &self.lines[1..end];
+        for line in body.iter().filter(|line| !line.text.trim().is_empty()) {
+            let text = line.text;
+            let key = (!text.starts_with([' ', '\t', '-', '#']))
+                .then(|| text.split_once(':'))
+                .flatten()
+                .map(|(key, _)| key.trim());
+
+            match key {
+                Some(key) => {
+                    pending.push(text);
+                    entries.push((key, std::mem::take(&mut pending)));
+                }
+                None if text.starts_with('#') => pending.push(text),
+                None => match entries.last_mut() {
+                    Some((_, lines)) => {
+                        lines.append(&mut pending);
+                        lines.push(text);
+                    }
+                    None => preamble.push(text),
+                },
+            }
+        }
+
+        let order = &self.config.frontmatter_key_order;
+        entries.sort_by_key(|(key, _)| order.iter().position(|k| k == key).unwrap_or(order.len()));
+
+        let mut lines: Vec<&str> = preamble
+            .into_iter()
+            .chain(entries.into_iter().flat_map(|(_, lines)| lines))
+            .chain(pending)
+            .collect();
+        for (i, line) in body.iter().enumerate() {
+            if line.text.trim().is_empty() {
+                lines.insert(i, line.text);
+            }
+        }
+
+        let mut text = String::new();
+        for line in std::iter::once("---")
+            .chain(lines)
+            .chain(std::iter::once("---"))
+        {
+            text.push_str(self.trim(line));
+            text.push_str(self.newline);
+        }
+
+        self.emit(0..end + 1, text, Last::Frontmatter);
+    }
+
+    fn blank_lines(&mut self, start: usize) -> usize {
+        let end = (start..self.lines.len())
+            .find(|&i| !self.is_blank(i))
+            .unwrap_or(self.lines.len());
+
+        let count = if self.last == Last::Start {
+            0
+        } else if end == self.lines.len() {
+            if self.config.trim_final_newlines {
+                0
+            } else {
+                end - start
+            }
+        } else {
+            (end - start).min(self.config.max_blank_lines)
+        };
+        let text = self.newline.repeat(count);
+        self.emit(start..end, text, Last::Blank);
+
+        end
+    }
+
+    /// The fence of a code block that `text` opens inside the innermost list
+    /// item it is indented into, which keeps the items before it open
+    fn item_fence_opening(&mut self, text: &'s str) -> Option<&'s str> {
+        let indent = text.len() - text.trim_start_matches(' ').len();
+        let level = self
+            .list_levels
+            .iter()
+            .rposition(|level| level.content <= indent)?;
+        let fence = fence_opening(&text[self.list_levels[level].content..])?;
+
+        self.list_levels.truncate(level + 1);
+        Some(fence)
+    }
+
+    /// Code is kept as written, up to and including the closing fence
+    fn fenced_block(&mut self, start: usize, fence: &str) -> usize {
+        let end = (start + 1..self.lines.len())
+            .find(|&i| is_fence_closing(self.lines[i].text, fence))
+            .map_or(self.lines.len(), |i| i + 1);
+
+        let mut text = String::new();
+        for line in &self.lines[start..end] {
+            text.push_str(line.text);
+            text.push_str(self.newline);
+        }
+        self.emit(start..end, text, Last::Content);
+
+        end
+    }
+
+    fn header(&mut self, i: usize) {
+        self.list_levels.clear();
+        let text = self.lines[i].text.trim();
+        let level = text.chars().take_while(|c| *c == '#').count();
+        let content = text[level..].trim();
+
+        let mut formatted = String::new();
+        if self.config.blank_lines_around_headers && self.last == Last::Content {
+            formatted.push_str(self.newline);
+        }
+        formatted.push_str(&text[..level]);
+        if !content.is_empty() {
+            formatted.push(' ');
+            formatted.push_str(content);
+        }
+        formatted.push_str(self.newline);
+
+        let mut last = Last::Content;
+        if self.config.blank_lines_around_headers
+            && i + 1 < self.lines.len()
+            && !self.is_blank(i + 1)
+        {
+            formatted.push_str(self.newline);
+            last = Last::Blank;
+        }
+
+        self.emit(i..i + 1, formatted, last);
+    }
+
+    fn list_item(&mut self, i: usize, item: ListItem) {
+        // Siblings and the items nested in them are closed
+        while self
+            .list_levels
+            .last()
+            .is_some_and(|level| level.indent >= item.indent)
+        {
+            self.list_levels.pop();
+        }
+        // Nested items start past the parent's marker, `1. ` is wider than `- `
+        let output = self.list_levels.last().map_or(0, |parent| {
+            parent.output + parent.width.max(self.config.list_indent)
+        });
+        let marker = match item.marker {
+            Marker::Bullet => self.config.list_marker.as_char().to_string(),
+            Marker::Ordered(marker) => marker.to_string(),
+        };
+        self.list_levels.push(ListLevel {
+            indent: item.indent,
+            content: item.content_column,
+            output,
+            width: marker.len() + 1,
+        });
+
+        let content = self.trailing(i, item.content);
+        let text = format!("{}{marker} {content}{}", " ".repeat(output), self.newline);
+        self.emit(i..i + 1, text, Last::Content);
+    }
+
+    fn text_line(&mut self, i: usize) {
+        let line = self.lines[i].text;
+        if !line.starts_with([' ', '\t']) {
+            self.list_levels.clear();
+        }
+
+        let text = format!("{}{}", self.trailing(i, line), self.newline);
+        self.emit(i..i + 1, text, Last::Content);
+    }
+
+    /// `text` of line `i` without trailing whitespace, keeping a hard line break
+    fn trailing(&self, i: usize, text: &'s str) -> String {
+        let trimmed = self.trim(text);
+        let hard_break = text.ends_with("  ") && i + 1 < self.lines.len() && !self.is_blank(i + 1);
+
+        if hard_break && self.config.trim_trailing_whitespace {
+            format!("{trimmed}  ")
+        } else {
+            trimmed.to_string()
+        }
+    }
+
+    fn trim<'t>(&self, text: &'t str) -> &'t str {
+        if self.config.trim_trailing_whitespace {
+            text.trim_end()
+        } else {
+            text
+        }
+    }
+
+    fn is_table_start(&self, i: usize) -> bool {
+        self.lines[i].text.trim_start().starts_with('|')
+            && self
+                .lines
+                .get(i + 1)
+                .is_some_and(|line| is_delimiter_row(line.text))
+    }
+
+    fn table(&mut self, start: usize) -> usize {
+        self.list_levels.clear();
+        let end = (start + 2..self.lines.len())
+            .find(|&i| !self.lines[i].text.trim_start().starts_with('|'))
+            .unwrap_or(self.lines.len());
+
+        let indent = &self.lines[start].text
+            [..self.lines[start].text.len() - self.lines[start].text.trim_start().len()];
+        let rows: Vec<Vec<&str>> = self.lines[start..end]
+            .iter()
+            .map(|line| table_cells(line.text))
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let alignments: Vec<Alignment> = (0..columns)
+            .map(|column| Alignment::of(rows[1].get(column).copied().unwrap_or("---")))
+            .collect();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .enumerate()
+                    .filter(|(row, _)| *row != 1)
+                    .filter_map(|(_, cells)| cells.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(3)
+            })
+            .collect();
+
+        let mut text = String::new();
+        for (row, cells) in rows.iter().enumerate() {
+            let cells: Vec<String> = (0..columns)
+                .map(|column| {
+                    let width = widths[column];
+                    let alignment = alignments[column];
+                    if row == 1 {
+                        alignment.delimiter(width)
+                    } else {
+                        alignment.pad(cells.get(column).copied().unwrap_or(""), width)
+                    }
+                })
+                .collect();
+            text.push_str(&format!(
+                "{indent}| {} |{}",
+                cells.join(" | "),
+                self.newline
+            ));
+        }
+        self.emit(start..end, text, Last::Content);
+
+        end
+    }
+}
+
+/// `#` to `######` followed by whitespace or nothing, as the parser reads headers.
+/// A `#` directly followed by text is a tag.
+fn is_header(text: &str) -> bool {
+    let level = text.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&level) && text[level..].chars().next().is_none_or(char::is_whitespace)
+}
+
+/// The fence of a code block opened by `text`, e.g. "```"
+pub fn fence_opening(text: &str) -> Option<&str> {
+    let trimmed = text.trim_start_matches(' ');
+    if text.len() - trimmed.len() > 3 {
+        return None;
+    }
+
+    ['`', '~'].into_iter().find_map(|fence| {
+        let len = trimmed.chars().take_while(|c| *c == fence).count();
+        (len >= 3).then(|| &trimmed[..len])
+    })
+}
+
+/// Whether `text` closes a code block opened with `fence`
+pub fn is_fence_closing(text: &str, fence: &str) -> bool {
+    let trimmed = text.trim();
+    let fence_char = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == fence_char)
+}
+
+enum Marker<'s> {
+    Bullet,
+    /// The number and its `.` or `)`, kept as written
+    Ordered(&'s str),
+}
+
+struct ListItem<'s> {
+    marker: Marker<'s>,
+    /// Indentation in columns, tabs advance to the next multiple of four
+    indent: usize,
+    /// Column the content starts at
+    content_column: usize,
+    content: &'s str,
+}
+
+fn list_item(text: &str) -> Option<ListItem<'_>> {
+    let rest = text.trim_start_matches([' ', '\t']);
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    let marker = if digits == 0 {
+        let bullet = rest
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '-' | '*' | '+'))?;
+
+        // `- - -` and `* * *` are thematic breaks
+        let is_break = rest
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .all(|c| c == bullet)
+            && rest.chars().filter(|c| *c == bullet).count() >= 3;
+        if is_break {
+            return None;
+        }
+        Marker::Bullet
+    } else if digits <= 9 && rest[digits..].starts_with(['.', ')']) {
+        Marker::Ordered(&rest[..digits + 1])
+    } else {
+        return None;
+    };
+
+    let marker_len = match marker {
+        Marker::Bullet => 1,
+        Marker::Ordered(marker) => marker.len(),
+    };
+    let after = &rest[marker_len..];
+    if !after.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    let indent = text[..text.len() - rest.len()].chars().fold(0, |width, c| {
+        if c == '\t' {
+            width / 4 * 4 + 4
+        } else {
+            width + 1
+        }
+    });
+
+    let content = after.trim_start();
+    Some(ListItem {
+        marker,
+        indent,
+        content_column: indent + rest.len() - content.len(),
+        content,
+    })
+}
+
+fn is_delimiter_row(text: &str) -> bool {
+    let trimmed = text.trim();
+    if !trimmed.starts_with('|') {
+        return false;
+    }
+
+    let cells = table_cells(trimmed);
+    !cells.is_empty()
+        && cells.iter().all(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
+
+/// Trimmed cells of a table row, `\|` does not split cells
+fn table_cells(text: &str) -> Vec<&str> {
+    let row = text.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = match row.strip_suffix('|') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => row,
+    };
+
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in row.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '|' if !escaped => {
+                cells.push(row[start..i].trim());
+                start = i + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    cells.push(row[start..].trim());
+
+    cells
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    fn of(delimiter: &str) -> Self {
+        match (delimiter.starts_with(':'), delimiter.ends_with(':')) {
+            (true, true) => Alignment::Center,
+            (true, false) => Alignment::Left,
+            (false, true) => Alignment::Right,
+            (false, false) => Alignment::None,
+        }
+    }
+
+    fn delimiter(&self, width: usize) -> String {
+        let (left, right) = match self {
+            Alignment::None => ("", ""),
+            Alignment::Left => (":", ""),
+            Alignment::Center => (":", ":"),
+            Alignment::Right => ("", ":"),
+        };
+        format!(
+            "{left}{}{right}",
+            "-".repeat(width - left.len() - right.len())
+        )
+    }
+
+    fn pad(&self, cell: &str, width: usize) -> String {
+        let padding = width - cell.chars().count();
+        let (before, after) = match self {
+            Alignment::Right => (padding, 0),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+            Alignment::None | Alignment::Left => (0, padding),
+        };
+        format!("{}{cell}{}", " ".repeat(before), " ".repeat(after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        format_document(source, &FormattingConfig::default())
+    }
+
+    #[test]
+    fn test_format_blocks() {
+        let source = "\n\n#   Title   \nIntro with #tag \t\nhard break  \nnext line\n\n\n\n\
+* one\n    +   nested\n        - deeper\n    * back\n- [ ] task   \n\n## Next\n#tag line\n";
+
+        assert_eq!(
+            format(source),
+            "# Title\n\nIntro with #tag\nhard break  \nnext line\n\n\
+- one\n  - nested\n    - deeper\n  - back\n- [ ] task\n\n## Next\n\n#tag line\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_code_and_breaks() {
+        let source = "```rust\nlet a = 1;   \n\n\n#not a header\n```\n* * *\n";
+
+        assert_eq!(format(source), source);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let source =
+            "| Name | Done |  Notes|\n|:--|:-:|---:|\n| a | yes | long note \\| piped |\n|b|\n";
+
+        assert_eq!(
+            format(source),
+            "| Name | Done |              Notes |\n\
+| :--- | :--: | -----------------: |\n\
+| a    | yes  | long note \\| piped |\n\
+| b    |      |                    |\n"
+        );
+    }
+
+    #[test]
+    fn test_format_frontmatter_order() {
+        let config = FormattingConfig {
+            frontmatter_key_order: vec!["title".to_string(), "tags".to_string()],
+            ..Default::default()
+        };
+        let source =
+            "---\n\ndate: 2024-01-01\ntags:\n  - a   \n  - b\ntitle: Note\n\n---\n# Note\n";
+
+        assert_eq!(
+            format_document(source, &config),
+            "---\n\ntitle: Note\ntags:\n  - a\n  - b\ndate: 2024-01-01\n\n---\n# Note\n"
+        );
+    }
+
+    #[test]
+    fn test_format_frontmatter_only_when_parsed() {
+        let source = "---\n\nSome text\n\n---\n";
+        assert_eq!(format(source), source);
+
+        // Comments are not frontmatter to the parser either
+        let source = "---\ndate: 2024-01-01\n# The tags\ntitle: Note\n---\n";
+        let config = FormattingConfig {
+            frontmatter_key_order: vec!["title".to_string()],
+            ..Default::default()
+        };
+        assert!(format_document(source, &config).starts_with("---\ndate: 2024-01-01\n"));
+    }
+
+    #[test]
+    fn test_format_ordered_lists() {
+        let source = "1. first\n   - nested\n2. second\n";
+        assert_eq!(format(source), source);
+
+        let source = "1) one\n      *  deep\n   + two\n10. ten\n    - under ten   \n";
+        assert_eq!(
+            format(source),
+            "1) one\n   - deep\n   - two\n10. ten\n    - under ten\n"
+        );
+    }
+
+    #[test]
+    fn test_format_fences_in_list_items() {
+        let source = "- item\n    ```\n    - not list   \n\n\n    # not header\n    ```\n";
+        assert_eq!(format(source), source);
+
+        let source = "* item\n  ~~~\n  *  code\n  ~~~\n    *  nested\n* next\n";
+        assert_eq!(
+            format(source),
+            "- item\n  ~~~\n  *  code\n  ~~~\n  - nested\n- next\n"
+        );
+    }
+
+    #[test]
+    fn test_format_final_newlines() {
+        let source = "# Title\nText  \n\n\n";
+        assert_eq!(format(source), "# Title\n\nText\n");
+
+        let config = FormattingConfig {
+            trim_trailing_whitespace: false,
+            trim_final_newlines: false,
+            ..Default::default()
+        };
+        assert_eq!(format_document(source, &config), "# Title\n\nText  \n\n\n");
+
+        let source = "* one\n* two";
+        assert_eq!(format(source), "- one\n- two\n");
+
+        let config = FormattingConfig {
+            insert_final_newline: false,
+            ..Default::default()
+        };
+        assert_eq!(format_document(source, &config), "- one\n- two");
+    }
+
+    #[test]
+    fn test_format_range() {
+        let source = "# Title\nText   \n\n*  item\n*  other\n\nEnd   ";
+        let start = source.find("other").unwrap();
+        let segment = format_range(source, start..start + 1, &FormattingConfig::default()).unwrap();
+
+        assert_eq!(&source[segment.range.clone()], "*  other\n");
+        assert_eq!(segment.text, "- other\n");
+
+        let all = format_range(source, 0..source.len(), &FormattingConfig::default()).unwrap();
+        assert_eq!(all.range, 0..source.len());
+        assert_eq!(all.text, "# Title\n\nText\n\n- item\n- other\n\nEnd\n");
+        assert_eq!(format(&all.text), all.text);
+    }
+}
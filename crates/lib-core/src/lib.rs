@@ -1,5 +1,8 @@
 pub mod config;
 pub mod document;
+pub mod export;
+pub mod format;
 pub mod path;
+pub mod render;
 pub mod text_buffer_conversions;
 pub mod vault;
@@ -0,0 +1,543 @@
+//! HTML rendering of documents.
+//!
+//! Links are rendered the way they resolve in the vault: wikilinks become
+//! relative `href`s to the note they point to and headers become
+//! [`header_slug`] anchors, so a rendered vault links up like the editor does.
+//! Links that do not resolve, and external ones with a scheme outside of
+//! [`SAFE_SCHEMES`], are rendered as their text.
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use lib_parser::{
+    LinkType, MarkdownNode, MarkdownText, ParsedMarkdown, Parser, SimpleSpan, Spanned, TaskState,
+    markdown_parser_with, unescape,
+    visit::{Visitor, walk_block, walk_text},
+    yaml::{Frontmatter, Yaml},
+};
+
+use crate::{
+    config::LinkConfig,
+    document::Document,
+    path::{find_relative_path, slug::header_slug},
+    vault::Vault,
+};
+
+/// Renders the documents of a vault to HTML fragments
+pub struct HtmlRenderer<'v> {
+    vault: &'v Vault,
+    links: &'v LinkConfig,
+    workspace_root: Option<&'v Path>,
+    link_extension: Option<&'v str>,
+}
+
+impl<'v> HtmlRenderer<'v> {
+    pub fn new(vault: &'v Vault, links: &'v LinkConfig) -> Self {
+        Self {
+            vault,
+            links,
+            workspace_root: None,
+            link_extension: None,
+        }
+    }
+
+    /// Root that absolute link targets like `/notes/a.md` are resolved against
+    pub fn workspace_root(mut self, root: &'v Path) -> Self {
+        self.workspace_root = Some(root);
+        self
+    }
+
+    /// Links to notes use this extension instead of `.md`, e.g. `html` for
+    /// a vault rendered to a static site
+    pub fn link_extension(mut self, extension: &'v str) -> Self {
+        self.link_extension = Some(extension);
+        self
+    }
+
+    /// Renders a document with its frontmatter and footnotes
+    pub fn render_document(&self, document: &Document) -> String {
+        self.render(document, &document.content.to_string())
+    }
+
+    /// Renders `source` as if it were the content of `document`, e.g. a
+    /// section of it
+    pub fn render(&self, document: &Document, source: &str) -> String {
+        let parsed = markdown_parser_with(document.parse_options)
+            .parse(source)
+            .into_output()
+            .unwrap_or(ParsedMarkdown {
+                frontmatter: None,
+                body: Vec::new(),
+            });
+
+        let mut writer = HtmlWriter {
+            renderer: self,
+            path: &document.path,
+            source,
+            html: String::new(),
+            in_list: false,
+            footnotes: Vec::new(),
+            footnote_numbers: HashMap::new(),
+        };
+        writer.visit_markdown(&parsed);
+        writer.close_list();
+        writer.write_footnotes();
+
+        writer.html
+    }
+
+    /// Where a link to `target` written in the note at `path` points, if it
+    /// resolves at all
+    fn resolve(&self, path: &Path, target: &str) -> Option<LinkTarget> {
+        if target.is_empty() {
+            return Some(LinkTarget {
+                path: path.to_path_buf(),
+                is_note: true,
+            });
+        }
+
+        let resolved = self
+            .vault
+            .resolve_link(target, path, self.links, self.workspace_root)
+            .ok()?;
+        let is_note = self.vault.get_document(&resolved).is_some()
+            || self
+                .vault
+                .get_document(&resolved.with_extension("md"))
+                .is_some();
+        Some(LinkTarget {
+            path: resolved,
+            is_note,
+        })
+    }
+
+    /// `href` of a link from the note at `path` to `target`, with the anchor
+    /// of `header`
+    fn href(&self, path: &Path, target: &LinkTarget, header: Option<&str>) -> String {
+        let anchor = anchor(header);
+        if target.path == path {
+            return anchor;
+        }
+
+        let resolved = match (self.link_extension, target.path.extension()) {
+            (Some(extension), None) => target.path.with_extension(extension),
+            (Some(extension), Some(current)) if current == "md" => {
+                target.path.with_extension(extension)
+            }
+            _ => target.path.clone(),
+        };
+        let relative = find_relative_path(path, &resolved)
+            .unwrap_or_else(|_| resolved.to_string_lossy().into_owned());
+
+        format!("{}{anchor}", encode_path(&relative))
+    }
+}
+
+/// A resolved link target
+struct LinkTarget {
+    path: PathBuf,
+    /// Whether a note of the vault is at `path`
+    is_note: bool,
+}
+
+struct HtmlWriter<'r, 'v, 's> {
+    renderer: &'r HtmlRenderer<'v>,
+    path: &'s Path,
+    source: &'s str,
+    html: String,
+    /// Whether the last block was a list item, so the list is still open
+    in_list: bool,
+    /// Rendered footnote definitions, by id
+    footnotes: Vec<(String, String)>,
+    /// Numbers of referenced footnotes, in order of first reference
+    footnote_numbers: HashMap<String, usize>,
+}
+
+impl HtmlWriter<'_, '_, '_> {
+    fn text(&mut self, text: &str) {
+        self.html.push_str(&escape_html(&unescape(text)));
+    }
+
+    fn close_list(&mut self) {
+        if std::mem::take(&mut self.in_list) {
+            self.html.push_str("</ul>\n");
+        }
+    }
+
+    fn footnote_number(&mut self, id: &str) -> usize {
+        let next = self.footnote_numbers.len() + 1;
+        *self
+            .footnote_numbers
+            .entry(unescape(id).into_owned())
+            .or_insert(next)
+    }
+
+    fn write_footnotes(&mut self) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+
+        let mut footnotes = std::mem::take(&mut self.footnotes);
+        footnotes
+            .sort_by_key(|(id, _)| self.footnote_numbers.get(id).copied().unwrap_or(usize::MAX));
+
+        self.html.push_str("<section class=\"footnotes\">\n<ol>\n");
+        for (id, content) in footnotes {
+            let id = escape_html(&id);
+            let _ = writeln!(
+                self.html,
+                "<li id=\"fn-{id}\">{content} <a href=\"#fnref-{id}\" class=\"footnote-backref\">↩</a></li>"
+            );
+        }
+        self.html.push_str("</ol>\n</section>\n");
+    }
+}
+
+impl<'a> Visitor<'a> for HtmlWriter<'_, '_, '_> {
+    fn visit_frontmatter(&mut self, frontmatter: &Frontmatter<'a>) {
+        self.html.push_str("<dl class=\"frontmatter\">\n");
+        for (key, value) in &frontmatter.0 {
+            let _ = write!(self.html, "<dt>{}</dt>", escape_html(key));
+            let values = match value {
+                Yaml::String(value) => vec![*value],
+                Yaml::List(items) => items.clone(),
+            };
+            for value in values {
+                let _ = write!(self.html, "<dd>{}</dd>", escape_html(value));
+            }
+            self.html.push('\n');
+        }
+        self.html.push_str("</dl>\n");
+    }
+
+    fn visit_block(&mut self, block: &Spanned<MarkdownNode<'a>>) {
+        if !matches!(block.0, MarkdownNode::ListItem { .. }) {
+            self.close_list();
+        }
+        walk_block(self, block);
+    }
+
+    fn visit_header(&mut self, level: usize, content: &'a str, _span: SimpleSpan) {
+        let level = level.clamp(1, 6);
+        let content = unescape(content);
+        let _ = writeln!(
+            self.html,
+            "<h{level} id=\"{}\">{}</h{level}>",
+            header_slug(&content),
+            escape_html(&content)
+        );
+    }
+
+    fn visit_paragraph(&mut self, content: &MarkdownText<'a>, _span: SimpleSpan) {
+        self.html.push_str("<p>");
+        walk_text(self, content);
+        self.html.push_str("</p>\n");
+    }
+
+    fn visit_list_item(
+        &mut self,
+        checkbox: Option<&Spanned<TaskState>>,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        if !self.in_list {
+            self.html.push_str("<ul>\n");
+            self.in_list = true;
+        }
+
+        match checkbox {
+            Some(Spanned(state, _)) => {
+                let checked = if state.is_closed() { " checked" } else { "" };
+                // Custom markers are arbitrary characters, not class names
+                let class = match state {
+                    TaskState::Other(_) => "custom".into(),
+                    state => state.name(),
+                };
+                let _ = write!(
+                    self.html,
+                    "<li class=\"task {class}\"><input type=\"checkbox\" disabled{checked}> ",
+                );
+            }
+            None => self.html.push_str("<li>"),
+        }
+        walk_text(self, content);
+        self.html.push_str("</li>\n");
+    }
+
+    fn visit_footnote_definition(
+        &mut self,
+        id: &'a str,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        // Footnotes are collected and written at the end of the document
+        let html = std::mem::take(&mut self.html);
+        walk_text(self, content);
+        let content = std::mem::replace(&mut self.html, html);
+        self.footnotes
+            .push((unescape(id).into_owned(), content.trim().to_string()));
+    }
+
+    fn visit_invalid(&mut self, span: SimpleSpan) {
+        let text = escape_html(self.source[span.into_range()].trim_end());
+        let _ = writeln!(self.html, "<p>{text}</p>");
+    }
+
+    fn visit_plain_text(&mut self, text: &'a str, _span: SimpleSpan) {
+        self.text(text);
+    }
+
+    fn visit_link(&mut self, link: &LinkType<'a>, _span: SimpleSpan) {
+        let renderer = self.renderer;
+        match link {
+            LinkType::WikiLink {
+                target,
+                display_text,
+                header,
+            } => {
+                let target = unescape(target);
+                let header = header.map(unescape);
+                let text = match (display_text, &header) {
+                    (Some(alias), _) => unescape(alias).into_owned(),
+                    (None, Some(header)) => format!("{target} > {header}"),
+                    (None, None) => target.to_string(),
+                };
+                let text = escape_html(&text);
+
+                match renderer
+                    .resolve(self.path, &target)
+                    .filter(|resolved| resolved.is_note)
+                {
+                    Some(resolved) => {
+                        let href = renderer.href(self.path, &resolved, header.as_deref());
+                        let _ = write!(
+                            self.html,
+                            "<a href=\"{}\" class=\"wikilink\">{text}</a>",
+                            escape_html(&href)
+                        );
+                    }
+                    None => {
+                        let _ = write!(
+                            self.html,
+                            "<span class=\"wikilink unresolved\">{text}</span>"
+                        );
+                    }
+                }
+            }
+            LinkType::InlineLink { text, uri, header } => {
+                let uri = unescape(uri);
+                let header = header.map(unescape);
+                let href = if is_external(&uri) {
+                    is_safe_scheme(&uri).then(|| format!("{uri}{}", anchor(header.as_deref())))
+                } else {
+                    renderer
+                        .resolve(self.path, &uri)
+                        .map(|resolved| renderer.href(self.path, &resolved, header.as_deref()))
+                };
+
+                match href {
+                    Some(href) => {
+                        let _ = write!(self.html, "<a href=\"{}\">", escape_html(&href));
+                        self.text(text);
+                        self.html.push_str("</a>");
+                    }
+                    // Other schemes, like `javascript:`, are not followed
+                    None if is_external(&uri) => self.text(text),
+                    None => {
+                        self.html.push_str("<span class=\"unresolved\">");
+                        self.text(text);
+                        self.html.push_str("</span>");
+                    }
+                }
+            }
+            LinkType::ImageLink { text, uri } => {
+                let _ = write!(
+                    self.html,
+                    "<img src=\"{}\" alt=\"{}\">",
+                    escape_html(&unescape(uri)),
+                    escape_html(&unescape(text))
+                );
+            }
+        }
+    }
+
+    fn visit_tag(&mut self, name: &'a str, _span: SimpleSpan) {
+        let _ = write!(
+            self.html,
+            "<span class=\"tag\">#{}</span>",
+            escape_html(&unescape(name))
+        );
+    }
+
+    fn visit_footnote(&mut self, id: &'a str, _span: SimpleSpan) {
+        let number = self.footnote_number(id);
+        let id = escape_html(&unescape(id));
+        let _ = write!(
+            self.html,
+            "<sup id=\"fnref-{id}\"><a href=\"#fn-{id}\" class=\"footnote-ref\">{number}</a></sup>"
+        );
+    }
+
+    fn visit_inline_field(
+        &mut self,
+        key: &'a str,
+        _value: &'a str,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        let _ = write!(
+            self.html,
+            "<span class=\"field\"><span class=\"field-key\">{}</span>: ",
+            escape_html(key)
+        );
+        walk_text(self, content);
+        self.html.push_str("</span>");
+    }
+}
+
+/// Whether a link destination points outside the vault, e.g. `https://…` or `mailto:…`
+pub fn is_external(uri: &str) -> bool {
+    uri.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Schemes of external links that are rendered as links
+const SAFE_SCHEMES: [&str; 5] = ["http", "https", "mailto", "ftp", "tel"];
+
+fn is_safe_scheme(uri: &str) -> bool {
+    uri.split_once(':').is_some_and(|(scheme, _)| {
+        SAFE_SCHEMES
+            .iter()
+            .any(|safe| scheme.eq_ignore_ascii_case(safe))
+    })
+}
+
+/// `#` and the [`header_slug`] of `header`, or nothing without one
+fn anchor(header: Option<&str>) -> String {
+    header.map_or_else(String::new, |header| format!("#{}", header_slug(header)))
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encodes a relative path for use in an `href`
+pub(crate) fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_alphanumeric() || matches!(c, '/' | '.' | '-' | '_' | '~') {
+            encoded.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> Vault {
+        let mut vault = Vault::default();
+        vault
+            .create_document(
+                PathBuf::from("/vault/index.md"),
+                0,
+                "---\ntitle: Home\ntags:\n  - a\n  - b\n---\n# Start & Go\n\n\
+See [[My Note#Part Two|the note]] and [[Missing]].\n\n\
+- [x] Done [[index#Start & Go]]\n- Plain [doc](sub/doc.md#Intro) [web](https://x.test)\n\n\
+Note[^n] #tag\n\n[^n]: Footnote *text*\n",
+            )
+            .unwrap();
+        vault
+            .create_document(PathBuf::from("/vault/deep/My Note.md"), 0, "# Part Two")
+            .unwrap();
+        vault
+    }
+
+    #[test]
+    fn test_render_document() {
+        let vault = vault();
+        let links = LinkConfig::default();
+        let document = vault.get_document(Path::new("/vault/index.md")).unwrap();
+        let html = HtmlRenderer::new(&vault, &links).render_document(document);
+
+        let expected = "<dl class=\"frontmatter\">\n<dt>title</dt><dd>Home</dd>\n\
+<dt>tags</dt><dd>a</dd><dd>b</dd>\n</dl>\n\
+<h1 id=\"start-go\">Start &amp; Go</h1>\n\
+<p>See <a href=\"./deep/My%20Note.md#part-two\" class=\"wikilink\">the note</a> and \
+<span class=\"wikilink unresolved\">Missing</span>.</p>\n\
+<ul>\n<li class=\"task done\"><input type=\"checkbox\" disabled checked> Done \
+<a href=\"#start-go\" class=\"wikilink\">index &gt; Start &amp; Go</a></li>\n";
+        assert!(html.starts_with(expected), "{html}");
+        assert!(html.contains(
+            "<li>Plain <a href=\"./sub/doc.md#intro\">doc</a> <a href=\"https://x.test\">web</a></li>\n</ul>\n"
+        ));
+        assert!(html.contains(
+            "<p>Note<sup id=\"fnref-n\"><a href=\"#fn-n\" class=\"footnote-ref\">1</a></sup> <span class=\"tag\">#tag</span></p>"
+        ));
+        assert!(html.ends_with(
+            "<section class=\"footnotes\">\n<ol>\n<li id=\"fn-n\">Footnote *text* \
+<a href=\"#fnref-n\" class=\"footnote-backref\">↩</a></li>\n</ol>\n</section>\n"
+        ));
+    }
+
+    #[test]
+    fn test_link_extension() {
+        let vault = vault();
+        let links = LinkConfig::default();
+        let document = vault.get_document(Path::new("/vault/index.md")).unwrap();
+        let html = HtmlRenderer::new(&vault, &links)
+            .link_extension("html")
+            .render_document(document);
+
+        assert!(html.contains("href=\"./deep/My%20Note.html#part-two\""));
+        assert!(html.contains("<span class=\"wikilink unresolved\">Missing</span>"));
+        assert!(html.contains("href=\"./sub/doc.html#intro\""));
+    }
+
+    #[test]
+    fn test_unsafe_and_unresolved_links() {
+        let mut vault = vault();
+        vault
+            .create_document(
+                PathBuf::from("/vault/links.md"),
+                0,
+                "[x](javascript:alert&#40;1&#41;) [y](JavaScript:alert%281%29) [mail](mailto:a@b.test)\n\n\
+[web](https://x.test/page#Some_Part) [abs](/notes/a.md) [[My Note#Part Two]]\n",
+            )
+            .unwrap();
+        let links = LinkConfig::default();
+        let document = vault.get_document(Path::new("/vault/links.md")).unwrap();
+        let html = HtmlRenderer::new(&vault, &links).render_document(document);
+
+        assert_eq!(
+            html,
+            "<p>x y <a href=\"mailto:a@b.test\">mail</a></p>\n\
+<p><a href=\"https://x.test/page#some-part\">web</a> <span class=\"unresolved\">abs</span> \
+<a href=\"./deep/My%20Note.md#part-two\" class=\"wikilink\">My Note &gt; Part Two</a>\n</p>\n"
+        );
+    }
+}
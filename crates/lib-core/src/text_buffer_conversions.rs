@@ -3,6 +3,23 @@ use std::ops::Range;
 use gen_lsp_types::{Position, Range as LspRange};
 use ropey::RopeSlice;
 
+/// Checks whether `position` lies inside `range`, treating the end as exclusive.
+pub fn range_contains_position(range: &LspRange, position: Position) -> bool {
+    if position.line < range.start.line || position.line > range.end.line {
+        return false;
+    }
+
+    if position.line == range.start.line && position.character < range.start.character {
+        return false;
+    }
+
+    if position.line == range.end.line && position.character >= range.end.character {
+        return false;
+    }
+
+    true
+}
+
 pub trait TextBufferConversions {
     /// Safely converts a byte offset to a Position. Returns None if out of bounds.
     fn try_byte_offset_to_position(&self, byte_offset: usize) -> Option<Position>;
@@ -41,6 +58,10 @@ pub trait TextBufferConversions {
 
     /// Returns the total length of the buffer in bytes.
     fn byte_len(&self) -> usize;
+
+    /// Converts a position counting UTF-16 code units, the LSP default, to one
+    /// counting chars. Columns past the end of the line stay past it.
+    fn utf16_to_char_position(&self, position: Position) -> Position;
 }
 
 impl TextBufferConversions for RopeSlice<'_> {
@@ -85,4 +106,19 @@ impl TextBufferConversions for RopeSlice<'_> {
     fn byte_len(&self) -> usize {
         self.len_bytes()
     }
+
+    fn utf16_to_char_position(&self, position: Position) -> Position {
+        let Some(line) = self.get_line(position.line as usize) else {
+            return position;
+        };
+
+        let character = position.character as usize;
+        let length = line.len_utf16_cu();
+        let chars = if character <= length {
+            line.utf16_cu_to_char(character)
+        } else {
+            line.len_chars() + character - length
+        };
+        Position::new(position.line, chars as u32)
+    }
 }
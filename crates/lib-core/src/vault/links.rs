@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use miette::{Result, miette};
+
+use crate::{
+    config::LinkConfig,
+    path::{combine_and_normalize, extract_filename_stem, slug::filename_slug},
+    vault::Vault,
+};
+
+impl Vault {
+    /// Resolves a link target written in the note at `source` to the path it points to.
+    ///
+    /// Path-like targets are resolved against the note, or against `workspace_root`
+    /// when absolute. Bare names like `[[note]]` match a note of that file name
+    /// anywhere in the vault, if filename resolution is enabled.
+    pub fn resolve_link(
+        &self,
+        target: &str,
+        source: &Path,
+        config: &LinkConfig,
+        workspace_root: Option<&Path>,
+    ) -> Result<PathBuf> {
+        if !is_path_syntax(target)
+            && config.enable_filename_resolution
+            && let Some(resolved) = self.find_by_filename(target)
+        {
+            return Ok(resolved.to_path_buf());
+        }
+
+        resolve_as_path(target, source, workspace_root)
+    }
+
+    /// Finds the note a bare link target names.
+    ///
+    /// Always strips the `.md` extension from the target, so both `[[note]]`
+    /// and `[[note.md]]` match `note.md`.
+    fn find_by_filename(&self, target: &str) -> Option<&Path> {
+        let target_stem = target.strip_suffix(".md").unwrap_or(target);
+
+        let normalized_target = filename_slug(target_stem);
+        self.iter().find_map(|doc| {
+            let doc_filename = extract_filename_stem(&doc.path)?;
+
+            (filename_slug(&doc_filename) == normalized_target).then_some(doc.path.as_path())
+        })
+    }
+}
+
+/// Check if target uses path syntax
+pub fn is_path_syntax(target: &str) -> bool {
+    target.starts_with('/') ||      // Absolute: /docs/note.md
+    target.starts_with("./") ||     // Relative: ./note.md
+    target.starts_with("../") ||    // Relative parent: ../note.md
+    target.contains('/') || // Has path separator: folder/note.md
+    target.contains('\\') // Windows Specific
+}
+
+fn resolve_as_path(target: &str, source: &Path, workspace_root: Option<&Path>) -> Result<PathBuf> {
+    if let Some(absolute) = target.strip_prefix('/') {
+        // Absolute path - resolve relative to workspace root
+        let Some(root) = workspace_root else {
+            return Err(miette!(
+                "No workspace root available for absolute path: {}",
+                target
+            ));
+        };
+
+        Ok(root.join(absolute))
+    } else {
+        combine_and_normalize(source, target).map_err(|e| miette!("{e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_path_syntax() {
+        assert!(is_path_syntax("/absolute/path.md"));
+        assert!(is_path_syntax("./relative.md"));
+        assert!(is_path_syntax("../parent.md"));
+        assert!(is_path_syntax("folder/file.md"));
+        assert!(!is_path_syntax("note"));
+        assert!(!is_path_syntax("my-note"));
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let mut vault = Vault::default();
+        vault
+            .create_document(PathBuf::from("/vault/deep/My Note.md"), 0, "# Note")
+            .unwrap();
+        let config = LinkConfig::default();
+        let source = Path::new("/vault/index.md");
+        let resolve = |target| {
+            vault
+                .resolve_link(target, source, &config, Some(Path::new("/vault")))
+                .unwrap()
+        };
+
+        assert_eq!(resolve("my note"), PathBuf::from("/vault/deep/My Note.md"));
+        assert_eq!(
+            resolve("My_Note.md"),
+            PathBuf::from("/vault/deep/My Note.md")
+        );
+        assert_eq!(resolve("./other.md"), PathBuf::from("/vault/other.md"));
+        assert_eq!(resolve("/deep/x.md"), PathBuf::from("/vault/deep/x.md"));
+        assert_eq!(resolve("missing"), PathBuf::from("/vault/missing"));
+    }
+}
@@ -1,39 +1,105 @@
-pub mod helpers;
+pub mod links;
+pub mod tags;
 
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
 };
 
+use gen_lsp_types::Range;
+use lib_parser::ParseOptions;
 use miette::Result;
 
-use crate::document::{Document, references::Reference};
+use crate::{
+    document::{Document, references::Reference, tasks::Task},
+    vault::tags::TagIndex,
+};
 
 #[derive(Default)]
 pub struct Vault {
     documents: HashMap<PathBuf, Document>,
+    parse_options: ParseOptions,
+    tags: TagIndex,
 }
 
 impl Vault {
     pub fn create_document(&mut self, path: PathBuf, version: i32, text: &str) -> Result<()> {
-        let document = Document::new(path.clone(), text, version)?;
+        let document = Document::with_options(path.clone(), text, version, self.parse_options)?;
+        self.tags.update_document(&document);
         self.documents.insert(path, document);
 
         Ok(())
     }
 
+    /// Sets the grammar options for new documents and reparses the existing ones
+    pub fn set_parse_options(&mut self, parse_options: ParseOptions) -> Result<()> {
+        self.parse_options = parse_options;
+        for document in self.documents.values_mut() {
+            document.set_parse_options(parse_options)?;
+            self.tags.update_document(document);
+        }
+
+        Ok(())
+    }
+
+    /// Indexes every Markdown file under `root`, returns how many were added
+    pub fn index_directory(&mut self, root: &Path) -> Result<usize> {
+        let markdown_files = walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let path = entry.path();
+                path.is_file() && path.extension().is_some_and(|ext| ext == "md")
+            });
+
+        let mut indexed = 0;
+        for entry in markdown_files {
+            let entry_path = entry.path();
+            let contents = match std::fs::read_to_string(entry_path) {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::debug!("Could not read file {:?}: {}", entry_path, e);
+                    continue;
+                }
+            };
+
+            self.create_document(entry_path.to_path_buf(), 0, &contents)?;
+            indexed += 1;
+        }
+
+        Ok(indexed)
+    }
+
     pub fn update_document(&mut self, path: &Path, version: i32, text: &str) -> Result<()> {
-        if let Some(document) = self.get_document_mut(path) {
+        if let Some(document) = self.documents.get_mut(path) {
             document.update(text, version)?;
+            self.tags.update_document(document);
+        }
+
+        Ok(())
+    }
+
+    /// Applies an incremental change to a document, see [`Document::edit`]
+    pub fn edit_document(
+        &mut self,
+        path: &Path,
+        version: i32,
+        range: Range,
+        text: &str,
+    ) -> Result<()> {
+        if let Some(document) = self.documents.get_mut(path) {
+            document.edit(range, text, version)?;
+            self.tags.update_document(document);
         }
 
         Ok(())
     }
 
     pub fn open_document(&mut self, path: &Path, version: i32, content: &str) -> Result<()> {
-        if let Some(doc) = self.get_document_mut(path) {
+        if let Some(doc) = self.documents.get_mut(path) {
             doc.is_open = true;
             doc.update(content, version)?;
+            self.tags.update_document(doc);
         }
 
         Ok(())
@@ -47,12 +113,15 @@ impl Vault {
 
     pub fn remove_document(&mut self, path: &Path) {
         self.documents.remove(path);
+        self.tags.remove_document(path);
     }
 
     pub fn get_document(&self, path: &Path) -> Option<&Document> {
         self.documents.get(path)
     }
 
+    /// Changes made through this are not seen by [`Vault::tag_index`], prefer
+    /// the methods above to edit a document
     pub fn get_document_mut(&mut self, path: &Path) -> Option<&mut Document> {
         self.documents.get_mut(path)
     }
@@ -65,6 +134,17 @@ impl Vault {
         self.iter().flat_map(|doc| doc.references.iter())
     }
 
+    /// Index of every tag used in the vault, kept up to date as documents change
+    pub fn tag_index(&self) -> &TagIndex {
+        &self.tags
+    }
+
+    /// Every indexed task along with the document it belongs to
+    pub fn tasks(&self) -> impl Iterator<Item = (&Document, &Task)> {
+        self.iter()
+            .flat_map(|doc| doc.tasks.iter().map(move |task| (doc, task)))
+    }
+
     pub fn get_references_with_path(&self) -> impl Iterator<Item = (&PathBuf, &Reference)> {
         self.documents.iter().flat_map(|(path, doc)| {
             doc.references
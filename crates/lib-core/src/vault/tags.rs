@@ -0,0 +1,350 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use gen_lsp_types::Range;
+
+use crate::document::{Document, references::ReferenceKind};
+
+pub use lib_parser::markdown::is_tag_char;
+
+/// Where a tag was used
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagOccurrence {
+    pub path: PathBuf,
+    pub range: Range,
+}
+
+#[derive(Debug, Default)]
+struct TagEntry {
+    /// Uses of exactly this tag
+    occurrences: Vec<TagOccurrence>,
+    /// Uses of this tag and all of its descendants
+    count: usize,
+    /// Full paths of the tags directly below this one
+    children: BTreeSet<String>,
+}
+
+/// Tags used across the vault, ordered by their lowercased path so that the
+/// descendants of a tag (`project/alpha`, `project/beta`, ...) are adjacent.
+///
+/// Built from inline `#tags` and the `tags` frontmatter field, and kept up to
+/// date one document at a time by the [`Vault`](crate::vault::Vault).
+/// Intermediate levels get an entry even if only `a/b/c` is ever used.
+#[derive(Debug, Default)]
+pub struct TagIndex {
+    tags: BTreeMap<String, TagEntry>,
+    roots: BTreeSet<String>,
+    /// Tags each document contributed, to drop them when it changes
+    documents: HashMap<PathBuf, Vec<String>>,
+}
+
+impl TagIndex {
+    /// Replaces the tags of `document` with the ones it uses now
+    pub fn update_document(&mut self, document: &Document) {
+        self.remove_document(&document.path);
+
+        let mut tags = Vec::new();
+        for reference in &document.references {
+            if let ReferenceKind::Tag { name } = &reference.kind {
+                tags.extend(self.insert(name, &document.path, reference.range));
+            }
+        }
+
+        for field in document.metadata.get_all("tags") {
+            for tag in field.value.to_string_list() {
+                tags.extend(self.insert(&tag, &document.path, field.range));
+            }
+        }
+
+        if !tags.is_empty() {
+            self.documents.insert(document.path.clone(), tags);
+        }
+    }
+
+    /// Drops every tag use in the document at `path`
+    pub fn remove_document(&mut self, path: &Path) {
+        for tag in self.documents.remove(path).unwrap_or_default() {
+            let Some(entry) = self.tags.get_mut(&tag) else {
+                continue;
+            };
+            let before = entry.occurrences.len();
+            entry
+                .occurrences
+                .retain(|occurrence| occurrence.path != path);
+            let removed = before - entry.occurrences.len();
+            if removed > 0 {
+                self.remove_uses(&tag, removed);
+            }
+        }
+    }
+
+    /// Adds a use of `tag`, returns its normalized path unless it is empty
+    fn insert(&mut self, tag: &str, path: &Path, range: Range) -> Option<String> {
+        let tag = normalize_tag(tag);
+        if tag.is_empty() {
+            return None;
+        }
+
+        self.add_uses(&tag, 1);
+        self.tags
+            .entry(tag.clone())
+            .or_default()
+            .occurrences
+            .push(TagOccurrence {
+                path: path.to_path_buf(),
+                range,
+            });
+        Some(tag)
+    }
+
+    /// Counts `amount` more uses on `tag` and each of its ancestors, linking
+    /// new levels into the tree
+    fn add_uses(&mut self, tag: &str, amount: usize) {
+        let levels = tag_levels(tag);
+        for (i, level) in levels.iter().enumerate() {
+            self.tags.entry(level.to_string()).or_default().count += amount;
+
+            let siblings = match i.checked_sub(1) {
+                Some(parent) => {
+                    &mut self
+                        .tags
+                        .entry(levels[parent].to_string())
+                        .or_default()
+                        .children
+                }
+                None => &mut self.roots,
+            };
+            siblings.insert(level.to_string());
+        }
+    }
+
+    /// Counts `amount` fewer uses on `tag` and each of its ancestors, pruning
+    /// the levels left unused
+    fn remove_uses(&mut self, tag: &str, amount: usize) {
+        let levels = tag_levels(tag);
+        // Deepest first, so each parent is still there to unlink from
+        for (i, level) in levels.iter().enumerate().rev() {
+            let Some(entry) = self.tags.get_mut(*level) else {
+                continue;
+            };
+            entry.count = entry.count.saturating_sub(amount);
+            if entry.count > 0 {
+                continue;
+            }
+
+            self.tags.remove(*level);
+            match i.checked_sub(1) {
+                Some(parent) => {
+                    if let Some(parent) = self.tags.get_mut(levels[parent]) {
+                        parent.children.remove(*level);
+                    }
+                }
+                None => {
+                    self.roots.remove(*level);
+                }
+            }
+        }
+    }
+
+    /// Every used tag in the index, in hierarchical order
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|(_, entry)| !entry.occurrences.is_empty())
+            .map(|(tag, _)| tag.as_str())
+    }
+
+    /// Occurrences of exactly `tag`
+    pub fn get(&self, tag: &str) -> &[TagOccurrence] {
+        self.tags
+            .get(&normalize_tag(tag))
+            .map_or(&[], |entry| entry.occurrences.as_slice())
+    }
+
+    /// Used tags starting with `prefix` as plain text, e.g. `proj` matches `project/alpha`
+    pub fn with_prefix<'s>(
+        &'s self,
+        prefix: &str,
+    ) -> impl Iterator<Item = (&'s str, &'s [TagOccurrence])> + use<'s> {
+        let prefix = normalize_tag(prefix);
+
+        self.tags
+            .range(prefix.clone()..)
+            .take_while(move |(tag, _)| tag.starts_with(&prefix))
+            .filter(|(_, entry)| !entry.occurrences.is_empty())
+            .map(|(tag, entry)| (tag.as_str(), entry.occurrences.as_slice()))
+    }
+
+    /// `tag` itself and every used tag nested under it
+    pub fn descendants<'s>(
+        &'s self,
+        tag: &str,
+    ) -> impl Iterator<Item = (&'s str, &'s [TagOccurrence])> + use<'s> {
+        let tag = normalize_tag(tag);
+
+        self.with_prefix(&tag)
+            .filter(move |(candidate, _)| is_descendant_tag(candidate, &tag))
+    }
+
+    /// Full paths of the tags directly below `parent`, or of the top level tags.
+    ///
+    /// Intermediate levels count as tags even if only `a/b/c` is ever used.
+    pub fn children(&self, parent: Option<&str>) -> Vec<String> {
+        let children = match parent
+            .map(normalize_tag)
+            .filter(|parent| !parent.is_empty())
+        {
+            Some(parent) => self.tags.get(&parent).map(|entry| &entry.children),
+            None => Some(&self.roots),
+        };
+
+        children.into_iter().flatten().cloned().collect()
+    }
+
+    /// Number of uses of `tag` and all of its descendants
+    pub fn count(&self, tag: &str) -> usize {
+        self.tags
+            .get(&normalize_tag(tag))
+            .map_or(0, |entry| entry.count)
+    }
+}
+
+/// `a`, `a/b` and `a/b/c` for `a/b/c`
+fn tag_levels(tag: &str) -> Vec<&str> {
+    tag.match_indices('/')
+        .map(|(i, _)| &tag[..i])
+        .chain([tag])
+        .collect()
+}
+
+/// Lowercases a tag and strips the leading `#` and surrounding slashes
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .trim_start_matches('#')
+        .trim_matches('/')
+        .to_lowercase()
+}
+
+/// Whether `tag` is `ancestor` or nested under it, ignoring case
+pub fn is_descendant_tag(tag: &str, ancestor: &str) -> bool {
+    let (tag, ancestor) = (normalize_tag(tag), normalize_tag(ancestor));
+
+    tag == ancestor
+        || tag
+            .strip_prefix(&ancestor)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::vault::Vault;
+
+    fn vault() -> Vault {
+        let mut vault = Vault::default();
+        vault
+            .create_document(
+                PathBuf::from("/a.md"),
+                0,
+                "#project/alpha/design and #project/beta\n\n#projects #Project",
+            )
+            .unwrap();
+        vault
+            .create_document(
+                PathBuf::from("/b.md"),
+                0,
+                "---\ntags:\n  - project/alpha\n  - area\n---\n#area/home",
+            )
+            .unwrap();
+        vault
+    }
+
+    #[test]
+    fn test_tag_hierarchy() {
+        let vault = vault();
+        let index = vault.tag_index();
+
+        assert_eq!(
+            index.tags().collect::<Vec<_>>(),
+            vec![
+                "area",
+                "area/home",
+                "project",
+                "project/alpha",
+                "project/alpha/design",
+                "project/beta",
+                "projects"
+            ]
+        );
+        assert_eq!(index.children(None), vec!["area", "project", "projects"]);
+        assert_eq!(
+            index.children(Some("#Project")),
+            vec!["project/alpha", "project/beta"]
+        );
+        assert_eq!(index.children(Some("project/beta")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_tag_queries() {
+        let vault = vault();
+        let index = vault.tag_index();
+
+        assert_eq!(index.get("project").len(), 1);
+        assert_eq!(index.count("project"), 4);
+        assert_eq!(
+            index.descendants("project").count(),
+            4,
+            "`projects` is not nested under `project`"
+        );
+        assert_eq!(index.with_prefix("proj").count(), 5);
+        assert!(is_descendant_tag("Project/Alpha", "#project"));
+        assert!(!is_descendant_tag("projects", "project"));
+    }
+
+    #[test]
+    fn test_numeric_tags_follow_parse_options() {
+        let mut vault = Vault::default();
+        vault
+            .create_document(PathBuf::from("/a.md"), 0, "Fixes #123 in #v2")
+            .unwrap();
+        assert_eq!(vault.tag_index().tags().collect::<Vec<_>>(), vec!["v2"]);
+
+        vault
+            .set_parse_options(lib_parser::ParseOptions { numeric_tags: true })
+            .unwrap();
+        assert_eq!(
+            vault.tag_index().tags().collect::<Vec<_>>(),
+            vec!["123", "v2"]
+        );
+    }
+
+    #[test]
+    fn test_index_follows_document_changes() {
+        let mut vault = vault();
+        let path = PathBuf::from("/a.md");
+
+        vault.update_document(&path, 1, "#project/gamma").unwrap();
+        assert_eq!(vault.tag_index().count("project"), 2);
+        assert_eq!(
+            vault.tag_index().children(Some("project")),
+            vec!["project/alpha", "project/gamma"]
+        );
+        assert_eq!(vault.tag_index().children(None), vec!["area", "project"]);
+        assert!(vault.tag_index().get("projects").is_empty());
+
+        vault.remove_document(&path);
+        assert_eq!(
+            vault.tag_index().tags().collect::<Vec<_>>(),
+            vec!["area", "area/home", "project/alpha"]
+        );
+        assert_eq!(vault.tag_index().count("project"), 1);
+        assert_eq!(
+            vault.tag_index().children(Some("project/alpha")),
+            Vec::<String>::new()
+        );
+    }
+}
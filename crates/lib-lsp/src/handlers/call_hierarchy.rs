@@ -0,0 +1,309 @@
+use gen_lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+    Position, Range, SymbolKind, Uri,
+};
+use lib_core::{
+    document::{
+        Document,
+        references::{Reference, ReferenceKind},
+    },
+    render::is_external,
+    text_buffer_conversions::TextBufferConversions,
+};
+use miette::{Context, IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    get_document,
+    handlers::{
+        document_symbol::header_sections, goto_definition::find_header,
+        link_resolver::resolve_linked_note, references::reference_locations,
+    },
+    server_state::ServerState,
+    uri::UriExt,
+};
+
+/// Which part of a note an item stands for, kept in [`CallHierarchyItem::data`]
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemData {
+    /// Start of the header, the whole note without one
+    header: Option<Position>,
+}
+
+/// The header at the position, or the note itself
+pub fn process_prepare_call_hierarchy(
+    lsp: &mut ServerState,
+    params: CallHierarchyPrepareParams,
+) -> Result<Option<Vec<CallHierarchyItem>>> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+    let document = get_document!(lsp, &uri);
+
+    let item = match document.get_reference_at_position(position) {
+        Some(
+            header @ Reference {
+                kind: ReferenceKind::Header { .. },
+                ..
+            },
+        ) => header_item(document, header),
+        _ => note_item(document),
+    };
+
+    Ok(item.map(|item| vec![item]))
+}
+
+/// Notes linking to the item, with the ranges of their links
+pub fn process_incoming_calls(
+    lsp: &mut ServerState,
+    params: CallHierarchyIncomingCallsParams,
+) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+    let (document, header) = item_target(lsp, &params.item)?;
+
+    let mut calls: Vec<CallHierarchyIncomingCall> = Vec::new();
+    for location in reference_locations(lsp, document, &params.item.uri, header) {
+        if let Some(call) = calls.iter_mut().find(|call| call.from.uri == location.uri) {
+            call.from_ranges.push(location.range);
+            continue;
+        }
+
+        let Some(from) = location
+            .uri
+            .to_file_path()
+            .and_then(|path| lsp.documents.get_document(&path))
+            .and_then(note_item)
+        else {
+            continue;
+        };
+        calls.push(CallHierarchyIncomingCall::new(from, vec![location.range]));
+    }
+    calls.sort_by(|a, b| a.from.uri.as_str().cmp(b.from.uri.as_str()));
+
+    Ok(Some(calls))
+}
+
+/// Notes and headers the item links to, with the ranges of the links
+pub fn process_outgoing_calls(
+    lsp: &mut ServerState,
+    params: CallHierarchyOutgoingCallsParams,
+) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+    let (document, header) = item_target(lsp, &params.item)?;
+    let scope = match header {
+        Some(header) => section_range(document, header),
+        None => params.item.range,
+    };
+
+    let mut calls: Vec<CallHierarchyOutgoingCall> = Vec::new();
+    for reference in &document.references {
+        let Some(target) = reference.kind.get_target() else {
+            continue;
+        };
+        let start = reference.range.start;
+        if start < scope.start || scope.end < start || is_external(target) {
+            continue;
+        }
+
+        let note = if target.is_empty() {
+            document
+        } else {
+            match resolve_linked_note(lsp, document, target) {
+                Ok((_, Some(note))) => note,
+                _ => continue,
+            }
+        };
+        let to = match reference
+            .kind
+            .get_link_header()
+            .and_then(|header| find_header(note, header))
+        {
+            Some(header) => header_item(note, header),
+            None => note_item(note),
+        };
+        let Some(to) = to else {
+            continue;
+        };
+
+        match calls
+            .iter_mut()
+            .find(|call| call.to.uri == to.uri && call.to.data == to.data)
+        {
+            Some(call) => call.from_ranges.push(reference.range),
+            None => calls.push(CallHierarchyOutgoingCall::new(to, vec![reference.range])),
+        }
+    }
+
+    Ok(Some(calls))
+}
+
+/// The document of an item and its header, if it stands for one
+fn item_target<'a>(
+    lsp: &'a ServerState,
+    item: &CallHierarchyItem,
+) -> Result<(&'a Document, Option<&'a Reference>)> {
+    let document = get_document!(lsp, &item.uri);
+    let data: Option<ItemData> = item
+        .data
+        .clone()
+        .map(serde_json::from_value)
+        .transpose()
+        .into_diagnostic()?;
+
+    let header = data
+        .and_then(|data| data.header)
+        .and_then(|position| document.get_reference_at_position(position))
+        .filter(|reference| matches!(reference.kind, ReferenceKind::Header { .. }));
+    Ok((document, header))
+}
+
+fn note_item(document: &Document) -> Option<CallHierarchyItem> {
+    let uri = Uri::from_file_path(&document.path)?;
+    let name = uri.get_file_stem()?;
+    let content = document.content.slice(..);
+    let range = content.byte_to_lsp_range(&(0..document.content.len_bytes()));
+    let start = Range::new(range.start, range.start);
+
+    Some(item(name, SymbolKind::File, None, uri, range, start, None))
+}
+
+fn header_item(document: &Document, header: &Reference) -> Option<CallHierarchyItem> {
+    let ReferenceKind::Header { content, .. } = &header.kind else {
+        return None;
+    };
+    let uri = Uri::from_file_path(&document.path)?;
+    let section = section_range(document, header);
+    let text = document.content.slice(..);
+    let line = header.range.start.line as usize;
+    let line_text = document.content.line(line).to_string();
+    let line_end =
+        document.content.line_to_byte(line) + line_text.trim_end_matches(['\n', '\r']).len();
+    let selection =
+        text.byte_to_lsp_range(&(text.position_to_byte_offset(header.range.start)..line_end));
+
+    Some(item(
+        content.clone(),
+        SymbolKind::String,
+        uri.get_file_stem(),
+        uri,
+        section,
+        selection,
+        Some(header.range.start),
+    ))
+}
+
+fn item(
+    name: String,
+    kind: SymbolKind,
+    detail: Option<String>,
+    uri: Uri,
+    range: Range,
+    selection_range: Range,
+    header: Option<Position>,
+) -> CallHierarchyItem {
+    let data = serde_json::to_value(ItemData { header }).ok();
+    CallHierarchyItem::new(name, kind, None, detail, uri, range, selection_range, data)
+}
+
+/// Range of the section `header` starts
+fn section_range(document: &Document, header: &Reference) -> Range {
+    let (headers, sections) = header_sections(document);
+    headers
+        .iter()
+        .position(|(_, _, range)| range.start == header.range.start)
+        .map_or(header.range, |i| sections[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{TextDocumentIdentifier, TextDocumentPositionParams};
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn prepare(ws: &mut TestWorkspace, position: Position) -> CallHierarchyItem {
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: "file:///workspace/a.md".parse().unwrap(),
+                },
+                position,
+            },
+            work_done_progress_params: Default::default(),
+        };
+        process_prepare_call_hierarchy(&mut ws.state, params)
+            .unwrap()
+            .unwrap()
+            .remove(0)
+    }
+
+    fn incoming(ws: &mut TestWorkspace, item: CallHierarchyItem) -> Vec<(String, usize)> {
+        let params = CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        process_incoming_calls(&mut ws.state, params)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|call| (call.from.name, call.from_ranges.len()))
+            .collect()
+    }
+
+    fn outgoing(ws: &mut TestWorkspace, item: CallHierarchyItem) -> Vec<(String, usize)> {
+        let params = CallHierarchyOutgoingCallsParams {
+            item,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        process_outgoing_calls(&mut ws.state, params)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|call| (call.to.name, call.from_ranges.len()))
+            .collect()
+    }
+
+    #[test]
+    fn selects_header_lines_in_chars() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, "Intro\n\n## 🚀 Launch\n");
+
+        let launch = prepare(&mut ws, Position::new(2, 4));
+        assert_eq!(launch.name, "🚀 Launch");
+        assert_eq!(
+            launch.selection_range,
+            Range::new(Position::new(2, 0), Position::new(2, 11))
+        );
+    }
+
+    #[test]
+    fn follows_links_between_notes_and_headers() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/a.md",
+            1,
+            "Intro [[b]]\n\n## Part\n\nSee [[b]], [[c#Goal]] and [x](https://example.com)\n",
+        )
+        .add_file("/workspace/b.md", 1, "[[a]] [[a#Part]]\n\n[[a#Part]]\n")
+        .add_file("/workspace/c.md", 1, "# Goal\n\n[[a]]\n");
+
+        let note = prepare(&mut ws, Position::new(0, 0));
+        assert_eq!(note.name, "a");
+        assert_eq!(
+            incoming(&mut ws, note.clone()),
+            vec![("b".to_string(), 3), ("c".to_string(), 1)]
+        );
+        assert_eq!(
+            outgoing(&mut ws, note),
+            vec![("b".to_string(), 2), ("Goal".to_string(), 1)]
+        );
+
+        let part = prepare(&mut ws, Position::new(2, 3));
+        assert_eq!(part.name, "Part");
+        assert_eq!(incoming(&mut ws, part.clone()), vec![("b".to_string(), 2)]);
+        assert_eq!(
+            outgoing(&mut ws, part),
+            vec![("b".to_string(), 1), ("Goal".to_string(), 1)]
+        );
+    }
+}
@@ -3,12 +3,15 @@ use gen_lsp_types::{
     DeleteFileOptions, DocumentChange, Edit, OptionalVersionedTextDocumentIdentifier, Position,
     Range, TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
 };
-use lib_core::document::references::ReferenceKind;
+use lib_core::document::{references::ReferenceKind, tasks::today};
+use lib_parser::{ParseOptions, Parser, cst::SyntaxKind, error::QuickFix, markdown_parser_with};
 use miette::{Context, Result, miette};
 
 use crate::{
     get_document,
-    handlers::link_resolver::resolve_target_uri,
+    handlers::{
+        diagnostics::parse_diagnostic, link_resolver::resolve_target_uri, tasks::cycle_tasks_edit,
+    },
     helpers::{extract_header_section, generate_link_text, get_content},
     server_state::ServerState,
     uri::UriExt,
@@ -21,16 +24,100 @@ pub fn process_code_action(
     let uri = params.text_document.uri;
     let range = params.range;
 
+    let mut actions = cycle_task_actions(lsp, &uri, range)?;
+    actions.extend(parse_error_actions(lsp, &uri, range)?);
+
     // If range is not given check if cursor in over a header
-    if range.start == range.end {
-        return handle_non_range(lsp, &uri, &range);
+    if range.start == range.end
+        && let Some(non_range_actions) = handle_non_range(lsp, &uri, &range)?
+    {
+        actions.extend(non_range_actions);
     }
 
-    let actions: Vec<CodeActionResponse> = Vec::new();
-
     Ok(Some(actions))
 }
 
+fn cycle_task_actions(
+    lsp: &mut ServerState,
+    uri: &Uri,
+    range: Range,
+) -> Result<Vec<CodeActionResponse>> {
+    let document = get_document!(lsp, uri);
+
+    let completion_date = lsp.config.tasks.append_completion_date.then(today);
+    let Some(edit) = cycle_tasks_edit(document, uri, range, completion_date.as_deref()) else {
+        return Ok(vec![]);
+    };
+
+    Ok(vec![CodeActionResponse::CodeAction(CodeAction {
+        title: "Cycle task state".to_owned(),
+        kind: Some(CodeActionKind::QuickFix),
+        edit: Some(edit),
+        ..Default::default()
+    })])
+}
+
+/// Quick fixes for the parse errors on the lines of `range`
+fn parse_error_actions(
+    lsp: &mut ServerState,
+    uri: &Uri,
+    range: Range,
+) -> Result<Vec<CodeActionResponse>> {
+    let document = get_document!(lsp, uri);
+    let docs_url = lsp.config.diagnostics.docs_url.as_deref();
+
+    let actions = document
+        .parse_errors
+        .iter()
+        .filter(|issue| {
+            issue.range.start.line <= range.end.line && issue.range.end.line >= range.start.line
+        })
+        .filter_map(|issue| {
+            let (title, edit) = match issue.kind.quick_fix()? {
+                QuickFix::Insert(text) => (
+                    format!("Insert `{text}`"),
+                    TextEdit::new(
+                        Range::new(issue.range.start, issue.range.start),
+                        text.to_owned(),
+                    ),
+                ),
+                QuickFix::EscapeOpening => {
+                    let opening = issue.opening?;
+                    (
+                        format!("Escape `{}`", issue.kind.opening()?),
+                        TextEdit::new(Range::new(opening.start, opening.start), "\\".to_owned()),
+                    )
+                }
+            };
+
+            Some(CodeActionResponse::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QuickFix),
+                diagnostics: Some(vec![parse_diagnostic(uri, issue, docs_url)]),
+                is_preferred: Some(true),
+                edit: Some(WorkspaceEdit {
+                    changes: None,
+                    document_changes: Some(vec![DocumentChange::TextDocumentEdit(
+                        TextDocumentEdit {
+                            text_document: OptionalVersionedTextDocumentIdentifier {
+                                text_document_identifier: gen_lsp_types::TextDocumentIdentifier {
+                                    uri: uri.clone(),
+                                },
+                                version: Some(document.version),
+                            },
+                            edits: vec![Edit::TextEdit(edit)],
+                        },
+                    )]),
+                    change_annotations: None,
+                }),
+                ..Default::default()
+            }))
+        })
+        .collect();
+
+    Ok(actions)
+}
+
 fn handle_non_range(
     lsp: &mut ServerState,
     uri: &Uri,
@@ -87,7 +174,11 @@ fn handle_non_range(
                         },
                         edits: vec![Edit::TextEdit(TextEdit::new(
                             Range::new(Position::new(0, 0), Position::new(0, 0)),
-                            normalize_header_levels(&header_content.to_string(), delta),
+                            normalize_header_levels(
+                                &header_content.to_string(),
+                                delta,
+                                document.parse_options,
+                            ),
                         ))],
                     }),
                     DocumentChange::TextDocumentEdit(TextDocumentEdit {
@@ -171,24 +262,29 @@ fn handle_non_range(
                 ..Default::default()
             }));
         }
+        ReferenceKind::Tag { .. } => {}
     }
 
     Ok(Some(actions))
 }
 
-fn normalize_header_levels(content: &str, delta: i32) -> String {
-    content
-        .split('\n')
-        .map(|line| {
-            let hashes = line.chars().take_while(|c| *c == '#').count();
-            if hashes == 0 || !line[hashes..].starts_with(' ') {
-                return line.to_string();
-            }
-            let new_level = (hashes as i32 + delta).max(1) as usize;
-            format!("{} {}", "#".repeat(new_level), &line[hashes + 1..])
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+fn normalize_header_levels(content: &str, delta: i32, options: ParseOptions) -> String {
+    let Some(parsed) = markdown_parser_with(options).parse(content).into_output() else {
+        return content.to_string();
+    };
+
+    let mut tree = parsed.syntax_tree(content);
+    for header in tree
+        .child_nodes_mut()
+        .filter(|node| node.kind == SyntaxKind::Header)
+    {
+        if let Some(marker) = header.child_token_mut(SyntaxKind::HeaderMarker) {
+            let new_level = (marker.text.len() as i32 + delta).max(1) as usize;
+            marker.set_text("#".repeat(new_level));
+        }
+    }
+
+    tree.to_string()
 }
 
 #[cfg(test)]
@@ -198,14 +294,20 @@ mod tests {
     #[test]
     fn test_normalize_already_h1() {
         let content = "# Title\n\nSome text.";
-        assert_eq!(normalize_header_levels(content, 0), content);
+        assert_eq!(
+            normalize_header_levels(content, 0, ParseOptions::default()),
+            content
+        );
     }
 
     #[test]
     fn test_normalize_h3_to_h1() {
         let input = "### My Section\n\nParagraph.\n\n#### Sub\n\nMore.";
         let expected = "# My Section\n\nParagraph.\n\n## Sub\n\nMore.";
-        assert_eq!(normalize_header_levels(input, -2), expected);
+        assert_eq!(
+            normalize_header_levels(input, -2, ParseOptions::default()),
+            expected
+        );
     }
 
     #[test]
@@ -213,13 +315,73 @@ mod tests {
         // H2 with delta -5 should clamp to H1, not go negative
         let input = "## Section\n\n### Child";
         let expected = "# Section\n\n# Child";
-        assert_eq!(normalize_header_levels(input, -5), expected);
+        assert_eq!(
+            normalize_header_levels(input, -5, ParseOptions::default()),
+            expected
+        );
     }
 
     #[test]
     fn test_normalize_ignores_non_headers() {
         let input = "# Title\n\nThis has a #hashtag in it.\n\n## Sub";
         let expected = "# Title\n\nThis has a #hashtag in it.\n\n# Sub";
-        assert_eq!(normalize_header_levels(input, -1), expected);
+        assert_eq!(
+            normalize_header_levels(input, -1, ParseOptions::default()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_error_quick_fixes() {
+        use crate::test_utils::TestWorkspace;
+
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/note.md",
+            3,
+            "See [[Other and more\n\nPlain\n\nText [oops\n",
+        );
+        let uri = Uri::from_file_path("/workspace/note.md").unwrap();
+
+        let fixes = |ws: &mut TestWorkspace, line: u32| -> Vec<(String, TextEdit)> {
+            let range = Range::new(Position::new(line, 0), Position::new(line, 0));
+            parse_error_actions(&mut ws.state, &uri, range)
+                .unwrap()
+                .into_iter()
+                .map(|action| {
+                    let CodeActionResponse::CodeAction(action) = action else {
+                        panic!("expected a code action");
+                    };
+                    let Some(DocumentChange::TextDocumentEdit(edit)) =
+                        action.edit.unwrap().document_changes.unwrap().pop()
+                    else {
+                        panic!("expected a text edit");
+                    };
+                    let Edit::TextEdit(text_edit) = edit.edits[0].clone() else {
+                        panic!("expected a plain edit");
+                    };
+                    (action.title, text_edit)
+                })
+                .collect()
+        };
+
+        let insert_at = Position::new(0, 20);
+        assert_eq!(
+            fixes(&mut ws, 0),
+            vec![(
+                "Insert `]]`".to_owned(),
+                TextEdit::new(Range::new(insert_at, insert_at), "]]".to_owned())
+            )]
+        );
+
+        let escape_at = Position::new(4, 5);
+        assert_eq!(
+            fixes(&mut ws, 4),
+            vec![(
+                "Escape `[`".to_owned(),
+                TextEdit::new(Range::new(escape_at, escape_at), "\\".to_owned())
+            )]
+        );
+        assert!(fixes(&mut ws, 2).is_empty());
     }
 }
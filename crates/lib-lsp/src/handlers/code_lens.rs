@@ -0,0 +1,159 @@
+use gen_lsp_types::{CodeLens, CodeLensParams, Command, Position, Range, Uri};
+use lib_core::document::references::ReferenceKind;
+use miette::{Context, IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    get_document, handlers::references::reference_locations, server_state::ServerState, uri::UriExt,
+};
+
+/// Client command opening the references view, as in VS Code
+pub const SHOW_REFERENCES_COMMAND: &str = "editor.action.showReferences";
+
+/// What counting the backlinks needs, kept in [`CodeLens::data`]
+#[derive(Debug, Serialize, Deserialize)]
+struct LensData {
+    uri: Uri,
+    /// Start of the header, the whole document without one
+    header: Option<Position>,
+}
+
+/// A lens at the top of the document and above each H1 and H2, counted in
+/// [`process_code_lens_resolve`]. A header on the first line shares the
+/// document's lens, whose count includes links to the header.
+pub fn process_code_lens(
+    lsp: &mut ServerState,
+    params: CodeLensParams,
+) -> Result<Option<Vec<CodeLens>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let headers = document
+        .references
+        .iter()
+        .filter(|reference| matches!(reference.kind, ReferenceKind::Header { level: 1 | 2, .. }))
+        .map(|reference| reference.range.start)
+        .filter(|start| *start != Position::default())
+        .map(Some);
+
+    let lenses = std::iter::once(None)
+        .chain(headers)
+        .filter_map(|header| {
+            let start = header.unwrap_or_default();
+            let data = LensData {
+                uri: uri.clone(),
+                header,
+            };
+            Some(CodeLens::new(
+                Range::new(start, start),
+                None,
+                Some(serde_json::to_value(data).ok()?),
+            ))
+        })
+        .collect();
+
+    Ok(Some(lenses))
+}
+
+pub fn process_code_lens_resolve(lsp: &mut ServerState, mut lens: CodeLens) -> Result<CodeLens> {
+    let Some(data) = lens.data.take() else {
+        return Ok(lens);
+    };
+    let data: LensData = serde_json::from_value(data).into_diagnostic()?;
+    let document = get_document!(lsp, &data.uri);
+
+    // A header edited away since the lens was made counts as the whole document
+    let header = data
+        .header
+        .and_then(|position| document.get_reference_at_position(position))
+        .filter(|reference| matches!(reference.kind, ReferenceKind::Header { .. }));
+    let locations = reference_locations(lsp, document, &data.uri, header);
+
+    let title = match locations.len() {
+        1 => "1 backlink".to_string(),
+        count => format!("{count} backlinks"),
+    };
+    let arguments = vec![
+        serde_json::to_value(&data.uri).into_diagnostic()?,
+        serde_json::to_value(lens.range.start).into_diagnostic()?,
+        serde_json::to_value(&locations).into_diagnostic()?,
+    ];
+    lens.command = Some(Command::new(
+        title,
+        None,
+        SHOW_REFERENCES_COMMAND.to_string(),
+        Some(arguments),
+    ));
+    Ok(lens)
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::TextDocumentIdentifier;
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn titles(ws: &mut TestWorkspace, lenses: Vec<CodeLens>) -> Vec<(u32, String, usize)> {
+        lenses
+            .into_iter()
+            .map(|lens| process_code_lens_resolve(&mut ws.state, lens).unwrap())
+            .map(|lens| {
+                let command = lens.command.unwrap();
+                assert_eq!(command.command, SHOW_REFERENCES_COMMAND);
+                let locations = command.arguments.unwrap()[2].as_array().unwrap().len();
+                (lens.range.start.line, command.title, locations)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_backlinks_of_file_and_headers() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, "# Top\n\n## Part\n\n### Deep\n")
+            .add_file("/workspace/b.md", 1, "[[a]] [[a#Part]]\n")
+            .add_file("/workspace/c.md", 1, "[[a#Part]] [[a#Deep]]\n");
+
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier {
+                uri: "file:///workspace/a.md".parse().unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let lenses = process_code_lens(&mut ws.state, params).unwrap().unwrap();
+        assert!(lenses.iter().all(|lens| lens.command.is_none()));
+
+        assert_eq!(
+            titles(&mut ws, lenses),
+            vec![
+                (0, "4 backlinks".to_string(), 4),
+                (2, "2 backlinks".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_the_document_when_the_header_is_gone() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, "Intro\n\n## Part\n")
+            .add_file("/workspace/b.md", 1, "[[a]] [[a#Part]]\n");
+
+        let uri: Uri = "file:///workspace/a.md".parse().unwrap();
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let lenses = process_code_lens(&mut ws.state, params).unwrap().unwrap();
+
+        ws.add_file("/workspace/a.md", 2, "Intro\n\nPart\n");
+        assert_eq!(
+            titles(&mut ws, lenses),
+            vec![
+                (0, "2 backlinks".to_string(), 2),
+                (2, "2 backlinks".to_string(), 2),
+            ]
+        );
+    }
+}
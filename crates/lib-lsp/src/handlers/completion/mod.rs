@@ -2,6 +2,7 @@ use lib_core::{
     document::{Document, references::ReferenceKind},
     path::slug::header_slug,
     text_buffer_conversions::TextBufferConversions,
+    vault::tags::is_tag_char,
 };
 
 use gen_lsp_types::{
@@ -30,10 +31,47 @@ struct LinkContext {
     is_incomplete: bool,
 }
 
+/// A `#tag` being typed, completed one `/` separated segment at a time
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TagContext<'a> {
+    /// Segments typed before the last `/`, e.g. `project/alpha` for `#project/alpha/de`
+    parent: Option<&'a str>,
+    /// End of the tag being typed, which is indexed as a use of its own
+    position: Position,
+}
+
+impl TagContext<'_> {
+    fn from_position(document: &Document, byte_pos: usize) -> Option<TagContext<'_>> {
+        let slice = document.content.slice(..);
+
+        let (hash_idx, found_char) = find_byte_backwards_any(&slice, byte_pos, b"#[( \t\n")?;
+        if found_char != b'#' {
+            return None;
+        }
+
+        // Tags start a word, a `#` after other text is a header link like `[[note#`
+        if hash_idx > 0 && !matches!(slice.byte(hash_idx - 1), b' ' | b'\t' | b'\n') {
+            return None;
+        }
+
+        let typed = slice.get_byte_slice(hash_idx + 1..byte_pos)?.as_str()?;
+        if !typed.chars().all(|c| c == '/' || is_tag_char(c)) {
+            return None;
+        }
+
+        Some(TagContext {
+            parent: typed.rsplit_once('/').map(|(parent, _)| parent),
+            position: slice.byte_offset_to_position(byte_pos),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum CompletionIntent<'a> {
     Document(LinkContext),
     Header(HeaderContext<'a>),
+    Tag(TagContext<'a>),
+    #[allow(dead_code)]
     Footnote,
 }
 
@@ -41,6 +79,10 @@ impl CompletionIntent<'_> {
     fn from_position<'a>(document: &'a Document, byte_pos: usize) -> Option<CompletionIntent<'a>> {
         let slice = document.content.slice(..);
 
+        if let Some(tag) = TagContext::from_position(document, byte_pos) {
+            return Some(CompletionIntent::Tag(tag));
+        }
+
         if byte_pos >= 2 {
             let trigger = slice
                 .get_byte_slice(byte_pos.saturating_sub(2)..byte_pos)
@@ -155,6 +197,11 @@ fn handle_invoked_completion(
     let slice = document.content.slice(..);
     let byte_pos = slice.position_to_byte_offset(position);
 
+    // Tags are completed from the cursor, the typed segments pick the level
+    if let Some(tag) = TagContext::from_position(document, byte_pos) {
+        return complete_tags(lsp, document, tag);
+    }
+
     let (anchor_idx, anchor_char) = find_byte_backwards_any(&slice, byte_pos, b"[(#:\n")?;
 
     if anchor_char == b'\n' {
@@ -185,6 +232,7 @@ fn handle_trigger_completion(
     match intent {
         CompletionIntent::Document(ctx) => complete_document_links(lsp, document, ctx),
         CompletionIntent::Header(ctx) => complete_headers(lsp, document, ctx),
+        CompletionIntent::Tag(ctx) => complete_tags(lsp, document, ctx),
         CompletionIntent::Footnote => {
             // do nothing
             None
@@ -304,6 +352,60 @@ fn complete_headers(
     Some(completions)
 }
 
+fn complete_tags(
+    lsp: &ServerState,
+    document: &Document,
+    ctx: TagContext,
+) -> Option<Vec<CompletionItem>> {
+    let index = lsp.documents.tag_index();
+    // Uses of `tag` and its descendants, leaving out the tag being typed
+    let uses = |tag: &str| {
+        index
+            .descendants(tag)
+            .flat_map(|(_, occurrences)| occurrences)
+            .filter(|occurrence| {
+                let range = occurrence.range;
+                occurrence.path != document.path
+                    || !(range.start <= ctx.position && ctx.position <= range.end)
+            })
+            .count()
+    };
+
+    let completions = index
+        .children(ctx.parent)
+        .into_iter()
+        .filter_map(|tag| {
+            let count = uses(&tag);
+            if count == 0 {
+                return None;
+            }
+            let segment = tag.rsplit('/').next().unwrap_or(&tag).to_string();
+            let has_children = index
+                .children(Some(&tag))
+                .iter()
+                .any(|child| uses(child) > 0);
+
+            Some(CompletionItem {
+                label: segment.clone(),
+                label_details: Some(CompletionItemLabelDetails {
+                    detail: has_children.then(|| "/".to_string()),
+                    description: Some(format!("{count} use(s)")),
+                }),
+                kind: Some(if has_children {
+                    CompletionItemKind::Folder
+                } else {
+                    CompletionItemKind::Keyword
+                }),
+                detail: Some(format!("#{tag}")),
+                insert_text: Some(segment),
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    Some(completions)
+}
+
 fn has_closing_chars(document: &Document, byte_pos: usize, link_type: LinkType) -> bool {
     let slice = document.content.slice(..);
 
@@ -344,6 +446,10 @@ fn find_byte_backwards_any(
     None
 }
 
+/// File part of the link whose `#` is at `byte_pos`, e.g. `note` in `[[note#`.
+///
+/// Scans the text rather than the syntax tree: a link being typed does not
+/// parse yet, so the tree only holds an error node of plain text there.
 fn extract_file_and_link_type_from_context(
     document: &Document,
     byte_pos: usize,
@@ -353,22 +459,62 @@ fn extract_file_and_link_type_from_context(
     let (idx, found_char) = find_byte_backwards_any(&slice, byte_pos, b"[(")?;
 
     match found_char {
-        b'[' => {
+        b'['
             // Peek left: is it another '['? -> WikiLink
-            if idx > 0 && slice.byte(idx - 1) == b'[' {
+            if idx > 0 && slice.byte(idx - 1) == b'[' => {
                 let path = slice.get_byte_slice(idx + 1..byte_pos)?.as_str()?;
                 return Some((path, LinkType::WikiLink));
             }
-        }
-        b'(' => {
+        b'('
             // Peek left: is it a ']'? -> MarkdownLink [text](path)
-            if idx > 0 && slice.byte(idx - 1) == b']' {
+            if idx > 0 && slice.byte(idx - 1) == b']' => {
                 let path = slice.get_byte_slice(idx + 1..byte_pos)?.as_str()?;
                 return Some((path, LinkType::MarkdownLink));
             }
-        }
         _ => {}
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn tag_labels(ws: &TestWorkspace, path: &str, position: Position) -> Option<Vec<String>> {
+        let document = ws
+            .state
+            .documents
+            .get_document(std::path::Path::new(path))
+            .unwrap();
+        let completions = handle_invoked_completion(&ws.state, document, position)?;
+
+        Some(completions.into_iter().map(|item| item.label).collect())
+    }
+
+    #[test]
+    fn tags_complete_segment_by_segment() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/a.md",
+            1,
+            "#project/alpha/design #project/beta #inbox",
+        )
+        .add_file("/workspace/b.md", 1, "Notes #\n\nMore #project/al\n\n[[a#");
+
+        assert_eq!(
+            tag_labels(&ws, "/workspace/b.md", Position::new(0, 7)),
+            Some(vec!["inbox".to_string(), "project".to_string()])
+        );
+        assert_eq!(
+            tag_labels(&ws, "/workspace/b.md", Position::new(2, 16)),
+            Some(vec!["alpha".to_string(), "beta".to_string()])
+        );
+        // A `#` inside a wiki link is a header, not a tag
+        assert_ne!(
+            tag_labels(&ws, "/workspace/b.md", Position::new(4, 4)),
+            Some(vec!["inbox".to_string(), "project".to_string()])
+        );
+    }
+}
@@ -1,7 +1,11 @@
+use std::str::FromStr;
+
 use gen_lsp_types::{
-    DocumentDiagnosticParams, DocumentDiagnosticReport, FullDocumentDiagnosticReport,
-    RelatedFullDocumentDiagnosticReport,
+    Code, CodeDescription, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DocumentDiagnosticParams, DocumentDiagnosticReport, FullDocumentDiagnosticReport, Location,
+    RelatedFullDocumentDiagnosticReport, Uri,
 };
+use lib_core::document::ParseIssue;
 use miette::{Context, Result};
 
 use crate::{get_document, server_state::ServerState, uri::UriExt};
@@ -13,6 +17,7 @@ pub fn process_diagnostic(
     let uri = params.text_document.uri;
 
     let document = get_document!(&lsp, &uri);
+    let docs_url = lsp.config.diagnostics.docs_url.as_deref();
 
     Ok(
         DocumentDiagnosticReport::RelatedFullDocumentDiagnosticReport(
@@ -20,9 +25,93 @@ pub fn process_diagnostic(
                 related_documents: None,
                 full_document_diagnostic_report: FullDocumentDiagnosticReport {
                     result_id: Some("markdown-lsp".to_owned()),
-                    items: document.diagnostics.clone(),
+                    items: document
+                        .parse_errors
+                        .iter()
+                        .map(|issue| parse_diagnostic(&uri, issue, docs_url))
+                        .collect(),
                 },
             },
         ),
     )
 }
+
+/// Diagnostic for a parse error of the document at `uri`
+pub(crate) fn parse_diagnostic(
+    uri: &Uri,
+    issue: &ParseIssue,
+    docs_url: Option<&str>,
+) -> Diagnostic {
+    let code = issue.kind.code();
+
+    let related_information = issue
+        .opening
+        .zip(issue.kind.opening())
+        .map(|(range, opening)| {
+            vec![DiagnosticRelatedInformation::new(
+                Location::new(uri.clone(), range),
+                format!("`{opening}` opened here"),
+            )]
+        });
+
+    Diagnostic {
+        range: issue.range,
+        severity: Some(DiagnosticSeverity::Warning),
+        code: Some(Code::String(code.to_owned())),
+        code_description: docs_url
+            .and_then(|url| Uri::from_str(&format!("{}#{code}", url.trim_end_matches('#'))).ok())
+            .map(CodeDescription::new),
+        source: Some("parser".to_owned()),
+        message: issue.message.clone(),
+        related_information,
+        tags: None,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{Position, Range, TextDocumentIdentifier};
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    #[test]
+    fn test_parse_errors_have_codes_and_openings() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/note.md", 1, "# Note\n\nSee [[Other and more\n");
+        ws.state.config.diagnostics.docs_url = Some("https://docs.test/codes".to_owned());
+
+        let uri = Uri::from_file_path("/workspace/note.md").unwrap();
+        let params = DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            identifier: None,
+            previous_result_id: None,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let DocumentDiagnosticReport::RelatedFullDocumentDiagnosticReport(report) =
+            process_diagnostic(&mut ws.state, params).unwrap()
+        else {
+            panic!("expected a full report");
+        };
+
+        let [diagnostic] = report.full_document_diagnostic_report.items.as_slice() else {
+            panic!("{report:?}");
+        };
+        assert_eq!(
+            diagnostic.code,
+            Some(Code::String("unclosed-wikilink".to_owned()))
+        );
+        assert_eq!(
+            diagnostic.code_description.as_ref().unwrap().href.as_str(),
+            "https://docs.test/codes#unclosed-wikilink"
+        );
+        let related = &diagnostic.related_information.as_ref().unwrap()[0];
+        assert_eq!(related.location.uri, uri);
+        assert_eq!(
+            related.location.range,
+            Range::new(Position::new(2, 4), Position::new(2, 6))
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use gen_lsp_types::{DidChangeTextDocumentParams, TextDocumentContentChangeEvent};
+use gen_lsp_types::{
+    DidChangeTextDocumentParams, PositionEncodingKind, Range, TextDocumentContentChangeEvent,
+};
+use lib_core::text_buffer_conversions::TextBufferConversions;
 use miette::{Result, miette};
 
 use crate::{server_state::ServerState, uri::UriExt};
@@ -13,19 +16,100 @@ pub fn process_did_change(
     let path = uri
         .to_file_path()
         .ok_or_else(|| miette!("Invalid URI: {}", uri.as_ref()))?;
+    let utf16 = lsp.position_encoding() == PositionEncodingKind::UTF16;
 
     for change in params.content_changes {
         match change {
             TextDocumentContentChangeEvent::TextDocumentContentChangeWholeDocument(event) => {
                 lsp.documents.update_document(&path, version, &event.text)?;
             }
-            TextDocumentContentChangeEvent::TextDocumentContentChangePartial(_) => {
-                return Err(miette!(
-                    "Incremental document changes are not yet supported.\nPlease configure your editor to send full document updates."
-                ));
+            TextDocumentContentChangeEvent::TextDocumentContentChangePartial(event) => {
+                // Each change applies to the text the previous ones left
+                let range = match lsp.documents.get_document(&path) {
+                    Some(document) if utf16 => {
+                        let content = document.content.slice(..);
+                        Range::new(
+                            content.utf16_to_char_position(event.range.start),
+                            content.utf16_to_char_position(event.range.end),
+                        )
+                    }
+                    _ => event.range,
+                };
+                lsp.documents
+                    .edit_document(&path, version, range, &event.text)?;
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{
+        ClientCapabilities, GeneralClientCapabilities, Position, TextDocumentContentChangePartial,
+        TextDocumentIdentifier, VersionedTextDocumentIdentifier,
+    };
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    const TASK: &str = "- [ ] Write 📅 2026-10-20\nnext\n";
+
+    fn insert(ws: &mut TestWorkspace, position: Position, text: &str) -> String {
+        let params = DidChangeTextDocumentParams::new(
+            VersionedTextDocumentIdentifier::new(
+                2,
+                TextDocumentIdentifier {
+                    uri: "file:///workspace/a.md".parse().unwrap(),
+                },
+            ),
+            vec![
+                TextDocumentContentChangeEvent::TextDocumentContentChangePartial(
+                    TextDocumentContentChangePartial::new(
+                        Range::new(position, position),
+                        None,
+                        text.to_string(),
+                    ),
+                ),
+            ],
+        );
+        process_did_change(&mut ws.state, params).unwrap();
+
+        let document = ws.state.documents.get_document("/workspace/a.md".as_ref());
+        document.unwrap().content.to_string()
+    }
+
+    #[test]
+    fn converts_utf16_columns_after_an_emoji() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, TASK);
+
+        assert_eq!(
+            insert(&mut ws, Position::new(0, 25), "!"),
+            "- [ ] Write 📅 2026-10-20!\nnext\n"
+        );
+    }
+
+    #[test]
+    fn keeps_char_columns_when_utf32_was_negotiated() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, TASK);
+        ws.state.set_client_capabilities(ClientCapabilities {
+            general: Some(GeneralClientCapabilities {
+                position_encodings: Some(vec![
+                    PositionEncodingKind::UTF16,
+                    PositionEncodingKind::UTF32,
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        assert_eq!(ws.state.position_encoding(), PositionEncodingKind::UTF32);
+        assert_eq!(
+            insert(&mut ws, Position::new(0, 24), "!"),
+            "- [ ] Write 📅 2026-10-20!\nnext\n"
+        );
+    }
+}
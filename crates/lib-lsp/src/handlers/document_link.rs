@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use gen_lsp_types::{DocumentLink, DocumentLinkParams, Uri};
+use lib_core::{
+    document::Document, render::is_external, text_buffer_conversions::TextBufferConversions,
+};
+use lib_parser::{LinkType, Parser, SimpleSpan, markdown_parser_with, unescape, visit::Visitor};
+use miette::{Context, IntoDiagnostic, Result, miette};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    get_document,
+    handlers::{goto_definition::find_header, link_resolver::resolve_linked_note},
+    server_state::ServerState,
+    uri::UriExt,
+};
+
+/// What resolving a vault link needs, kept in [`DocumentLink::data`]
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkData {
+    /// The document containing the link
+    source: Uri,
+    target: String,
+    header: Option<String>,
+}
+
+/// Links to other notes, attachments and images are resolved in
+/// [`process_document_link_resolve`], external links right away
+pub fn process_document_link(
+    lsp: &mut ServerState,
+    params: DocumentLinkParams,
+) -> Result<Option<Vec<DocumentLink>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let source = document.content.to_string();
+    let Some(parsed) = markdown_parser_with(document.parse_options)
+        .parse(&source)
+        .into_output()
+    else {
+        return Ok(None);
+    };
+
+    let mut links = Links::default();
+    links.visit_markdown(&parsed);
+
+    let content = document.content.slice(..);
+    let document_links = links
+        .0
+        .into_iter()
+        .filter_map(|(target, header, span)| {
+            let range = content.byte_to_lsp_range(&span.into_range());
+
+            if is_external(&target) {
+                let uri = Uri::parse(target.clone()).ok()?;
+                return Some(DocumentLink::new(range, Some(uri), Some(target), None));
+            }
+
+            let data = LinkData {
+                source: uri.clone(),
+                target,
+                header,
+            };
+            Some(DocumentLink::new(
+                range,
+                None,
+                None,
+                serde_json::to_value(data).ok(),
+            ))
+        })
+        .collect();
+
+    Ok(Some(document_links))
+}
+
+pub fn process_document_link_resolve(
+    lsp: &mut ServerState,
+    mut link: DocumentLink,
+) -> Result<DocumentLink> {
+    let Some(data) = link.data.take() else {
+        return Ok(link);
+    };
+    let data: LinkData = serde_json::from_value(data).into_diagnostic()?;
+    let document = get_document!(lsp, &data.source);
+
+    let (path, line) = resolve_link_target(lsp, document, &data)?;
+    let mut target = Uri::from_file_path(&path)
+        .ok_or_else(|| miette!("Failed to convert path to URI: {:?}", path))?
+        .to_string();
+    let mut tooltip = path.display().to_string();
+    if let Some(header) = &data.header {
+        tooltip.push_str(&format!("#{header}"));
+    }
+    if let Some(line) = line {
+        target.push_str(&format!("#L{}", line + 1));
+    }
+
+    link.target = Some(
+        Uri::parse(target)
+            .map_err(|(error, target)| miette!("Invalid link URI {target}: {error}"))?,
+    );
+    link.tooltip = Some(tooltip);
+    Ok(link)
+}
+
+/// Path of the note or file a link points to, with the line of its header
+fn resolve_link_target(
+    lsp: &ServerState,
+    document: &Document,
+    data: &LinkData,
+) -> Result<(PathBuf, Option<u32>)> {
+    let target = if data.target.is_empty() {
+        document
+    } else {
+        match resolve_linked_note(lsp, document, &data.target)? {
+            (_, Some(note)) => note,
+            (path, None) => return Ok((path, None)),
+        }
+    };
+
+    let line = data
+        .header
+        .as_deref()
+        .and_then(|header| find_header(target, header))
+        .map(|header| header.range.start.line);
+
+    Ok((target.path.clone(), line))
+}
+
+/// Targets and headers of the links and images in a document
+#[derive(Default)]
+struct Links(Vec<(String, Option<String>, SimpleSpan)>);
+
+impl<'a> Visitor<'a> for Links {
+    fn visit_link(&mut self, link: &LinkType<'a>, span: SimpleSpan) {
+        let (target, header) = match link {
+            LinkType::InlineLink { uri, header, .. } => (*uri, *header),
+            LinkType::WikiLink { target, header, .. } => (*target, *header),
+            LinkType::ImageLink { uri, .. } => (*uri, None),
+        };
+        let header = header.map(|header| unescape(header).into_owned());
+        self.0.push((unescape(target).into_owned(), header, span));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::TextDocumentIdentifier;
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    #[test]
+    fn resolves_every_link_kind() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/a.md",
+            1,
+            "# Top\n\nSee [[Other#Part Two]], [b](notes/b.md), [up](#top) and ![img](img/pic.png)\n\n\
+Also [site](https://example.com/page).\n",
+        )
+        .add_file("/workspace/Other.md", 1, "# Other\n\ntext\n\n## Part Two\n")
+        .add_file("/workspace/notes/b.md", 1, "b\n");
+
+        let params = DocumentLinkParams {
+            text_document: TextDocumentIdentifier {
+                uri: "file:///workspace/a.md".parse().unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let links = process_document_link(&mut ws.state, params)
+            .unwrap()
+            .unwrap();
+
+        let resolved: Vec<_> = links
+            .into_iter()
+            .map(|link| process_document_link_resolve(&mut ws.state, link).unwrap())
+            .map(|link| (link.target.unwrap().to_string(), link.tooltip.unwrap()))
+            .collect();
+
+        assert_eq!(
+            resolved,
+            vec![
+                (
+                    "file:///workspace/Other.md#L5".to_string(),
+                    "/workspace/Other.md#Part Two".to_string()
+                ),
+                (
+                    "file:///workspace/notes/b.md".to_string(),
+                    "/workspace/notes/b.md".to_string()
+                ),
+                (
+                    "file:///workspace/a.md#L1".to_string(),
+                    "/workspace/a.md#top".to_string()
+                ),
+                (
+                    "file:///workspace/img/pic.png".to_string(),
+                    "/workspace/img/pic.png".to_string()
+                ),
+                (
+                    "https://example.com/page".to_string(),
+                    "https://example.com/page".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use gen_lsp_types::{
     DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, Position, Range, SymbolKind,
 };
-use lib_core::document::references::ReferenceKind;
+use lib_core::document::{Document, references::ReferenceKind};
 use miette::{Context, Result};
 
 use crate::{get_document, server_state::ServerState, uri::UriExt};
@@ -13,6 +13,20 @@ pub fn process_document_symbol(
     let uri = params.text_document.uri;
     let document = get_document!(lsp, &uri);
 
+    let (headers, section_ranges) = header_sections(document);
+    if headers.is_empty() {
+        return Ok(Some(DocumentSymbolResponse::DocumentSymbolList(vec![])));
+    }
+
+    let mut idx = 0;
+    let symbols = build_symbol_tree(&headers, &section_ranges, &mut idx, 0);
+
+    Ok(Some(DocumentSymbolResponse::DocumentSymbolList(symbols)))
+}
+
+/// Headers of `document` as `(level, content, range)`, with the range of the
+/// section each one starts, up to the next header of the same or a higher level
+pub(crate) fn header_sections(document: &Document) -> (Vec<(usize, String, Range)>, Vec<Range>) {
     let headers: Vec<(usize, String, Range)> = document
         .references
         .iter()
@@ -25,10 +39,6 @@ pub fn process_document_symbol(
         })
         .collect();
 
-    if headers.is_empty() {
-        return Ok(Some(DocumentSymbolResponse::DocumentSymbolList(vec![])));
-    }
-
     let total_lines = document.content.len_lines() as u32;
     let section_ranges: Vec<Range> = headers
         .iter()
@@ -47,10 +57,7 @@ pub fn process_document_symbol(
         })
         .collect();
 
-    let mut idx = 0;
-    let symbols = build_symbol_tree(&headers, &section_ranges, &mut idx, 0);
-
-    Ok(Some(DocumentSymbolResponse::DocumentSymbolList(symbols)))
+    (headers, section_ranges)
 }
 
 fn build_symbol_tree(
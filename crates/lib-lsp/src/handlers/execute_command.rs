@@ -0,0 +1,101 @@
+use gen_lsp_types::{
+    ApplyWorkspaceEditParams, ApplyWorkspaceEditRequest, ExecuteCommandParams, LspAny,
+};
+use lib_core::document::tasks::today;
+use miette::{Context, IntoDiagnostic, Result, miette};
+
+use crate::{
+    get_document,
+    handlers::tasks::{CYCLE_TASK_COMMAND, CycleTaskArgs, cycle_tasks_edit},
+    server_state::ServerState,
+    uri::UriExt,
+};
+
+/// Commands advertised in the `executeCommandProvider` capability
+pub const COMMANDS: [&str; 1] = [CYCLE_TASK_COMMAND];
+
+pub fn process_execute_command(
+    lsp: &mut ServerState,
+    params: ExecuteCommandParams,
+) -> Result<Option<LspAny>> {
+    let argument = params
+        .arguments
+        .and_then(|arguments| arguments.into_iter().next())
+        .unwrap_or_default();
+
+    match params.command.as_str() {
+        CYCLE_TASK_COMMAND => {
+            let args: CycleTaskArgs = serde_json::from_value(argument)
+                .into_diagnostic()
+                .context("Invalid arguments for cycle task command")?;
+            let document = get_document!(lsp, &args.uri);
+
+            let completion_date = args
+                .append_completion_date
+                .unwrap_or(lsp.config.tasks.append_completion_date)
+                .then(today);
+            let Some(edit) =
+                cycle_tasks_edit(document, &args.uri, args.range, completion_date.as_deref())
+            else {
+                return Ok(None);
+            };
+
+            lsp.send_request::<ApplyWorkspaceEditRequest>(ApplyWorkspaceEditParams {
+                label: Some("Cycle task state".to_string()),
+                edit,
+                metadata: None,
+            })?;
+        }
+        command => return Err(miette!("Unknown command: {}", command)),
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{Position, Range};
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    #[test]
+    fn cycle_task_command_sends_apply_edit() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/todo.md", 3, "- [/] Ship it\n");
+
+        let args = serde_json::json!({
+            "uri": "file:///workspace/todo.md",
+            "range": Range::new(Position::new(0, 0), Position::new(0, 0)),
+            "appendCompletionDate": false,
+        });
+        process_execute_command(
+            &mut ws.state,
+            ExecuteCommandParams {
+                command: CYCLE_TASK_COMMAND.to_string(),
+                arguments: Some(vec![args]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let requests = ws.state.take_outgoing_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "workspace/applyEdit");
+
+        let params: ApplyWorkspaceEditParams =
+            serde_json::from_value(requests[0].params.clone()).unwrap();
+        let edit = serde_json::to_string(&params.edit).unwrap();
+        assert!(edit.contains(r#""newText":"x""#), "{edit}");
+        assert!(!edit.contains('✅'), "{edit}");
+
+        let unknown = process_execute_command(
+            &mut ws.state,
+            ExecuteCommandParams {
+                command: "markdown.unknown".to_string(),
+                ..Default::default()
+            },
+        );
+        assert!(unknown.is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use gen_lsp_types::{FoldingRange, FoldingRangeKind, FoldingRangeParams, Range};
+use lib_core::format::{fence_opening, is_fence_closing};
+use miette::{Context, Result};
+
+use crate::{
+    get_document, handlers::document_symbol::header_sections, server_state::ServerState,
+    uri::UriExt,
+};
+
+/// Lists are folded from this many lines on
+const MIN_LIST_LINES: usize = 3;
+
+pub fn process_folding_range(
+    lsp: &mut ServerState,
+    params: FoldingRangeParams,
+) -> Result<Option<Vec<FoldingRange>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let source = document.content.to_string();
+    let lines: Vec<&str> = source.lines().collect();
+    let mut ranges = block_ranges(&lines, document.frontmatter_range);
+
+    // Sections end before the blank lines preceding the next header
+    let (_, section_ranges) = header_sections(document);
+    for section in section_ranges {
+        let start = section.start.line as usize;
+        let mut end = (section.end.line as usize).min(lines.len().saturating_sub(1));
+        while end > start && lines[end].trim().is_empty() {
+            end -= 1;
+        }
+        ranges.push(fold(start, end, Some(FoldingRangeKind::Region)));
+    }
+
+    // Clients show one fold per start line, keep the outermost
+    ranges.retain(|range| range.end_line > range.start_line);
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+    ranges.dedup_by_key(|range| range.start_line);
+
+    Ok(Some(ranges))
+}
+
+fn fold(start: usize, end: usize, kind: Option<FoldingRangeKind>) -> FoldingRange {
+    FoldingRange::new(start as u32, None, end as u32, None, kind, None)
+}
+
+/// Frontmatter, code blocks, blockquotes, lists and HTML comments
+fn block_ranges(lines: &[&str], frontmatter: Option<Range>) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+
+    if let Some(frontmatter) = frontmatter {
+        let end = frontmatter.end.line as usize;
+        ranges.push(fold(0, end, Some(FoldingRangeKind::Region)));
+        i = end + 1;
+    }
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        let end = if let Some(fence) = fence_opening(line) {
+            (i + 1..lines.len())
+                .find(|&j| is_fence_closing(lines[j], fence))
+                .unwrap_or(lines.len() - 1)
+        } else if let Some(comment) = line.find("<!--")
+            && !line[comment..].contains("-->")
+        {
+            let end = (i + 1..lines.len())
+                .find(|&j| lines[j].contains("-->"))
+                .unwrap_or(lines.len() - 1);
+            ranges.push(fold(i, end, Some(FoldingRangeKind::Comment)));
+            i = end + 1;
+            continue;
+        } else if line.trim_start().starts_with('>') {
+            (i..lines.len())
+                .take_while(|&j| lines[j].trim_start().starts_with('>'))
+                .last()
+                .unwrap_or(i)
+        } else if is_list_item(line) {
+            let end = list_end(lines, i);
+            list_item_ranges(lines, i..end + 1, &mut ranges);
+            if end + 1 - i < MIN_LIST_LINES {
+                i = end + 1;
+                continue;
+            }
+            end
+        } else {
+            i += 1;
+            continue;
+        };
+
+        ranges.push(fold(i, end, None));
+        i = end + 1;
+    }
+
+    ranges
+}
+
+fn is_list_item(line: &str) -> bool {
+    let item = line.trim_start();
+    ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| item.starts_with(marker))
+        || item.trim_end().len() == 1 && item.starts_with(['-', '*', '+'])
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Last line of the list starting at `start`: items and the indented lines
+/// continuing them, across single blank lines
+fn list_end(lines: &[&str], start: usize) -> usize {
+    let mut end = start;
+    let mut i = start + 1;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            let next = lines.get(i + 1).copied().unwrap_or_default();
+            if next.trim().is_empty() || !(is_list_item(next) || indentation(next) > 0) {
+                break;
+            }
+        } else if is_list_item(line) || indentation(line) > 0 {
+            end = i;
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    end
+}
+
+/// Items with nested lines fold down to their last nested line
+fn list_item_ranges(lines: &[&str], list: std::ops::Range<usize>, ranges: &mut Vec<FoldingRange>) {
+    for i in list.clone() {
+        if !is_list_item(lines[i]) {
+            continue;
+        }
+        let indent = indentation(lines[i]);
+        let end = (i + 1..list.end)
+            .take_while(|&j| lines[j].trim().is_empty() || indentation(lines[j]) > indent)
+            .filter(|&j| !lines[j].trim().is_empty())
+            .last();
+        if let Some(end) = end {
+            ranges.push(fold(i, end, None));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::TextDocumentIdentifier;
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    #[test]
+    fn folds_sections_and_blocks() {
+        let content = "---\ntitle: Folds\n---\n# Top\n\nIntro\n\n## Sub\n\n\
+```rust\nlet a = 1;\n```\n\n> [!note]\n> Callout\n\n\
+- one\n- two\n  - nested\n  - more\n\n<!--\nhidden\n-->\n\n# Next\n\n- a\n- b\n";
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, content);
+
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier {
+                uri: "file:///workspace/a.md".parse().unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let ranges: Vec<_> = process_folding_range(&mut ws.state, params)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect();
+
+        let region = Some(FoldingRangeKind::Region);
+        assert_eq!(
+            ranges,
+            vec![
+                (0, 2, region.clone()),
+                (3, 23, region.clone()),
+                (7, 23, region.clone()),
+                (9, 11, None),
+                (13, 14, None),
+                (16, 19, None),
+                (17, 19, None),
+                (21, 23, Some(FoldingRangeKind::Comment)),
+                (25, 28, region),
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_only_parsed_frontmatter() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, "---\n\nSome text\n\n---\n");
+        ws.add_file(
+            "/workspace/b.md",
+            1,
+            "---\ntitle: B\ntags: [a]\n---   \n\nText\n",
+        );
+
+        let fold_lines = |ws: &mut TestWorkspace, uri: &str| -> Vec<_> {
+            let params = FoldingRangeParams {
+                text_document: TextDocumentIdentifier {
+                    uri: uri.parse().unwrap(),
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            process_folding_range(&mut ws.state, params)
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .map(|range| (range.start_line, range.end_line))
+                .collect()
+        };
+
+        assert_eq!(fold_lines(&mut ws, "file:///workspace/a.md"), vec![]);
+        assert_eq!(fold_lines(&mut ws, "file:///workspace/b.md"), vec![(0, 3)]);
+    }
+}
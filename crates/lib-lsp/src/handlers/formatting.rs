@@ -0,0 +1,157 @@
+use gen_lsp_types::{
+    DocumentFormattingParams, DocumentRangeFormattingParams, FormattingOptions, TextEdit,
+};
+use lib_core::{
+    config::FormattingConfig,
+    format::{format_document, format_range},
+    text_buffer_conversions::TextBufferConversions,
+};
+use miette::{Context, Result};
+
+use crate::{get_document, server_state::ServerState, uri::UriExt};
+
+pub fn process_formatting(
+    lsp: &mut ServerState,
+    params: DocumentFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let source = document.content.to_string();
+    let config = formatting_config(&lsp.config.formatting, &params.options);
+    let formatted = format_document(&source, &config);
+    if formatted == source {
+        return Ok(Some(Vec::new()));
+    }
+
+    let content = document.content.slice(..);
+    let range = content.byte_to_lsp_range(&(0..content.byte_len()));
+    Ok(Some(vec![TextEdit::new(range, formatted)]))
+}
+
+pub fn process_range_formatting(
+    lsp: &mut ServerState,
+    params: DocumentRangeFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let source = document.content.to_string();
+    let content = document.content.slice(..);
+    let requested = content.lsp_to_byte_range(&params.range);
+
+    let config = formatting_config(&lsp.config.formatting, &params.options);
+    let Some(segment) = format_range(&source, requested, &config) else {
+        return Ok(Some(Vec::new()));
+    };
+    if segment.text == source[segment.range.clone()] {
+        return Ok(Some(Vec::new()));
+    }
+
+    let range = content.byte_to_lsp_range(&segment.range);
+    Ok(Some(vec![TextEdit::new(range, segment.text)]))
+}
+
+/// The configured formatting with the whitespace options the editor sent. Tab
+/// size and spaces are ignored, list indentation comes from the configuration.
+fn formatting_config(config: &FormattingConfig, options: &FormattingOptions) -> FormattingConfig {
+    FormattingConfig {
+        trim_trailing_whitespace: options
+            .trim_trailing_whitespace
+            .unwrap_or(config.trim_trailing_whitespace),
+        insert_final_newline: options
+            .insert_final_newline
+            .unwrap_or(config.insert_final_newline),
+        trim_final_newlines: options
+            .trim_final_newlines
+            .unwrap_or(config.trim_final_newlines),
+        ..config.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{FormattingOptions, Position, Range, TextDocumentIdentifier};
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    const CONTENT: &str = "#  Title\nText \n* one\n* two\n";
+
+    fn document() -> TextDocumentIdentifier {
+        TextDocumentIdentifier {
+            uri: "file:///workspace/a.md".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn formats_whole_document() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, CONTENT);
+
+        let params = DocumentFormattingParams {
+            text_document: document(),
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        };
+        let edits = process_formatting(&mut ws.state, params).unwrap().unwrap();
+
+        assert_eq!(
+            edits,
+            vec![TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(4, 0)),
+                "# Title\n\nText\n- one\n- two\n".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn follows_editor_whitespace_options() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, "Text  \n\n* one");
+
+        let params = DocumentFormattingParams {
+            text_document: document(),
+            options: FormattingOptions {
+                tab_size: 8,
+                trim_trailing_whitespace: Some(false),
+                insert_final_newline: Some(false),
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+        };
+        let edits = process_formatting(&mut ws.state, params).unwrap().unwrap();
+
+        assert_eq!(
+            edits,
+            vec![TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(2, 5)),
+                "Text  \n\n- one".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn formats_lines_in_range() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, CONTENT);
+
+        let params = DocumentRangeFormattingParams {
+            text_document: document(),
+            range: Range::new(Position::new(3, 1), Position::new(3, 2)),
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        };
+        let edits = process_range_formatting(&mut ws.state, params)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            edits,
+            vec![TextEdit::new(
+                Range::new(Position::new(3, 0), Position::new(4, 0)),
+                "- two\n".to_string()
+            )]
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use lib_core::{
-    document::{Document, references::ReferenceKind},
+    document::{
+        Document,
+        references::{Reference, ReferenceKind},
+    },
     path::slug::header_slug,
 };
 
@@ -56,10 +59,27 @@ fn find_definition<'a>(
         return Ok((target_doc, Range::default()));
     };
 
-    let target_content = header_text.strip_prefix('#').unwrap_or(header_text);
+    let reference = find_header(target_doc, header_text);
+
+    match reference {
+        Some(reference) => Ok((target_doc, reference.range)),
+        None => {
+            tracing::warn!(
+                "Header '#{}' not found in document '{}'. Falling back to file start.",
+                header_text.strip_prefix('#').unwrap_or(header_text),
+                target
+            );
+            Ok((target_doc, Range::default()))
+        }
+    }
+}
+
+/// The header of `document` a link fragment like `#My Header` or `#my-header` points to
+pub(crate) fn find_header<'a>(document: &'a Document, header: &str) -> Option<&'a Reference> {
+    let target_content = header.strip_prefix('#').unwrap_or(header);
     let normalized_target = header_slug(target_content);
 
-    let reference = target_doc.references.iter().find(|reference| {
+    document.references.iter().find(|reference| {
         let ReferenceKind::Header { content, .. } = &reference.kind else {
             return false;
         };
@@ -71,17 +91,5 @@ fn find_definition<'a>(
         let normalized_content = header_slug(content);
 
         normalized_content == target_content || normalized_content == normalized_target
-    });
-
-    match reference {
-        Some(reference) => Ok((target_doc, reference.range)),
-        None => {
-            tracing::warn!(
-                "Header '#{}' not found in document '{}'. Falling back to file start.",
-                target_content,
-                target
-            );
-            Ok((target_doc, Range::default()))
-        }
-    }
+    })
 }
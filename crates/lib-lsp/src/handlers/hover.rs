@@ -1,4 +1,4 @@
-use lib_core::document::references::ReferenceKind;
+use lib_core::document::{metadata::MetadataField, references::ReferenceKind};
 
 use gen_lsp_types::{Contents, Hover, HoverParams, MarkupContent, MarkupKind};
 use miette::{Context, Result};
@@ -39,8 +39,33 @@ pub fn process_hover(lsp: &mut ServerState, params: HoverParams) -> Result<Optio
             }
         },
         None => {
+            if let Some(field) = document.metadata.field_at_position(position) {
+                return Ok(Some(hover_metadata_field(lsp, field)));
+            }
+
             debug!("No reference found at position {:?}", position);
             Ok(None)
         }
     }
 }
+
+fn hover_metadata_field(lsp: &ServerState, field: &MetadataField) -> Hover {
+    let usages = lsp
+        .documents
+        .iter()
+        .filter(|doc| doc.metadata.get(&field.key).is_some())
+        .count();
+
+    let value = field.value.to_string_list().join(", ");
+
+    Hover {
+        contents: Contents::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!(
+                "**{}**: {}\n\nUsed in {} document(s)",
+                field.key, value, usages
+            ),
+        }),
+        range: Some(field.range),
+    }
+}
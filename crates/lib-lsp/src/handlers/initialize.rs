@@ -1,15 +1,22 @@
 use gen_lsp_types::{
-    ChangeNotifications, CodeActionOptions, CodeActionProvider, CompletionOptions,
-    DefinitionProvider, DiagnosticOptions, DiagnosticProvider, DocumentSymbolOptions,
-    DocumentSymbolProvider, FileOperationFilter, FileOperationOptions, FileOperationPattern,
-    FileOperationPatternKind, FileOperationRegistrationOptions, HoverProvider, InitializeParams,
-    InitializeResult, ReferenceOptions, ReferencesProvider, RenameOptions, RenameProvider,
-    ServerCapabilities, ServerInfo, TextDocumentSync, WorkspaceFoldersServerCapabilities,
-    WorkspaceOptions, WorkspaceSymbolOptions, WorkspaceSymbolProvider,
+    CallHierarchyProvider, ChangeNotifications, ClientCapabilities, CodeActionOptions,
+    CodeActionProvider, CodeLensOptions, CompletionOptions, DefinitionProvider, DiagnosticOptions,
+    DiagnosticProvider, DocumentFormattingProvider, DocumentLinkOptions,
+    DocumentRangeFormattingProvider, DocumentSymbolOptions, DocumentSymbolProvider,
+    ExecuteCommandOptions, FileOperationFilter, FileOperationOptions, FileOperationPattern,
+    FileOperationPatternKind, FileOperationRegistrationOptions, FoldingRangeProvider, Full,
+    HoverProvider, InitializeParams, InitializeResult, InlayHintProvider, PositionEncodingKind,
+    ReferenceOptions, ReferencesProvider, RenameOptions, RenameProvider, SelectionRangeProvider,
+    SemanticTokensOptions, SemanticTokensOptionsRange, SemanticTokensProvider, ServerCapabilities,
+    ServerInfo, TextDocumentSync, WorkspaceFoldersServerCapabilities, WorkspaceOptions,
+    WorkspaceSymbolOptions, WorkspaceSymbolProvider,
 };
 use miette::{IntoDiagnostic, Result};
 
-use crate::messages::{Request, Response};
+use crate::{
+    handlers::{execute_command::COMMANDS, semantic_tokens},
+    messages::{Request, Response},
+};
 
 pub fn process_initialize(request: Request) -> Result<(Response, InitializeParams)> {
     let initialize_params: InitializeParams =
@@ -28,13 +35,17 @@ pub fn process_initialize(request: Request) -> Result<(Response, InitializeParam
 
     let initialize_result = InitializeResult {
         capabilities: ServerCapabilities {
+            position_encoding: Some(position_encoding(&initialize_params.capabilities)),
             text_document_sync: Some(TextDocumentSync::Kind(
-                gen_lsp_types::TextDocumentSyncKind::Full,
+                gen_lsp_types::TextDocumentSyncKind::Incremental,
             )),
             hover_provider: Some(HoverProvider::Bool(true)),
             definition_provider: Some(DefinitionProvider::Bool(true)),
             code_action_provider: Some(CodeActionProvider::CodeActionOptions(CodeActionOptions {
-                code_action_kinds: Some(vec![gen_lsp_types::CodeActionKind::RefactorExtract]),
+                code_action_kinds: Some(vec![
+                    gen_lsp_types::CodeActionKind::RefactorExtract,
+                    gen_lsp_types::CodeActionKind::QuickFix,
+                ]),
                 ..Default::default()
             })),
             diagnostic_provider: Some(DiagnosticProvider::DiagnosticOptions(DiagnosticOptions {
@@ -73,6 +84,28 @@ pub fn process_initialize(request: Request) -> Result<(Response, InitializeParam
                     ..Default::default()
                 },
             )),
+            document_formatting_provider: Some(DocumentFormattingProvider::Bool(true)),
+            document_range_formatting_provider: Some(DocumentRangeFormattingProvider::Bool(true)),
+            folding_range_provider: Some(FoldingRangeProvider::Bool(true)),
+            selection_range_provider: Some(SelectionRangeProvider::Bool(true)),
+            document_link_provider: Some(DocumentLinkOptions {
+                resolve_provider: Some(true),
+                ..Default::default()
+            }),
+            semantic_tokens_provider: Some(SemanticTokensProvider::SemanticTokensOptions(
+                SemanticTokensOptions {
+                    legend: semantic_tokens::legend(),
+                    range: Some(SemanticTokensOptionsRange::Bool(true)),
+                    full: Some(Full::Bool(true)),
+                    ..Default::default()
+                },
+            )),
+            inlay_hint_provider: Some(InlayHintProvider::Bool(true)),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(true),
+                ..Default::default()
+            }),
+            call_hierarchy_provider: Some(CallHierarchyProvider::Bool(true)),
             rename_provider: Some(RenameProvider::RenameOptions(RenameOptions {
                 prepare_provider: Some(true),
                 ..Default::default()
@@ -84,9 +117,19 @@ pub fn process_initialize(request: Request) -> Result<(Response, InitializeParam
                     "[".to_string(),
                     ":".to_string(),
                     "(".to_string(),
+                    "/".to_string(),
                 ]),
                 ..Default::default()
             }),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: COMMANDS.iter().map(|command| command.to_string()).collect(),
+                ..Default::default()
+            }),
+            // Custom requests beyond the LSP spec
+            experimental: Some(serde_json::json!({
+                "tasksProvider": true,
+                "tagHierarchyProvider": true,
+            })),
             ..Default::default()
         },
         server_info: Some(ServerInfo {
@@ -103,3 +146,20 @@ pub fn process_initialize(request: Request) -> Result<(Response, InitializeParam
         initialize_params,
     ))
 }
+
+/// Positions count chars throughout the server, which is UTF-32 to the
+/// client. Clients that only know the default UTF-16 get their document
+/// changes converted, see [`crate::handlers::did_change`].
+pub(crate) fn position_encoding(capabilities: &ClientCapabilities) -> PositionEncodingKind {
+    let offers_utf32 = capabilities
+        .general
+        .as_ref()
+        .and_then(|general| general.position_encodings.as_ref())
+        .is_some_and(|encodings| encodings.contains(&PositionEncodingKind::UTF32));
+
+    if offers_utf32 {
+        PositionEncodingKind::UTF32
+    } else {
+        PositionEncodingKind::UTF16
+    }
+}
@@ -0,0 +1,154 @@
+use gen_lsp_types::{InlayHint, InlayHintParams, Label, Position};
+use lib_core::{
+    document::{Document, references::ReferenceKind},
+    render::is_external,
+    text_buffer_conversions::TextBufferConversions,
+};
+use miette::{Context, Result};
+
+use crate::{
+    get_document, handlers::link_resolver::resolve_linked_note,
+    helpers::references::ReferenceCollector, server_state::ServerState, uri::UriExt,
+};
+
+pub fn process_inlay_hint(
+    lsp: &mut ServerState,
+    params: InlayHintParams,
+) -> Result<Option<Vec<InlayHint>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+    let config = &lsp.config.inlay_hints;
+    let visible = params.range.start.line..=params.range.end.line;
+
+    let mut hints = Vec::new();
+    for reference in &document.references {
+        if !visible.contains(&reference.range.start.line) {
+            continue;
+        }
+
+        match &reference.kind {
+            ReferenceKind::WikiLink { target, alias, .. } if config.link_titles => {
+                let shown = alias.as_deref().unwrap_or(target);
+                if let Some(title) = link_title(lsp, document, target, shown) {
+                    hints.push(hint(reference.range.end, title));
+                }
+            }
+            ReferenceKind::Link {
+                target, alt_text, ..
+            } if config.link_titles && !is_external(target) => {
+                if let Some(title) = link_title(lsp, document, target, alt_text) {
+                    hints.push(hint(reference.range.end, title));
+                }
+            }
+            ReferenceKind::Header { .. } if config.backlink_counts => {
+                let backlinks = ReferenceCollector::new(document, &uri, reference, lsp)
+                    .collect_from(&lsp.documents)
+                    .len();
+                if backlinks > 0 {
+                    let line = reference.range.start.line;
+                    hints.push(hint(line_end(document, line), format!("← {backlinks}")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Some(hints))
+}
+
+fn hint(position: Position, label: String) -> InlayHint {
+    InlayHint {
+        position,
+        label: Label::String(label),
+        kind: None,
+        text_edits: None,
+        tooltip: None,
+        padding_left: Some(true),
+        padding_right: None,
+        data: None,
+    }
+}
+
+/// Title of the note `target` links to, unless the link already shows it
+fn link_title(lsp: &ServerState, document: &Document, target: &str, shown: &str) -> Option<String> {
+    if target.is_empty() {
+        return None;
+    }
+
+    let (_, note) = resolve_linked_note(lsp, document, target).ok()?;
+    let title = note?.title()?;
+    (!title.eq_ignore_ascii_case(shown.trim())).then_some(title)
+}
+
+fn line_end(document: &Document, line: u32) -> Position {
+    let text = document.content.line(line as usize).to_string();
+    let end =
+        document.content.line_to_byte(line as usize) + text.trim_end_matches(['\n', '\r']).len();
+    document.content.slice(..).byte_offset_to_position(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{Range, TextDocumentIdentifier};
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn hints(ws: &mut TestWorkspace, path: &str) -> Vec<(u32, u32, String)> {
+        let params = InlayHintParams {
+            text_document: TextDocumentIdentifier {
+                uri: format!("file://{path}").parse().unwrap(),
+            },
+            range: Range::new(Position::new(0, 0), Position::new(100, 0)),
+            work_done_progress_params: Default::default(),
+        };
+
+        process_inlay_hint(&mut ws.state, params)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|hint| {
+                let Label::String(label) = hint.label else {
+                    panic!("expected a string label");
+                };
+                (hint.position.line, hint.position.character, label)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shows_titles_and_backlinks() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/index.md",
+            1,
+            "# 🚀 Index\n\n[[2024-03-01-1234]] [[Plain|Plain title]] [x](2024-03-01-1234.md)\n",
+        )
+        .add_file(
+            "/workspace/2024-03-01-1234.md",
+            1,
+            "---\ntitle: Meeting notes\n---\n# Agenda\n",
+        )
+        .add_file("/workspace/Plain.md", 1, "# Plain title\n")
+        .add_file(
+            "/workspace/other.md",
+            1,
+            "[[index#🚀 Index]] and [[index]]\n",
+        );
+
+        assert_eq!(
+            hints(&mut ws, "/workspace/index.md"),
+            vec![
+                (2, 19, "Meeting notes".to_string()),
+                (2, 65, "Meeting notes".to_string()),
+            ]
+        );
+
+        ws.state.config.inlay_hints.link_titles = false;
+        ws.state.config.inlay_hints.backlink_counts = true;
+        assert_eq!(
+            hints(&mut ws, "/workspace/index.md"),
+            vec![(0, 9, "← 1".to_string())]
+        );
+    }
+}
@@ -1,15 +1,26 @@
+pub mod call_hierarchy;
 pub mod code_action;
+pub mod code_lens;
 pub mod completion;
 pub mod diagnostics;
 pub mod did_change;
 pub mod did_close;
 pub mod did_open;
+pub mod document_link;
 pub mod document_symbol;
+pub mod execute_command;
+pub mod folding_range;
+pub mod formatting;
 pub mod goto_definition;
 pub mod hover;
 pub mod initialize;
+pub mod inlay_hint;
 pub mod link_resolver;
 pub mod references;
 pub mod rename;
+pub mod selection_range;
+pub mod semantic_tokens;
+pub mod tags;
+pub mod tasks;
 pub mod will_create;
 pub mod workspace_symbol;
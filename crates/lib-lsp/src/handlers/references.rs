@@ -1,4 +1,5 @@
-use gen_lsp_types::{Location, ReferenceParams};
+use gen_lsp_types::{Location, ReferenceParams, Uri};
+use lib_core::document::{Document, references::Reference};
 use miette::{Context, Result};
 
 use crate::{
@@ -15,11 +16,7 @@ pub fn process_references(
     let document = get_document!(lsp, &uri);
     let reference_at_position = document.get_reference_at_position(position);
 
-    let mut reference_locations = if let Some(reference) = reference_at_position {
-        ReferenceCollector::new(document, &uri, reference, lsp).collect_from(&lsp.documents)
-    } else {
-        ReferenceCollector::collect_file_reference_locations(lsp, &uri)
-    };
+    let mut reference_locations = reference_locations(lsp, document, &uri, reference_at_position);
 
     // Include the hovered reference itself if requested
     if params.context.include_declaration
@@ -31,3 +28,68 @@ pub fn process_references(
 
     Ok(Some(reference_locations))
 }
+
+/// Locations referring to `reference`, or to the document itself without one
+pub(crate) fn reference_locations(
+    lsp: &ServerState,
+    document: &Document,
+    uri: &Uri,
+    reference: Option<&Reference>,
+) -> Vec<Location> {
+    match reference {
+        Some(reference) => {
+            ReferenceCollector::new(document, uri, reference, lsp).collect_from(&lsp.documents)
+        }
+        None => ReferenceCollector::collect_file_reference_locations(lsp, uri),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{
+        Position, ReferenceContext, TextDocumentIdentifier, TextDocumentPositionParams,
+    };
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn tag_references(ws: &mut TestWorkspace) -> Vec<u32> {
+        let params = ReferenceParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: "file:///workspace/a.md".parse().unwrap(),
+                },
+                position: Position::new(0, 2),
+            },
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let mut lines: Vec<_> = process_references(&mut ws.state, params)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|location| location.range.start.line)
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn tag_references_include_nested_tags() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, "#project\n").add_file(
+            "/workspace/b.md",
+            1,
+            "#project\n\n#Project/alpha\n\n#projects\n\n#other",
+        );
+
+        assert_eq!(tag_references(&mut ws), vec![0, 2]);
+
+        ws.state.config.markdown.nested_tag_references = false;
+        assert_eq!(tag_references(&mut ws), vec![0]);
+    }
+}
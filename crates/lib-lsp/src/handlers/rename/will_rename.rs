@@ -4,9 +4,10 @@ use gen_lsp_types::{RenameFilesParams, TextEdit, Uri, WorkspaceEdit};
 use lib_core::{
     document::{
         Document,
-        references::{Reference, ReferenceKind},
+        references::{Reference, ReferenceKind, retarget_link},
     },
     path::{find_relative_path, resolve_reference_target},
+    text_buffer_conversions::TextBufferConversions,
 };
 use miette::{IntoDiagnostic, Result};
 
@@ -38,7 +39,6 @@ pub fn process_will_rename_files(
         // update references connected to the changed file
         for (doc, reference) in find_references_to_uri(lsp, &old_uri) {
             let new_rel = find_relative_path(&doc.path, &new_path)?;
-            let new_ref = create_reference_with_new_uri(reference, new_rel);
 
             let Some(doc_uri) = Uri::from_file_path(&doc.path) else {
                 tracing::debug!("Failed to convert path to URI: {:?}", doc.path);
@@ -48,7 +48,7 @@ pub fn process_will_rename_files(
             changes
                 .entry(doc_uri)
                 .or_default()
-                .push(TextEdit::new(reference.range, new_ref.to_file_text()));
+                .push(link_edit(doc, reference, new_rel));
         }
 
         // update references in the moved file
@@ -60,8 +60,7 @@ pub fn process_will_rename_files(
                 .filter_map(|reference| {
                     let resolved = resolve_reference_target(&old_path, reference).ok()?;
                     let new_rel = find_relative_path(&new_path, resolved).ok()?;
-                    let new_ref = create_reference_with_new_uri(reference, new_rel);
-                    Some(TextEdit::new(reference.range, new_ref.to_file_text()))
+                    Some(link_edit(doc, reference, new_rel))
                 })
             {
                 changes.entry(new_uri.clone()).or_default().push(edit);
@@ -75,6 +74,19 @@ pub fn process_will_rename_files(
     }))
 }
 
+/// Rewrites the target of a link, keeping the rest of its source text
+fn link_edit(document: &Document, reference: &Reference, new_target: String) -> TextEdit {
+    let content = document.content.slice(..);
+    let raw = content
+        .get_byte_slice(content.lsp_to_byte_range(&reference.range))
+        .map(|raw| raw.to_string())
+        .unwrap_or_default();
+
+    let new_text = retarget_link(&raw, &new_target)
+        .unwrap_or_else(|| create_reference_with_new_uri(reference, new_target).to_file_text());
+    TextEdit::new(reference.range, new_text)
+}
+
 fn create_reference_with_new_uri(reference: &Reference, new_target: String) -> Reference {
     let ref_kind = match reference.kind.clone() {
         ReferenceKind::WikiLink { alias, header, .. } => ReferenceKind::WikiLink {
@@ -163,4 +175,33 @@ mod tests {
         assert_eq!(edits.len(), 1);
         assert_eq!(edits[0].new_text, "[notes](../notes.md)");
     }
+
+    #[test]
+    fn rename_keeps_escapes_around_the_target() {
+        let mut ws = TestWorkspace::new();
+
+        ws.add_file(
+            "/workspace/notes.md",
+            1,
+            "[[target.md#C&#35; tips|\\#1 &amp; more]] [C\\# &#35;](./target.md)",
+        )
+        .add_file("/workspace/target.md", 1, "# C# tips");
+
+        let changes = ws.rename("target.md", "renamed.md");
+
+        let mut edits: Vec<_> = changes
+            .get("/workspace/notes.md")
+            .unwrap()
+            .iter()
+            .map(|edit| edit.new_text.as_str())
+            .collect();
+        edits.sort();
+        assert_eq!(
+            edits,
+            vec![
+                "[C\\# &#35;](./renamed.md)",
+                "[[./renamed.md#C&#35; tips|\\#1 &amp; more]]"
+            ]
+        );
+    }
 }
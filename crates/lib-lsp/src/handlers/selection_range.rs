@@ -0,0 +1,360 @@
+use std::ops::Range;
+
+use gen_lsp_types::{SelectionRange, SelectionRangeParams};
+use lib_core::text_buffer_conversions::TextBufferConversions;
+use lib_parser::{
+    InlineMarkdownNode, MarkdownNode, MarkdownText, Parser, Spanned, markdown_parser_with,
+};
+use miette::{Context, Result};
+
+use crate::{get_document, server_state::ServerState, uri::UriExt};
+
+pub fn process_selection_range(
+    lsp: &mut ServerState,
+    params: SelectionRangeParams,
+) -> Result<Option<Vec<SelectionRange>>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let source = document.content.to_string();
+    let Some(parsed) = markdown_parser_with(document.parse_options)
+        .parse(&source)
+        .into_output()
+    else {
+        return Ok(None);
+    };
+
+    let content = document.content.slice(..);
+    let selections = params
+        .positions
+        .into_iter()
+        .map(|position| {
+            let offset = content
+                .try_position_to_byte_offset(position)
+                .unwrap_or(source.len());
+            let chain = selection_chain(&source, &parsed.body, offset);
+
+            // Built from the outermost range, each becoming the parent of the next
+            let mut selection: Option<SelectionRange> = None;
+            for range in chain.iter().rev() {
+                selection = Some(SelectionRange::new(
+                    content.byte_to_lsp_range(range),
+                    selection.map(Box::new),
+                ));
+            }
+            selection.unwrap_or_else(|| {
+                SelectionRange::new(content.byte_to_lsp_range(&(offset..offset)), None)
+            })
+        })
+        .collect();
+
+    Ok(Some(selections))
+}
+
+/// Byte ranges around `offset`, innermost first: the word, the inline element,
+/// the sentence, the block, the lists of a list item and the header sections
+fn selection_chain(
+    source: &str,
+    body: &[Spanned<MarkdownNode>],
+    offset: usize,
+) -> Vec<Range<usize>> {
+    let mut chain = Vec::new();
+    let block_range = |block: &Spanned<MarkdownNode>| trimmed(source, block.1.into_range());
+
+    let block_index = body.iter().position(|block| {
+        let range = block_range(block);
+        range.start <= offset && offset <= range.end
+    });
+
+    if let Some(index) = block_index {
+        let block = &body[index];
+        match &block.0 {
+            MarkdownNode::Paragraph(content)
+            | MarkdownNode::ListItem { content, .. }
+            | MarkdownNode::FootnoteDefinition { content, .. } => {
+                inline_chain(source, content, offset, &mut chain);
+                chain.extend(sentence(source, content, offset));
+            }
+            MarkdownNode::Header { .. } | MarkdownNode::Invalid => {
+                chain.extend(word(source, block_range(block), offset));
+            }
+        }
+        chain.push(block_range(block));
+
+        if matches!(block.0, MarkdownNode::ListItem { .. }) {
+            list_chain(source, body, index, &mut chain);
+        }
+    }
+
+    // Sections containing the offset, nested ones start later
+    let mut sections: Vec<Range<usize>> = body
+        .iter()
+        .enumerate()
+        .filter_map(|(i, block)| {
+            let MarkdownNode::Header { level, .. } = block.0 else {
+                return None;
+            };
+            let last = body[i + 1..]
+                .iter()
+                .take_while(
+                    |next| !matches!(next.0, MarkdownNode::Header { level: l, .. } if l <= level),
+                )
+                .last()
+                .unwrap_or(block);
+            Some(block.1.start..block_range(last).end)
+        })
+        .filter(|section| section.start <= offset && offset <= section.end)
+        .collect();
+    sections.sort_by_key(|section| std::cmp::Reverse(section.start));
+    chain.extend(sections);
+
+    // Every range must contain the one before it
+    let mut nested: Vec<Range<usize>> = Vec::with_capacity(chain.len());
+    for range in chain {
+        let contains_last = nested.last().is_none_or(|last| {
+            range.start <= last.start && last.end <= range.end && range != *last
+        });
+        if contains_last {
+            nested.push(range);
+        }
+    }
+    nested
+}
+
+/// `range` without trailing whitespace
+fn trimmed(source: &str, range: Range<usize>) -> Range<usize> {
+    range.start..range.start + source[range].trim_end().len()
+}
+
+/// The word, then the inline elements around `offset`
+fn inline_chain(
+    source: &str,
+    content: &MarkdownText,
+    offset: usize,
+    chain: &mut Vec<Range<usize>>,
+) {
+    let containing =
+        |node: &&Spanned<InlineMarkdownNode>| node.1.start <= offset && offset < node.1.end;
+    let Some(node) = content
+        .iter()
+        .find(containing)
+        .or_else(|| content.iter().find(|node| node.1.end == offset))
+    else {
+        return;
+    };
+
+    if let InlineMarkdownNode::InlineField { content, .. } = &node.0
+        && content
+            .iter()
+            .any(|inner| inner.1.start <= offset && offset <= inner.1.end)
+    {
+        inline_chain(source, content, offset, chain);
+    } else {
+        chain.extend(word(source, node.1.into_range(), offset));
+    }
+
+    if !matches!(node.0, InlineMarkdownNode::PlainText(_)) {
+        chain.push(node.1.into_range());
+    }
+}
+
+/// Letters, digits and `_` around `offset`, within `bounds`
+fn word(source: &str, bounds: Range<usize>, offset: usize) -> Option<Range<usize>> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let text = &source[bounds.clone()];
+    let offset = offset.checked_sub(bounds.start)?.min(text.len());
+
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = offset
+        + text[offset..]
+            .chars()
+            .take_while(|c| is_word(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+
+    (start < end).then(|| bounds.start + start..bounds.start + end)
+}
+
+/// The sentence around `offset`, ended by `.`, `!` or `?` in plain text
+fn sentence(source: &str, content: &MarkdownText, offset: usize) -> Option<Range<usize>> {
+    let start = content.first()?.1.start;
+    let end = trimmed(source, start..content.last()?.1.end).end;
+
+    let mut sentences = Vec::new();
+    let mut sentence_start = start;
+    for node in content {
+        let InlineMarkdownNode::PlainText(text) = node.0 else {
+            continue;
+        };
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let ends_sentence = matches!(c, '.' | '!' | '?')
+                && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+            if ends_sentence {
+                let sentence_end = node.1.start + i + 1;
+                sentences.push(sentence_start..sentence_end);
+                let rest = &source[sentence_end..end.max(sentence_end)];
+                sentence_start = sentence_end + (rest.len() - rest.trim_start().len());
+            }
+        }
+    }
+    if sentence_start < end {
+        sentences.push(sentence_start..end);
+    }
+
+    sentences
+        .into_iter()
+        .find(|sentence| sentence.start <= offset && offset <= sentence.end)
+}
+
+/// For the list item at `index`: the items at its level, then each parent
+/// item with its nested items and the items at the parent's level
+fn list_chain(
+    source: &str,
+    body: &[Spanned<MarkdownNode>],
+    index: usize,
+    chain: &mut Vec<Range<usize>>,
+) {
+    let is_item = |i: usize| matches!(body[i].0, MarkdownNode::ListItem { .. });
+    let first = (0..index)
+        .rev()
+        .take_while(|&i| is_item(i))
+        .last()
+        .unwrap_or(index);
+    let last = (index + 1..body.len())
+        .take_while(|&i| is_item(i))
+        .last()
+        .unwrap_or(index);
+    let indent = |i: usize| {
+        let start = body[i].1.start;
+        start - source[..start].rfind('\n').map_or(0, |newline| newline + 1)
+    };
+    let range =
+        |from: usize, to: usize| body[from].1.start..trimmed(source, body[to].1.into_range()).end;
+
+    let mut current = index;
+    loop {
+        let level = indent(current);
+        if current != index {
+            let nested_end = (current + 1..=last)
+                .take_while(|&i| indent(i) > level)
+                .last()
+                .unwrap_or(current);
+            chain.push(range(current, nested_end));
+        }
+
+        let level_start = (first..current)
+            .rev()
+            .take_while(|&i| indent(i) >= level)
+            .last();
+        let level_end = (current + 1..=last)
+            .take_while(|&i| indent(i) >= level)
+            .last();
+        chain.push(range(
+            level_start.unwrap_or(current),
+            level_end.unwrap_or(current),
+        ));
+
+        match (first..current).rev().find(|&i| indent(i) < level) {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{Position, TextDocumentIdentifier};
+    use lib_parser::markdown_parser;
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn chain<'s>(source: &'s str, at: &str) -> Vec<&'s str> {
+        let parsed = markdown_parser().parse(source).into_output().unwrap();
+        let offset = source.find(at).unwrap();
+        selection_chain(source, &parsed.body, offset)
+            .into_iter()
+            .map(|range| &source[range])
+            .collect()
+    }
+
+    const SOURCE: &str = "# Top\n\n## Notes\n\nFirst one. See [[Other Note]] now!\n\n\
+- Item\n  - Nested [link](target.md)\n  - Sibling\n- Last\n\n## Next\n";
+
+    #[test]
+    fn test_selection_chain() {
+        assert_eq!(
+            chain(SOURCE, "Note]]"),
+            vec![
+                "Note",
+                "[[Other Note]]",
+                "See [[Other Note]] now!",
+                "First one. See [[Other Note]] now!",
+                "## Notes\n\nFirst one. See [[Other Note]] now!\n\n\
+- Item\n  - Nested [link](target.md)\n  - Sibling\n- Last",
+                "# Top\n\n## Notes\n\nFirst one. See [[Other Note]] now!\n\n\
+- Item\n  - Nested [link](target.md)\n  - Sibling\n- Last\n\n## Next",
+            ]
+        );
+
+        assert_eq!(
+            chain(SOURCE, "ink]("),
+            vec![
+                "link",
+                "[link](target.md)",
+                "Nested [link](target.md)",
+                "- Nested [link](target.md)",
+                "- Nested [link](target.md)\n  - Sibling",
+                "- Item\n  - Nested [link](target.md)\n  - Sibling",
+                "- Item\n  - Nested [link](target.md)\n  - Sibling\n- Last",
+                "## Notes\n\nFirst one. See [[Other Note]] now!\n\n\
+- Item\n  - Nested [link](target.md)\n  - Sibling\n- Last",
+                "# Top\n\n## Notes\n\nFirst one. See [[Other Note]] now!\n\n\
+- Item\n  - Nested [link](target.md)\n  - Sibling\n- Last\n\n## Next",
+            ]
+        );
+    }
+
+    #[test]
+    fn selection_ranges_nest() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, SOURCE);
+
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier {
+                uri: "file:///workspace/a.md".parse().unwrap(),
+            },
+            positions: vec![Position::new(4, 2), Position::new(5, 0)],
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let selections = process_selection_range(&mut ws.state, params)
+            .unwrap()
+            .unwrap();
+
+        let mut depth = 0;
+        let mut selection = Some(&selections[0]);
+        while let Some(current) = selection {
+            if let Some(parent) = &current.parent {
+                assert!(parent.range.start <= current.range.start);
+                assert!(current.range.end <= parent.range.end);
+            }
+            depth += 1;
+            selection = current.parent.as_deref();
+        }
+        assert_eq!(depth, 5);
+
+        // Between blocks only the sections remain
+        assert_eq!(selections[1].range.start, Position::new(2, 0));
+        assert_eq!(
+            selections[1].parent.as_ref().unwrap().range.start,
+            Position::new(0, 0)
+        );
+    }
+}
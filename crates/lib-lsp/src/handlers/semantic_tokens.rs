@@ -0,0 +1,395 @@
+use std::ops::Range;
+
+use gen_lsp_types::{
+    SemanticToken, SemanticTokens, SemanticTokensLegend, SemanticTokensParams,
+    SemanticTokensRangeParams,
+};
+use lib_core::{
+    document::Document, render::is_external, text_buffer_conversions::TextBufferConversions,
+};
+use lib_parser::{
+    LinkType, MarkdownText, Parser, SimpleSpan, Spanned, TaskState, markdown_parser_with,
+    slice_span, unescape,
+    visit::{Visitor, walk_text},
+    yaml::{Frontmatter, Yaml},
+};
+use miette::{Context, Result};
+
+use crate::{
+    get_document, handlers::link_resolver::resolve_linked_note, server_state::ServerState,
+    uri::UriExt,
+};
+
+/// Token types of the legend, indexed by [`TokenType`]
+pub const TOKEN_TYPES: [&str; 9] = [
+    "heading", "wikilink", "link", "embed", "tag", "footnote", "task", "property", "string",
+];
+
+/// Token modifiers of the legend, bit `i` of a token's modifiers is entry `i`
+pub const TOKEN_MODIFIERS: [&str; 9] = [
+    "level1",
+    "level2",
+    "level3",
+    "level4",
+    "level5",
+    "level6",
+    "unresolved",
+    "declaration",
+    "done",
+];
+
+const UNRESOLVED: u32 = 1 << 6;
+const DECLARATION: u32 = 1 << 7;
+const DONE: u32 = 1 << 8;
+
+#[derive(Debug, Clone, Copy)]
+enum TokenType {
+    Heading,
+    WikiLink,
+    Link,
+    Embed,
+    Tag,
+    Footnote,
+    Task,
+    /// Frontmatter key
+    Property,
+    /// Frontmatter value
+    String,
+}
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend::new(
+        TOKEN_TYPES.map(String::from).to_vec(),
+        TOKEN_MODIFIERS.map(String::from).to_vec(),
+    )
+}
+
+pub fn process_semantic_tokens(
+    lsp: &mut ServerState,
+    params: SemanticTokensParams,
+) -> Result<Option<SemanticTokens>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    Ok(semantic_tokens(lsp, document, None))
+}
+
+pub fn process_semantic_tokens_range(
+    lsp: &mut ServerState,
+    params: SemanticTokensRangeParams,
+) -> Result<Option<SemanticTokens>> {
+    let uri = params.text_document.uri;
+    let document = get_document!(lsp, &uri);
+
+    let lines = params.range.start.line..=params.range.end.line;
+    Ok(semantic_tokens(lsp, document, Some(lines)))
+}
+
+/// Tokens of `document`, only those on `lines` if given
+fn semantic_tokens(
+    lsp: &ServerState,
+    document: &Document,
+    lines: Option<std::ops::RangeInclusive<u32>>,
+) -> Option<SemanticTokens> {
+    let source = document.content.to_string();
+    let parsed = markdown_parser_with(document.parse_options)
+        .parse(&source)
+        .into_output()?;
+
+    let mut collector = TokenCollector {
+        lsp,
+        document,
+        source: &source,
+        tokens: Vec::new(),
+    };
+    collector.visit_markdown(&parsed);
+
+    let content = document.content.slice(..);
+    let mut tokens: Vec<_> = collector
+        .tokens
+        .into_iter()
+        .flat_map(|(range, token_type, modifiers)| {
+            line_ranges(&source, range)
+                .map(move |range| (content.byte_to_lsp_range(&range), token_type, modifiers))
+        })
+        .filter(|(range, ..)| {
+            lines
+                .as_ref()
+                .is_none_or(|lines| lines.contains(&range.start.line))
+        })
+        .collect();
+    tokens.sort_by_key(|(range, ..)| (range.start.line, range.start.character));
+
+    // Positions are relative to the previous token
+    let mut previous = gen_lsp_types::Position::new(0, 0);
+    let data = tokens
+        .into_iter()
+        .map(|(range, token_type, modifiers)| {
+            let delta_line = range.start.line - previous.line;
+            let delta_start = if delta_line == 0 {
+                range.start.character - previous.character
+            } else {
+                range.start.character
+            };
+            previous = range.start;
+
+            SemanticToken {
+                delta_line,
+                delta_start,
+                length: range.end.character - range.start.character,
+                token_type: token_type as u32,
+                token_modifiers_bitset: modifiers,
+            }
+        })
+        .collect();
+
+    Some(SemanticTokens::new(None, data))
+}
+
+/// `range` split at line breaks, since tokens cannot span lines
+fn line_ranges(source: &str, range: Range<usize>) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = range.start;
+    source[range].split_inclusive('\n').filter_map(move |line| {
+        let piece = start..start + line.trim_end_matches(['\n', '\r']).len();
+        start += line.len();
+        (!piece.is_empty()).then_some(piece)
+    })
+}
+
+struct TokenCollector<'s> {
+    lsp: &'s ServerState,
+    document: &'s Document,
+    source: &'s str,
+    tokens: Vec<(Range<usize>, TokenType, u32)>,
+}
+
+impl TokenCollector<'_> {
+    fn push(&mut self, range: Range<usize>, token_type: TokenType, modifiers: u32) {
+        self.tokens.push((range, token_type, modifiers));
+    }
+
+    fn push_slice(&mut self, slice: &str, token_type: TokenType) {
+        if let Some(span) = slice_span(self.source, slice) {
+            self.push(span.into_range(), token_type, 0);
+        }
+    }
+
+    /// Whether a link target points to a note or a file that exists
+    fn is_resolved(&self, target: &str) -> bool {
+        if target.is_empty() {
+            return true;
+        }
+
+        match resolve_linked_note(self.lsp, self.document, target) {
+            Ok((_, Some(_))) => true,
+            Ok((path, None)) => path.is_file(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for TokenCollector<'_> {
+    fn visit_frontmatter(&mut self, frontmatter: &Frontmatter<'a>) {
+        for (key, value) in &frontmatter.0 {
+            self.push_slice(key, TokenType::Property);
+            match value {
+                Yaml::String(value) => self.push_slice(value, TokenType::String),
+                Yaml::List(items) => {
+                    for item in items {
+                        self.push_slice(item, TokenType::String);
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_header(&mut self, level: usize, _content: &'a str, span: SimpleSpan) {
+        let text = self.source[span.into_range()].trim_end();
+        let modifiers = 1 << (level.clamp(1, 6) - 1);
+        self.push(
+            span.start..span.start + text.len(),
+            TokenType::Heading,
+            modifiers,
+        );
+    }
+
+    fn visit_list_item(
+        &mut self,
+        checkbox: Option<&Spanned<TaskState>>,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        if let Some(Spanned(state, span)) = checkbox {
+            let modifiers = if state.is_closed() { DONE } else { 0 };
+            self.push(span.into_range(), TokenType::Task, modifiers);
+        }
+        walk_text(self, content);
+    }
+
+    fn visit_footnote_definition(
+        &mut self,
+        _id: &'a str,
+        content: &MarkdownText<'a>,
+        span: SimpleSpan,
+    ) {
+        // The `[^id]:` label
+        if let Some(end) = self.source[span.into_range()].find("]:") {
+            self.push(
+                span.start..span.start + end + 2,
+                TokenType::Footnote,
+                DECLARATION,
+            );
+        }
+        walk_text(self, content);
+    }
+
+    fn visit_link(&mut self, link: &LinkType<'a>, span: SimpleSpan) {
+        let mut range = span.into_range();
+        let (token_type, target) = match link {
+            // `![[note]]` embeds, the `!` is left to the surrounding text
+            LinkType::WikiLink { target, .. } if is_embed(self.source, range.start) => {
+                range.start -= 1;
+                (TokenType::Embed, *target)
+            }
+            LinkType::WikiLink { target, .. } => (TokenType::WikiLink, *target),
+            LinkType::InlineLink { uri, .. } => (TokenType::Link, *uri),
+            LinkType::ImageLink { uri, .. } => (TokenType::Embed, *uri),
+        };
+
+        let target = unescape(target);
+        let resolved = is_external(&target) || self.is_resolved(&target);
+        let modifiers = if resolved { 0 } else { UNRESOLVED };
+        self.push(range, token_type, modifiers);
+    }
+
+    fn visit_tag(&mut self, _name: &'a str, span: SimpleSpan) {
+        self.push(span.into_range(), TokenType::Tag, 0);
+    }
+
+    fn visit_footnote(&mut self, _id: &'a str, span: SimpleSpan) {
+        self.push(span.into_range(), TokenType::Footnote, 0);
+    }
+}
+
+/// Whether the wikilink starting at byte `start` is preceded by an unescaped `!`
+fn is_embed(source: &str, start: usize) -> bool {
+    let before = &source[..start];
+    before.ends_with('!') && !before.ends_with("\\!")
+}
+
+#[cfg(test)]
+mod tests {
+    use gen_lsp_types::{Position, Range, TextDocumentIdentifier};
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    /// `(line, character, length, type, modifiers)` of each token
+    fn decode(tokens: SemanticTokens) -> Vec<(u32, u32, u32, &'static str, Vec<&'static str>)> {
+        let mut position = (0, 0);
+        tokens
+            .data
+            .into_iter()
+            .map(|token| {
+                position = if token.delta_line == 0 {
+                    (position.0, position.1 + token.delta_start)
+                } else {
+                    (position.0 + token.delta_line, token.delta_start)
+                };
+                let modifiers = (0..TOKEN_MODIFIERS.len())
+                    .filter(|bit| token.token_modifiers_bitset & (1 << bit) != 0)
+                    .map(|bit| TOKEN_MODIFIERS[bit])
+                    .collect();
+                (
+                    position.0,
+                    position.1,
+                    token.length,
+                    TOKEN_TYPES[token.token_type as usize],
+                    modifiers,
+                )
+            })
+            .collect()
+    }
+
+    fn document() -> TextDocumentIdentifier {
+        TextDocumentIdentifier {
+            uri: "file:///workspace/a.md".parse().unwrap(),
+        }
+    }
+
+    const CONTENT: &str = "---\ntitle: Hi\n---\n## Notes\n\n\
+See [[b]] and [[missing]] ![pic](b.md) #tag[^1]\n\n- [x] Done [c](c.md)\n\n[^1]: Note\n";
+
+    #[test]
+    fn classifies_wikilink_embeds() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/a.md",
+            1,
+            "![[b]] and \\![[b]] ![[missing#Part]]\n",
+        )
+        .add_file("/workspace/b.md", 1, "b\n");
+
+        let params = SemanticTokensParams {
+            text_document: document(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let tokens = process_semantic_tokens(&mut ws.state, params)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            decode(tokens),
+            vec![
+                (0, 0, 6, "embed", vec![]),
+                (0, 13, 5, "wikilink", vec![]),
+                (0, 19, 17, "embed", vec!["unresolved"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn classifies_markdown_structure() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file("/workspace/a.md", 1, CONTENT)
+            .add_file("/workspace/b.md", 1, "b\n");
+
+        let params = SemanticTokensParams {
+            text_document: document(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let tokens = process_semantic_tokens(&mut ws.state, params)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            decode(tokens),
+            vec![
+                (1, 0, 5, "property", vec![]),
+                (1, 7, 2, "string", vec![]),
+                (3, 0, 8, "heading", vec!["level2"]),
+                (5, 4, 5, "wikilink", vec![]),
+                (5, 14, 11, "wikilink", vec!["unresolved"]),
+                (5, 26, 12, "embed", vec![]),
+                (5, 39, 4, "tag", vec![]),
+                (5, 43, 4, "footnote", vec![]),
+                (7, 2, 3, "task", vec!["done"]),
+                (7, 11, 9, "link", vec!["unresolved"]),
+                (9, 0, 5, "footnote", vec!["declaration"]),
+            ]
+        );
+
+        let params = SemanticTokensRangeParams {
+            text_document: document(),
+            range: Range::new(Position::new(7, 0), Position::new(7, 5)),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let tokens = process_semantic_tokens_range(&mut ws.state, params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(decode(tokens).len(), 2);
+    }
+}
@@ -0,0 +1,140 @@
+use gen_lsp_types::{Location, LspRequestMethod, MessageDirection, Request, Uri};
+use lib_core::vault::tags::{TagIndex, normalize_tag};
+use miette::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{server_state::ServerState, uri::UriExt};
+
+/// `markdown/tagHierarchy`: the tree of nested tags, like a type hierarchy for `#parent/child`.
+pub enum TagHierarchyRequest {}
+
+impl Request for TagHierarchyRequest {
+    type Params = TagHierarchyParams;
+    type Result = Vec<TagNode>;
+    const METHOD: LspRequestMethod = LspRequestMethod::new("markdown/tagHierarchy");
+    const MESSAGE_DIRECTION: MessageDirection = MessageDirection::ClientToServer;
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TagHierarchyParams {
+    /// Only return the subtree below this tag, with or without `#`
+    pub tag: Option<String>,
+    /// Include the locations every tag is used at
+    pub include_locations: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagNode {
+    /// Full tag path, e.g. `project/alpha`
+    pub name: String,
+    /// Last segment of the path, e.g. `alpha`
+    pub segment: String,
+    /// Uses of this tag and all of its descendants
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locations: Option<Vec<Location>>,
+    pub children: Vec<TagNode>,
+}
+
+impl TagNode {
+    fn new(index: &TagIndex, name: String, include_locations: bool) -> Self {
+        let children = index
+            .children(Some(&name))
+            .into_iter()
+            .map(|child| TagNode::new(index, child, include_locations))
+            .collect();
+
+        let locations = include_locations.then(|| {
+            index
+                .get(&name)
+                .iter()
+                .filter_map(|occurrence| {
+                    Some(Location::new(
+                        Uri::from_file_path(&occurrence.path)?,
+                        occurrence.range,
+                    ))
+                })
+                .collect()
+        });
+
+        Self {
+            segment: name.rsplit('/').next().unwrap_or(&name).to_string(),
+            count: index.count(&name),
+            locations,
+            children,
+            name,
+        }
+    }
+}
+
+pub fn process_tag_hierarchy(
+    lsp: &mut ServerState,
+    params: TagHierarchyParams,
+) -> Result<Vec<TagNode>> {
+    let index = lsp.documents.tag_index();
+
+    let roots = match params.tag.as_deref() {
+        Some(tag) => index
+            .descendants(tag)
+            .next()
+            .map(|_| vec![normalize_tag(tag)])
+            .unwrap_or_default(),
+        None => index.children(None),
+    };
+
+    Ok(roots
+        .into_iter()
+        .map(|root| TagNode::new(index, root, params.include_locations))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    #[test]
+    fn tag_tree_is_built_from_nested_tags() {
+        let mut ws = TestWorkspace::new();
+        ws.add_file(
+            "/workspace/a.md",
+            1,
+            "#project/alpha/design #project/beta #inbox",
+        )
+        .add_file("/workspace/b.md", 1, "- [ ] Review #project/alpha");
+
+        let tree = process_tag_hierarchy(&mut ws.state, TagHierarchyParams::default()).unwrap();
+        let roots: Vec<_> = tree.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(roots, vec!["inbox", "project"]);
+
+        let project = &tree[1];
+        assert_eq!(project.count, 3);
+        assert_eq!(project.children.len(), 2);
+        assert_eq!(project.children[0].segment, "alpha");
+        assert_eq!(project.children[0].children[0].name, "project/alpha/design");
+
+        let tree = process_tag_hierarchy(
+            &mut ws.state,
+            TagHierarchyParams {
+                tag: Some("#Project/Alpha".to_string()),
+                include_locations: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].locations.as_ref().unwrap().len(), 1);
+        assert_eq!(tree[0].count, 2);
+
+        let tree = process_tag_hierarchy(
+            &mut ws.state,
+            TagHierarchyParams {
+                tag: Some("missing".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(tree.is_empty());
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+
+use gen_lsp_types::{
+    DocumentChange, Edit, Location, LspRequestMethod, MessageDirection,
+    OptionalVersionedTextDocumentIdentifier, Position, Range, Request, TextDocumentEdit,
+    TextDocumentIdentifier, TextEdit, Uri, WorkspaceEdit,
+};
+use globset::Glob;
+use lib_core::{
+    document::{
+        Document,
+        tasks::{Task, TaskQuery, TaskState, is_iso_date},
+    },
+    text_buffer_conversions::TextBufferConversions,
+};
+use miette::{IntoDiagnostic, Result, miette};
+use serde::{Deserialize, Serialize};
+
+use crate::{server_state::ServerState, uri::UriExt};
+
+/// `markdown/tasks`: lists tasks across the whole vault, e.g. to build an agenda view.
+pub enum TasksRequest {}
+
+impl Request for TasksRequest {
+    type Params = TasksParams;
+    type Result = Vec<TaskItem>;
+    const METHOD: LspRequestMethod = LspRequestMethod::new("markdown/tasks");
+    const MESSAGE_DIRECTION: MessageDirection = MessageDirection::ClientToServer;
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TasksParams {
+    /// State names (`todo`, `done`, `in-progress`, `cancelled`, `deferred`,
+    /// `question`) or raw checkbox characters
+    pub states: Vec<String>,
+    /// Tag on the task or in the document's `tags` frontmatter, with or without `#`
+    pub tag: Option<String>,
+    /// Inclusive `YYYY-MM-DD` lower bound on the due date
+    pub due_after: Option<String>,
+    /// Inclusive `YYYY-MM-DD` upper bound on the due date
+    pub due_before: Option<String>,
+    /// Glob matched against the path relative to the workspace root, e.g. `projects/**`
+    pub file_glob: Option<String>,
+    /// Frontmatter or inline fields the document must have
+    pub frontmatter: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskItem {
+    pub location: Location,
+    /// Name of the state, see [`TasksParams::states`]
+    pub state: String,
+    /// Character between the checkbox brackets
+    pub marker: char,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl TaskItem {
+    fn new(uri: Uri, task: &Task) -> Self {
+        Self {
+            location: Location::new(uri, task.range),
+            state: task.state.name().to_string(),
+            marker: task.state.marker(),
+            description: task.description.clone(),
+            priority: task
+                .priority
+                .map(|priority| format!("{priority:?}").to_lowercase()),
+            due: task.due.clone(),
+            scheduled: task.scheduled.clone(),
+            start: task.start.clone(),
+            created: task.created.clone(),
+            done: task.done.clone(),
+            recurrence: task.recurrence.clone(),
+            tags: task.tags.clone(),
+        }
+    }
+}
+
+pub fn process_tasks(lsp: &mut ServerState, params: TasksParams) -> Result<Vec<TaskItem>> {
+    let states = params
+        .states
+        .iter()
+        .map(|name| {
+            TaskState::from_name(name).ok_or_else(|| miette!("Unknown task state: {}", name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let query = TaskQuery {
+        states,
+        tag: params.tag,
+        due_after: params.due_after,
+        due_before: params.due_before,
+        metadata: params.frontmatter.into_iter().collect(),
+    };
+
+    let file_matcher = params
+        .file_glob
+        .map(|glob| Glob::new(&glob).map(|glob| glob.compile_matcher()))
+        .transpose()
+        .into_diagnostic()?;
+
+    let mut tasks: Vec<(&std::path::Path, TaskItem)> = lsp
+        .documents
+        .tasks()
+        .filter(|(doc, task)| query.matches(doc, task))
+        .filter(|(doc, _)| {
+            let Some(matcher) = &file_matcher else {
+                return true;
+            };
+
+            let root = lsp
+                .get_workspace_root_for_path(&doc.path)
+                .and_then(|root| root.to_file_path());
+            let relative = root
+                .as_deref()
+                .and_then(|root| doc.path.strip_prefix(root).ok())
+                .unwrap_or(&doc.path);
+
+            matcher.is_match(relative)
+        })
+        .filter_map(|(doc, task)| {
+            let uri = Uri::from_file_path(&doc.path)?;
+            Some((doc.path.as_path(), TaskItem::new(uri, task)))
+        })
+        .collect();
+
+    // Vault iteration order is arbitrary, keep the response stable for clients
+    tasks.sort_by(|(a_path, a), (b_path, b)| {
+        a_path.cmp(b_path).then(
+            a.location
+                .range
+                .start
+                .line
+                .cmp(&b.location.range.start.line),
+        )
+    });
+
+    Ok(tasks.into_iter().map(|(_, item)| item).collect())
+}
+
+/// `workspace/executeCommand` command cycling the tasks under the cursor, see [`CycleTaskArgs`]
+pub const CYCLE_TASK_COMMAND: &str = "markdown.cycleTask";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycleTaskArgs {
+    pub uri: Uri,
+    /// Cursor position or selection, every task starting on one of its lines is cycled
+    pub range: Range,
+    /// Overrides `tasks.append_completion_date` from the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub append_completion_date: Option<bool>,
+}
+
+/// Builds the edit cycling every task in `range` to its next state.
+///
+/// Tasks marked done get a `✅ completion_date` appended, reopened tasks lose it.
+/// Parents whose subtasks are all closed are marked done as well.
+pub fn cycle_tasks_edit(
+    document: &Document,
+    uri: &Uri,
+    range: Range,
+    completion_date: Option<&str>,
+) -> Option<WorkspaceEdit> {
+    let mut changes: Vec<(&Task, TaskState)> = document
+        .tasks
+        .iter()
+        .filter(|task| (range.start.line..=range.end.line).contains(&task.range.start.line))
+        .map(|task| (task, task.state.cycle()))
+        .collect();
+
+    if changes.is_empty() {
+        return None;
+    }
+
+    let state_of = |changes: &[(&Task, TaskState)], task: &Task| {
+        changes
+            .iter()
+            .find(|(changed, _)| *changed == task)
+            .map_or(task.state, |(_, state)| *state)
+    };
+
+    // Complete parents bottom-up, a completed parent may complete its own parent
+    let mut i = 0;
+    while i < changes.len() {
+        let (task, state) = changes[i];
+        i += 1;
+        if !state.is_closed() {
+            continue;
+        }
+
+        let Some(parent) = document.parent_task(task) else {
+            continue;
+        };
+        let parent_state = state_of(&changes, parent);
+        if parent_state == TaskState::Done
+            || !document
+                .subtasks(parent)
+                .all(|child| state_of(&changes, child).is_closed())
+        {
+            continue;
+        }
+
+        match changes.iter_mut().find(|(changed, _)| *changed == parent) {
+            Some((_, state)) => *state = TaskState::Done,
+            None => changes.push((parent, TaskState::Done)),
+        }
+    }
+
+    let edits = changes
+        .iter()
+        .filter(|(task, state)| task.state != *state)
+        .flat_map(|(task, state)| task_state_edits(document, task, *state, completion_date))
+        .map(Edit::TextEdit)
+        .collect();
+
+    Some(WorkspaceEdit {
+        changes: None,
+        document_changes: Some(vec![DocumentChange::TextDocumentEdit(TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                text_document_identifier: TextDocumentIdentifier { uri: uri.clone() },
+                version: Some(document.version),
+            },
+            edits,
+        })]),
+        change_annotations: None,
+    })
+}
+
+fn task_state_edits(
+    document: &Document,
+    task: &Task,
+    state: TaskState,
+    completion_date: Option<&str>,
+) -> Vec<TextEdit> {
+    let checkbox = task.checkbox_range;
+    let mut edits = vec![TextEdit::new(
+        Range::new(
+            Position::new(checkbox.start.line, checkbox.start.character + 1),
+            Position::new(checkbox.end.line, checkbox.end.character - 1),
+        ),
+        state.marker().to_string(),
+    )];
+
+    let line_idx = task.range.start.line as usize;
+    let line = document.content.line(line_idx).to_string();
+    let line_start = document.content.line_to_byte(line_idx);
+    let range = |start: usize, end: usize| {
+        let content = document.content.slice(..);
+        content.byte_to_lsp_range(&(line_start + start..line_start + end))
+    };
+
+    if state == TaskState::Done {
+        if let Some(date) = completion_date
+            && task.done.is_none()
+        {
+            let end = line.trim_end().len();
+            edits.push(TextEdit::new(range(end, end), format!(" ✅ {date}")));
+        }
+    } else if task.state == TaskState::Done
+        && let Some((start, end)) = completion_date_span(&line)
+    {
+        edits.push(TextEdit::new(range(start, end), String::new()));
+    }
+
+    edits
+}
+
+/// Byte span of a `✅ YYYY-MM-DD` signifier in `line`, including the whitespace before it
+fn completion_date_span(line: &str) -> Option<(usize, usize)> {
+    let signifier = line.find('✅')?;
+    let rest = &line[signifier + '✅'.len_utf8()..];
+    let rest = rest.strip_prefix('\u{fe0f}').unwrap_or(rest);
+    let date_start = line.len() - rest.trim_start().len();
+
+    line.get(date_start..date_start + 10)
+        .filter(|date| is_iso_date(date))?;
+
+    Some((line[..signifier].trim_end().len(), date_start + 10))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::test_utils::TestWorkspace;
+
+    fn cycle(input: &str, range: Range, completion_date: Option<&str>) -> String {
+        let document = Document::new(PathBuf::from("/tasks.md"), input, 0).unwrap();
+        let uri = Uri::from_file_path(&document.path).unwrap();
+        let Some(edit) = cycle_tasks_edit(&document, &uri, range, completion_date) else {
+            return input.to_string();
+        };
+
+        let Some(DocumentChange::TextDocumentEdit(change)) =
+            edit.document_changes.unwrap().into_iter().next()
+        else {
+            panic!("Expected a text document edit");
+        };
+        let mut edits: Vec<_> = change
+            .edits
+            .into_iter()
+            .map(|edit| match edit {
+                Edit::TextEdit(edit) => edit,
+                other => panic!("Unexpected edit {other:?}"),
+            })
+            .collect();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+
+        let mut rope = Rope::from_str(input);
+        for edit in edits {
+            let bytes = rope.slice(..).lsp_to_byte_range(&edit.range);
+            let (start, end) = (rope.byte_to_char(bytes.start), rope.byte_to_char(bytes.end));
+            rope.remove(start..end);
+            rope.insert(start, &edit.new_text);
+        }
+        rope.to_string()
+    }
+
+    fn cursor(line: u32) -> Range {
+        Range::new(Position::new(line, 3), Position::new(line, 3))
+    }
+
+    #[test]
+    fn cycle_task_states() {
+        let date = Some("2026-10-18");
+
+        assert_eq!(cycle("- [ ] Write\n", cursor(0), date), "- [/] Write\n");
+        assert_eq!(
+            cycle("- [/] Write 📅 2026-10-20\n", cursor(0), date),
+            "- [x] Write 📅 2026-10-20 ✅ 2026-10-18\n"
+        );
+        assert_eq!(cycle("- [/] Write\n", cursor(0), None), "- [x] Write\n");
+        assert_eq!(
+            cycle("- [x] Write ✅ 2026-10-01 #work\n", cursor(0), date),
+            "- [ ] Write #work\n"
+        );
+        assert_eq!(
+            cycle(
+                "- [x] 🚀 Ship 📅 2026-10-20 ✅ 2026-10-01\n",
+                cursor(0),
+                date
+            ),
+            "- [ ] 🚀 Ship 📅 2026-10-20\n"
+        );
+        assert_eq!(cycle("Not a task\n", cursor(0), date), "Not a task\n");
+    }
+
+    #[test]
+    fn cycle_task_selection() {
+        let input = "- [ ] One\n- [/] Two\n- [?] Three\n";
+        let selection = Range::new(Position::new(0, 0), Position::new(1, 4));
+
+        assert_eq!(
+            cycle(input, selection, None),
+            "- [/] One\n- [x] Two\n- [?] Three\n"
+        );
+    }
+
+    #[test]
+    fn completing_last_subtask_completes_parents() {
+        let input = "- [ ] Project\n  - [ ] Phase\n    - [x] A\n    - [/] B\n  - [-] Dropped\n- [ ] Other\n";
+
+        assert_eq!(
+            cycle(input, cursor(3), Some("2026-10-18")),
+            "- [x] Project ✅ 2026-10-18\n  - [x] Phase ✅ 2026-10-18\n    - [x] A\n    - [x] B ✅ 2026-10-18\n  - [-] Dropped\n- [ ] Other\n"
+        );
+
+        // An open sibling keeps the parent open
+        let input = "- [ ] Project\n  - [ ] A\n  - [/] B\n";
+        assert_eq!(
+            cycle(input, cursor(2), None),
+            "- [ ] Project\n  - [ ] A\n  - [x] B\n"
+        );
+    }
+
+    #[test]
+    fn tasks_are_filtered_across_vault() {
+        let mut ws = TestWorkspace::new();
+        ws.state.insert_root("file:///workspace".parse().unwrap());
+
+        ws.add_file(
+            "/workspace/projects/alpha.md",
+            1,
+            "---\nstatus: active\n---\n- [ ] Design 📅 2026-10-20\n- [x] Kickoff\n",
+        )
+        .add_file(
+            "/workspace/journal/today.md",
+            1,
+            "- [ ] Groceries #home\n- [>] Taxes 📅 2027-04-01\n",
+        );
+
+        let tasks = process_tasks(
+            &mut ws.state,
+            TasksParams {
+                states: vec!["todo".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let descriptions: Vec<_> = tasks.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["Groceries #home", "Design"]);
+
+        let tasks = process_tasks(
+            &mut ws.state,
+            TasksParams {
+                file_glob: Some("projects/**".to_string()),
+                frontmatter: HashMap::from([("status".to_string(), "active".to_string())]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].location.range.start.line, 3);
+
+        let tasks = process_tasks(
+            &mut ws.state,
+            TasksParams {
+                due_after: Some("2027-01-01".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].state, "deferred");
+    }
+}
@@ -4,7 +4,10 @@ use lib_core::{
         Document,
         references::{Reference as DocReference, ReferenceKind},
     },
-    vault::Vault,
+    vault::{
+        Vault,
+        tags::{is_descendant_tag, normalize_tag},
+    },
 };
 
 use crate::{
@@ -115,9 +118,30 @@ impl<'a> ReferenceCollector<'a> {
 
                 self.match_link_reference(uri, reference, header.as_deref(), &resolved_target)
             }
+            ReferenceKind::Tag { name } => self.match_tag_reference(uri, reference, name),
         }
     }
 
+    /// Find uses of the same tag, and of tags nested under it if configured
+    pub(crate) fn match_tag_reference(
+        &self,
+        uri: &gen_lsp_types::Uri,
+        reference: &DocReference,
+        source_tag: &str,
+    ) -> Option<Location> {
+        let ReferenceKind::Tag { name } = &reference.kind else {
+            return None;
+        };
+
+        let matches = if self.lsp.config.markdown.nested_tag_references {
+            is_descendant_tag(name, source_tag)
+        } else {
+            normalize_tag(name) == normalize_tag(source_tag)
+        };
+
+        matches.then(|| Location::new(uri.clone(), reference.range))
+    }
+
     /// Find links that reference the given header
     pub(crate) fn match_header_reference(
         &self,
@@ -154,6 +178,7 @@ impl<'a> ReferenceCollector<'a> {
             ReferenceKind::Header { .. } => self
                 .match_link_to_header(reference, uri, source_header, source_target)
                 .map(|_| location),
+            ReferenceKind::Tag { .. } => None,
         }
     }
 
@@ -8,6 +8,8 @@ pub const RPC_VERSION: &str = "2.0";
 pub enum Message {
     Request(Request),
     Notification(Notification),
+    /// Reply from the client to a request sent by the server
+    Response(Response),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,7 +27,6 @@ pub struct Request<P = Value> {
 }
 
 impl Request {
-    #[allow(dead_code)]
     pub fn new<P>(id: usize, method: &str, params: P) -> Result<Self, Error>
     where
         P: Serialize,
@@ -5,21 +5,34 @@ use std::io::{self, BufRead, Write};
 use crate::{
     ServerState, dispatch_lsp_request,
     handlers::{
+        call_hierarchy::{
+            process_incoming_calls, process_outgoing_calls, process_prepare_call_hierarchy,
+        },
         code_action::process_code_action,
+        code_lens::{process_code_lens, process_code_lens_resolve},
         completion::{completion_resolve::process_completion_resolve, process_completion},
         diagnostics::process_diagnostic,
         did_change::process_did_change,
         did_close::process_did_close,
         did_open::process_did_open,
+        document_link::{process_document_link, process_document_link_resolve},
         document_symbol::process_document_symbol,
+        execute_command::process_execute_command,
+        folding_range::process_folding_range,
+        formatting::{process_formatting, process_range_formatting},
         goto_definition::process_goto_definition,
         hover::process_hover,
         initialize::process_initialize,
+        inlay_hint::process_inlay_hint,
         references::process_references,
         rename::{
             did_rename::process_did_rename, process_prepare_rename, process_rename,
             will_rename::process_will_rename_files,
         },
+        selection_range::process_selection_range,
+        semantic_tokens::{process_semantic_tokens, process_semantic_tokens_range},
+        tags::{TagHierarchyRequest, process_tag_hierarchy},
+        tasks::{TasksRequest, process_tasks},
         will_create::{process_did_create, process_will_create_files},
         workspace_symbol::process_workspace_symbol,
     },
@@ -73,6 +86,23 @@ pub fn run_lsp() -> Result<()> {
                             gen_lsp_types::RenameRequest => process_rename,
                             gen_lsp_types::WillRenameFilesRequest => process_will_rename_files,
                             gen_lsp_types::WillCreateFilesRequest => process_will_create_files,
+                            gen_lsp_types::ExecuteCommandRequest => process_execute_command,
+                            gen_lsp_types::FoldingRangeRequest => process_folding_range,
+                            gen_lsp_types::SelectionRangeRequest => process_selection_range,
+                            gen_lsp_types::DocumentLinkRequest => process_document_link,
+                            gen_lsp_types::DocumentLinkResolveRequest => process_document_link_resolve,
+                            gen_lsp_types::SemanticTokensRequest => process_semantic_tokens,
+                            gen_lsp_types::SemanticTokensRangeRequest => process_semantic_tokens_range,
+                            gen_lsp_types::InlayHintRequest => process_inlay_hint,
+                            gen_lsp_types::CodeLensRequest => process_code_lens,
+                            gen_lsp_types::CodeLensResolveRequest => process_code_lens_resolve,
+                            gen_lsp_types::CallHierarchyPrepareRequest => process_prepare_call_hierarchy,
+                            gen_lsp_types::CallHierarchyIncomingCallsRequest => process_incoming_calls,
+                            gen_lsp_types::CallHierarchyOutgoingCallsRequest => process_outgoing_calls,
+                            gen_lsp_types::DocumentFormattingRequest => process_formatting,
+                            gen_lsp_types::DocumentRangeFormattingRequest => process_range_formatting,
+                            TasksRequest => process_tasks,
+                            TagHierarchyRequest => process_tag_hierarchy,
                         });
                     }
                 }
@@ -98,6 +128,17 @@ pub fn run_lsp() -> Result<()> {
                     }
                 }
             }
+            Message::Response(response) => {
+                if let Some(error) = response.error {
+                    tracing::warn!("Client rejected request {}: {}", response.id, error.message);
+                } else {
+                    tracing::trace!("Client answered request {}", response.id);
+                }
+            }
+        }
+
+        for request in lsp.take_outgoing_requests() {
+            write_msg(&mut writer, &encode_message(&request)?)?;
         }
     }
 
@@ -1,11 +1,13 @@
 use std::path::Path;
 
-use gen_lsp_types::{ClientCapabilities, Uri, WorkspaceFolder};
-use miette::Result;
+use gen_lsp_types::{
+    ClientCapabilities, PositionEncodingKind, Request as LspRequest, Uri, WorkspaceFolder,
+};
+use miette::{IntoDiagnostic, Result};
 
 use lib_core::{config::Config, vault::Vault};
 
-use crate::uri::UriExt;
+use crate::{handlers::initialize::position_encoding, messages::Request, uri::UriExt};
 
 #[derive(Default)]
 pub struct ServerState {
@@ -13,6 +15,9 @@ pub struct ServerState {
     pub config: Config,
     workspace_roots: Vec<Uri>,
     client_capabilities: Option<ClientCapabilities>,
+    /// Requests to the client, written out after the current message is handled
+    outgoing_requests: Vec<Request>,
+    next_request_id: usize,
 }
 
 impl ServerState {
@@ -25,6 +30,12 @@ impl ServerState {
         tracing::info!("Loading configuration: {:?}", self.config);
 
         self.config = Config::from_file_or_default(config_path);
+        if let Err(err) = self
+            .documents
+            .set_parse_options(self.config.markdown.parse_options())
+        {
+            tracing::error!("Failed to reparse documents: {err:?}");
+        }
     }
 
     pub fn insert_root(&mut self, uri: Uri) {
@@ -35,6 +46,27 @@ impl ServerState {
         self.client_capabilities = Some(capabilities);
     }
 
+    /// The encoding agreed on in `initialize`
+    pub fn position_encoding(&self) -> PositionEncodingKind {
+        self.client_capabilities
+            .as_ref()
+            .map_or(PositionEncodingKind::UTF16, position_encoding)
+    }
+
+    /// Queues a request to the client, e.g. `workspace/applyEdit`
+    pub(crate) fn send_request<R: LspRequest>(&mut self, params: R::Params) -> Result<()> {
+        let request =
+            Request::new(self.next_request_id, R::METHOD.as_str(), params).into_diagnostic()?;
+        self.next_request_id += 1;
+        self.outgoing_requests.push(request);
+
+        Ok(())
+    }
+
+    pub(crate) fn take_outgoing_requests(&mut self) -> Vec<Request> {
+        std::mem::take(&mut self.outgoing_requests)
+    }
+
     /// Returns the "primary" root (the first one opened), if any.
     /// Useful for fallback scenarios, but prefer `get_workspace_root_for_uri`.
     pub fn primary_root(&self) -> Option<&Uri> {
@@ -62,27 +94,7 @@ impl ServerState {
                 continue;
             };
 
-            let markdown_files = walkdir::WalkDir::new(&root_path)
-                .into_iter()
-                .filter_map(|entry| entry.ok())
-                .filter(|entry| {
-                    let path = entry.path();
-                    path.is_file() && path.extension().is_some_and(|ext| ext == "md")
-                });
-
-            for entry in markdown_files {
-                let entry_path = entry.path();
-                let contents = match std::fs::read_to_string(entry_path) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        tracing::debug!("Could not read file {:?}: {}", entry_path, e);
-                        continue;
-                    }
-                };
-
-                self.documents
-                    .create_document(entry_path.to_path_buf(), 0, &contents)?;
-            }
+            self.documents.index_directory(&root_path)?;
         }
 
         Ok(())
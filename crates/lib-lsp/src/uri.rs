@@ -76,21 +76,6 @@ pub trait UriExt: Sized + sealed::Sealed {
                 .map(|os_str| os_str.to_string_lossy().into_owned())
         })
     }
-
-    /// Get the file-name directly from the Uri
-    fn get_file_name(&self) -> Option<String> {
-        self.to_file_path().and_then(|path| {
-            path.file_name()
-                .map(|os_str| os_str.to_string_lossy().into_owned())
-        })
-    }
-
-    /// Get the parent directory as a new Uri
-    fn parent(&self) -> Option<Uri> {
-        self.to_file_path()
-            .and_then(|path| path.parent().map(|p| p.to_path_buf()))
-            .and_then(Uri::from_file_path)
-    }
 }
 
 impl sealed::Sealed for gen_lsp_types::Uri {}
@@ -1,5 +1,10 @@
 use chumsky::Parser;
-use lib_parser::markdown_parser;
+use divan::Bencher;
+use lib_parser::{
+    ParseOptions, blocks_parser,
+    incremental::{BlockSpan, Reparse},
+    markdown_parser,
+};
 use std::fs;
 
 fn main() {
@@ -65,3 +70,43 @@ fn parse_multiple_files_concurrently() {
         handle.join().expect("Thread panicked");
     }
 }
+
+/// The large file with a word typed at the start of its middle block, along
+/// with the window an incremental reparse has to cover.
+fn edited_large_file() -> (String, Reparse) {
+    let content = fs::read_to_string("benches/test_large.md").unwrap();
+    let parsed = markdown_parser().parse(&content).into_output().unwrap();
+    let blocks: Vec<_> = parsed
+        .body
+        .iter()
+        .map(|block| {
+            let range = block.1.into_range();
+            let following: String = content[range.end..].chars().take(2).collect();
+            BlockSpan::new(&block.0, range, &following)
+        })
+        .collect();
+
+    let offset = blocks[blocks.len() / 2].range.start;
+    let reparse = Reparse::new(&blocks, offset..offset, "edit ".len(), content.len()).unwrap();
+
+    let mut edited = content.clone();
+    edited.insert_str(offset, "edit ");
+    (edited, reparse)
+}
+
+#[divan::bench]
+fn reparse_large_file_after_edit_full(bencher: Bencher) {
+    let (content, _) = edited_large_file();
+
+    bencher.bench_local(|| divan::black_box(markdown_parser().parse(&content)));
+}
+
+#[divan::bench]
+fn reparse_large_file_after_edit_incremental(bencher: Bencher) {
+    let (content, reparse) = edited_large_file();
+
+    bencher.bench_local(|| {
+        let window = &content[reparse.new.clone()];
+        divan::black_box(blocks_parser(ParseOptions::default()).parse(window))
+    });
+}
@@ -1,5 +1,5 @@
 use chumsky::Parser;
-use lib_parser::{InlineMarkdownNode, MarkdownNode, markdown_parser};
+use lib_parser::{InlineMarkdownNode, MarkdownNode, Spanned, markdown_parser};
 
 fn test_input(name: &str, input: &str) {
     println!("\n=== {} ===", name);
@@ -43,9 +43,8 @@ fn test_input(name: &str, input: &str) {
                 }
                 MarkdownNode::ListItem { checkbox, content } => {
                     let checkbox_str = match checkbox {
-                        Some(true) => "[x]",
-                        Some(false) => "[ ]",
-                        None => "   ",
+                        Some(Spanned(state, _)) => format!("[{}]", state.marker()),
+                        None => "   ".to_string(),
                     };
                     println!(
                         "  [{}] ListItem {} ({} inlines)",
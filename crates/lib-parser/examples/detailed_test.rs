@@ -1,5 +1,5 @@
 use chumsky::Parser;
-use lib_parser::{InlineMarkdownNode, MarkdownNode, markdown_parser};
+use lib_parser::{InlineMarkdownNode, MarkdownNode, Spanned, markdown_parser};
 
 fn main() {
     println!("=== Testing what unsupported markdown becomes ===\n");
@@ -38,9 +38,8 @@ fn main() {
                 }
                 MarkdownNode::ListItem { checkbox, content } => {
                     let checkbox_str = match checkbox {
-                        Some(true) => "[x]",
-                        Some(false) => "[ ]",
-                        None => "   ",
+                        Some(Spanned(state, _)) => format!("[{}]", state.marker()),
+                        None => "   ".to_string(),
                     };
                     println!(
                         "  [{}] ListItem {} with {} inline elements",
@@ -71,11 +71,8 @@ Even more [[valid]] content
     if let Some(parsed) = result4.0 {
         println!("  Body nodes: {}", parsed.body.len());
         for (i, node) in parsed.body.iter().enumerate() {
-            match &node.0 {
-                MarkdownNode::Invalid => {
-                    println!("    [{}] Invalid", i);
-                }
-                _ => {}
+            if node.0 == MarkdownNode::Invalid {
+                println!("    [{}] Invalid", i);
             }
         }
     }
@@ -1,5 +1,5 @@
 use chumsky::Parser;
-use lib_parser::yaml::{Frontmatter, Yaml, yaml_parser};
+use lib_parser::yaml::{Yaml, yaml_parser};
 
 fn main() {
     println!("=== Testing YAML frontmatter parser ===\n");
@@ -0,0 +1,505 @@
+//! Lossless concrete syntax tree.
+//!
+//! Every byte of the source is owned by exactly one token, either a
+//! meaningful one (markers, delimiters, text) or trivia (whitespace and
+//! newlines), so printing the tree gives back the source byte-for-byte.
+//!
+//! The tree is built from the spans of a [`ParsedMarkdown`], which stays the
+//! typed view of the document. Both share spans, so the tree node of an AST
+//! node is found with [`SyntaxNode::find_node`]. Editing the text of tokens and
+//! printing the tree is how structural edits are made without re-scanning text.
+
+use std::{borrow::Cow, fmt, ops::Range};
+
+use chumsky::span::SimpleSpan;
+
+use crate::{InlineMarkdownNode, LinkType, MarkdownNode, ParsedMarkdown, Spanned, slice_span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    // Nodes
+    Document,
+    Frontmatter,
+    Header,
+    Paragraph,
+    ListItem,
+    Checkbox,
+    FootnoteDefinition,
+    Link,
+    WikiLink,
+    Image,
+    Tag,
+    Footnote,
+    InlineField,
+    /// A block the parser recovered from, or text it never reached
+    Error,
+
+    // Tokens
+    Whitespace,
+    Newline,
+    Text,
+    /// Delimiters such as `[[`, `](`, `|`, `::` or the `#` of a tag
+    Punct,
+    /// `---` around the frontmatter
+    FrontmatterDelimiter,
+    /// Raw YAML between the frontmatter delimiters
+    FrontmatterContent,
+    /// `#` run of a header, opening or closing
+    HeaderMarker,
+    HeaderText,
+    /// `-` or `*` of a list item
+    ListMarker,
+    /// Character between the checkbox brackets
+    TaskMarker,
+    LinkText,
+    LinkDestination,
+    /// Header part of a link after `#`
+    LinkHeader,
+    WikiTarget,
+    TagName,
+    FootnoteId,
+    FieldKey,
+}
+
+impl SyntaxKind {
+    /// Whitespace and newlines, which carry no meaning of their own
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Newline)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken<'a> {
+    pub kind: SyntaxKind,
+    /// Span in the source the tree was built from, not updated by edits
+    pub span: SimpleSpan,
+    pub text: Cow<'a, str>,
+}
+
+impl<'a> SyntaxToken<'a> {
+    /// Replaces the text printed for this token
+    pub fn set_text(&mut self, text: impl Into<Cow<'a, str>>) {
+        self.text = text.into();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(SyntaxToken<'a>),
+}
+
+impl SyntaxElement<'_> {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind,
+            SyntaxElement::Token(token) => token.kind,
+        }
+    }
+
+    pub fn span(&self) -> SimpleSpan {
+        match self {
+            SyntaxElement::Node(node) => node.span,
+            SyntaxElement::Token(token) => token.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'a> {
+    pub kind: SyntaxKind,
+    pub span: SimpleSpan,
+    pub children: Vec<SyntaxElement<'a>>,
+}
+
+impl<'a> SyntaxNode<'a> {
+    /// Every element below this node in source order, nodes before their children
+    pub fn preorder(&self) -> impl Iterator<Item = &SyntaxElement<'a>> {
+        let mut stack = vec![self.children.iter()];
+
+        std::iter::from_fn(move || {
+            loop {
+                let Some(element) = stack.last_mut()?.next() else {
+                    stack.pop();
+                    continue;
+                };
+                if let SyntaxElement::Node(node) = element {
+                    stack.push(node.children.iter());
+                }
+                return Some(element);
+            }
+        })
+    }
+
+    /// This node and every node below it, in source order
+    pub fn descendants(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        std::iter::once(self).chain(self.preorder().filter_map(|element| match element {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }))
+    }
+
+    /// Every token below this node, in source order
+    pub fn tokens(&self) -> impl Iterator<Item = &SyntaxToken<'a>> {
+        self.preorder().filter_map(|element| match element {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        self.children.iter().filter_map(|element| match element {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    pub fn child_nodes_mut(&mut self) -> impl Iterator<Item = &mut SyntaxNode<'a>> {
+        self.children
+            .iter_mut()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+    }
+
+    /// First direct child token of `kind`
+    pub fn child_token(&self, kind: SyntaxKind) -> Option<&SyntaxToken<'a>> {
+        self.children.iter().find_map(|element| match element {
+            SyntaxElement::Token(token) if token.kind == kind => Some(token),
+            _ => None,
+        })
+    }
+
+    /// First direct child token of `kind`, for editing
+    pub fn child_token_mut(&mut self, kind: SyntaxKind) -> Option<&mut SyntaxToken<'a>> {
+        self.children.iter_mut().find_map(|element| match element {
+            SyntaxElement::Token(token) if token.kind == kind => Some(token),
+            _ => None,
+        })
+    }
+
+    /// Outermost node with exactly `span`, e.g. the node of an AST element
+    pub fn find_node(&self, span: SimpleSpan) -> Option<&SyntaxNode<'a>> {
+        self.descendants().find(|node| node.span == span)
+    }
+
+    /// Token containing the byte `offset`
+    pub fn token_at(&self, offset: usize) -> Option<&SyntaxToken<'a>> {
+        self.tokens()
+            .find(|token| token.span.start <= offset && offset < token.span.end)
+    }
+}
+
+impl fmt::Display for SyntaxNode<'_> {
+    /// Prints the text of every token, the source itself unless tokens were edited
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens().try_for_each(|token| f.write_str(&token.text))
+    }
+}
+
+impl<'a> ParsedMarkdown<'a> {
+    /// Lossless syntax tree over `source`, which must be the text this was parsed from
+    pub fn syntax_tree(&self, source: &'a str) -> SyntaxNode<'a> {
+        let mut children = Vec::new();
+
+        let first_block = self
+            .body
+            .first()
+            .map_or(source.len(), |block| block.1.start);
+        if self.frontmatter.is_some()
+            && let Some(frontmatter) = frontmatter_node(source, 0..first_block)
+        {
+            children.push(SyntaxElement::Node(frontmatter));
+        }
+
+        children.extend(
+            self.body
+                .iter()
+                .map(|block| SyntaxElement::Node(block_node(source, block))),
+        );
+
+        node(
+            source,
+            SyntaxKind::Document,
+            0..source.len(),
+            children,
+            SyntaxKind::Error,
+        )
+    }
+}
+
+/// Builds a node from its known children, filling the gaps between them with
+/// `gap` tokens and trivia so that the node covers all of `range`.
+fn node<'a>(
+    source: &'a str,
+    kind: SyntaxKind,
+    range: Range<usize>,
+    known: Vec<SyntaxElement<'a>>,
+    gap: SyntaxKind,
+) -> SyntaxNode<'a> {
+    let mut children = Vec::new();
+    let mut cursor = range.start;
+
+    for child in known {
+        let span = child.span();
+        // Overlapping spans would print bytes twice
+        if span.start < cursor || span.end > range.end {
+            continue;
+        }
+        lex(source, cursor..span.start, gap, &mut children);
+        children.push(child);
+        cursor = span.end;
+    }
+    lex(source, cursor..range.end, gap, &mut children);
+
+    SyntaxNode {
+        kind,
+        span: SimpleSpan::from(range),
+        children,
+    }
+}
+
+fn token<'a>(source: &'a str, kind: SyntaxKind, range: Range<usize>) -> SyntaxElement<'a> {
+    SyntaxElement::Token(SyntaxToken {
+        kind,
+        text: Cow::Borrowed(&source[range.clone()]),
+        span: SimpleSpan::from(range),
+    })
+}
+
+/// Token for a non-empty slice the AST borrowed from `source`
+fn slice_token<'a>(source: &'a str, kind: SyntaxKind, slice: &str) -> Option<SyntaxElement<'a>> {
+    let span = slice_span(source, slice).filter(|span| span.start < span.end)?;
+    Some(token(source, kind, span.into_range()))
+}
+
+/// Splits `range` into newlines, whitespace runs and runs of `kind`
+fn lex<'a>(
+    source: &'a str,
+    range: Range<usize>,
+    kind: SyntaxKind,
+    out: &mut Vec<SyntaxElement<'a>>,
+) {
+    let text = &source[range.clone()];
+    let mut start = 0;
+
+    while start < text.len() {
+        let rest = &text[start..];
+        let (token_kind, len) = if rest.starts_with("\r\n") {
+            (SyntaxKind::Newline, 2)
+        } else if rest.starts_with('\n') {
+            (SyntaxKind::Newline, 1)
+        } else if rest.starts_with(|c: char| c.is_whitespace()) {
+            // A `\r` right before a `\n` belongs to the newline
+            let len = rest
+                .char_indices()
+                .find(|(i, c)| *c == '\n' || !c.is_whitespace() || rest[*i..].starts_with("\r\n"))
+                .map_or(rest.len(), |(i, _)| i);
+            (SyntaxKind::Whitespace, len)
+        } else {
+            let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (kind, len)
+        };
+
+        let offset = range.start + start;
+        out.push(token(source, token_kind, offset..offset + len));
+        start += len;
+    }
+}
+
+fn frontmatter_node<'a>(source: &'a str, range: Range<usize>) -> Option<SyntaxNode<'a>> {
+    let mut delimiters = source[range.clone()]
+        .split_inclusive('\n')
+        .scan(range.start, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .filter(|(_, line)| line.trim() == "---")
+        .map(|(start, line)| {
+            let marker = start + line.find("---").unwrap_or_default();
+            marker..marker + 3
+        });
+
+    let open = delimiters.next()?;
+    let close = delimiters.next()?;
+
+    let mut children = vec![token(
+        source,
+        SyntaxKind::FrontmatterDelimiter,
+        open.clone(),
+    )];
+    let content_start = source[open.end..close.start]
+        .find('\n')
+        .map_or(open.end, |newline| open.end + newline + 1);
+    let content_end = source[..close.start]
+        .rfind('\n')
+        .map_or(close.start, |newline| {
+            newline - usize::from(source[..newline].ends_with('\r'))
+        })
+        .max(content_start);
+    if content_start < content_end {
+        children.push(token(
+            source,
+            SyntaxKind::FrontmatterContent,
+            content_start..content_end,
+        ));
+    }
+    children.push(token(
+        source,
+        SyntaxKind::FrontmatterDelimiter,
+        close.clone(),
+    ));
+
+    Some(node(
+        source,
+        SyntaxKind::Frontmatter,
+        range.start..close.end,
+        children,
+        SyntaxKind::Error,
+    ))
+}
+
+fn block_node<'a>(
+    source: &'a str,
+    Spanned(block, span): &Spanned<MarkdownNode<'a>>,
+) -> SyntaxNode<'a> {
+    let range = span.into_range();
+
+    match block {
+        MarkdownNode::Header { content, .. } => node(
+            source,
+            SyntaxKind::Header,
+            range,
+            slice_token(source, SyntaxKind::HeaderText, content)
+                .into_iter()
+                .collect(),
+            SyntaxKind::HeaderMarker,
+        ),
+        MarkdownNode::Paragraph(content) => node(
+            source,
+            SyntaxKind::Paragraph,
+            range,
+            inline_elements(source, content),
+            SyntaxKind::Text,
+        ),
+        MarkdownNode::ListItem { checkbox, content } => {
+            let mut children = Vec::new();
+            if let Some(Spanned(_, span)) = checkbox {
+                let marker = token(source, SyntaxKind::TaskMarker, span.start + 1..span.end - 1);
+                children.push(SyntaxElement::Node(node(
+                    source,
+                    SyntaxKind::Checkbox,
+                    span.into_range(),
+                    vec![marker],
+                    SyntaxKind::Punct,
+                )));
+            }
+            children.extend(inline_elements(source, content));
+
+            node(
+                source,
+                SyntaxKind::ListItem,
+                range,
+                children,
+                SyntaxKind::ListMarker,
+            )
+        }
+        MarkdownNode::FootnoteDefinition { id, content } => {
+            let mut children: Vec<_> = slice_token(source, SyntaxKind::FootnoteId, id)
+                .into_iter()
+                .collect();
+            children.extend(inline_elements(source, content));
+
+            node(
+                source,
+                SyntaxKind::FootnoteDefinition,
+                range,
+                children,
+                SyntaxKind::Punct,
+            )
+        }
+        MarkdownNode::Invalid => node(
+            source,
+            SyntaxKind::Error,
+            range,
+            Vec::new(),
+            SyntaxKind::Text,
+        ),
+    }
+}
+
+fn inline_elements<'a>(
+    source: &'a str,
+    content: &[Spanned<InlineMarkdownNode<'a>>],
+) -> Vec<SyntaxElement<'a>> {
+    let mut elements = Vec::new();
+
+    for Spanned(inline, span) in content {
+        let range = span.into_range();
+        let slices = |parts: &[(SyntaxKind, Option<&str>)]| -> Vec<SyntaxElement<'a>> {
+            parts
+                .iter()
+                .filter_map(|(kind, slice)| slice_token(source, *kind, (*slice)?))
+                .collect()
+        };
+
+        let (kind, children) = match inline {
+            InlineMarkdownNode::PlainText(_) => {
+                lex(source, range, SyntaxKind::Text, &mut elements);
+                continue;
+            }
+            InlineMarkdownNode::Tag(name) => (
+                SyntaxKind::Tag,
+                slices(&[(SyntaxKind::TagName, Some(name))]),
+            ),
+            InlineMarkdownNode::Footnote(id) => (
+                SyntaxKind::Footnote,
+                slices(&[(SyntaxKind::FootnoteId, Some(id))]),
+            ),
+            InlineMarkdownNode::Link(LinkType::InlineLink { text, uri, header }) => (
+                SyntaxKind::Link,
+                slices(&[
+                    (SyntaxKind::LinkText, Some(text)),
+                    (SyntaxKind::LinkDestination, Some(uri)),
+                    (SyntaxKind::LinkHeader, *header),
+                ]),
+            ),
+            InlineMarkdownNode::Link(LinkType::WikiLink {
+                target,
+                display_text,
+                header,
+            }) => (
+                SyntaxKind::WikiLink,
+                slices(&[
+                    (SyntaxKind::WikiTarget, Some(target)),
+                    (SyntaxKind::LinkHeader, *header),
+                    (SyntaxKind::LinkText, *display_text),
+                ]),
+            ),
+            InlineMarkdownNode::Link(LinkType::ImageLink { text, uri }) => (
+                SyntaxKind::Image,
+                slices(&[
+                    (SyntaxKind::LinkText, Some(text)),
+                    (SyntaxKind::LinkDestination, Some(uri)),
+                ]),
+            ),
+            InlineMarkdownNode::InlineField { key, content, .. } => {
+                let mut children = slices(&[(SyntaxKind::FieldKey, Some(key))]);
+                children.extend(inline_elements(source, content));
+                (SyntaxKind::InlineField, children)
+            }
+        };
+
+        elements.push(SyntaxElement::Node(node(
+            source,
+            kind,
+            range,
+            children,
+            SyntaxKind::Punct,
+        )));
+    }
+
+    elements
+}
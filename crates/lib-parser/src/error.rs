@@ -0,0 +1,255 @@
+//! Typed parse errors.
+//!
+//! The grammar reports the errors it knows about as [`MarkdownError`]s that
+//! carry their [`ParseErrorKind`], anything else chumsky runs into is
+//! [`ParseErrorKind::Unexpected`].
+
+use std::fmt;
+
+use chumsky::{
+    error::{Error, LabelError, Rich, RichPattern, RichReason},
+    span::SimpleSpan,
+    util::MaybeRef,
+};
+
+/// What went wrong while parsing a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseErrorKind {
+    /// `[[Note` without the closing `]]`
+    UnclosedWikiLink,
+    /// `[[]]` or `[[#header]]`
+    EmptyWikiLinkTarget,
+    /// `[text` without the closing `]`
+    UnclosedLinkText,
+    /// `[]` or `[ ](note.md)`
+    EmptyLinkText,
+    /// `[text]` that is not followed by `(destination)`
+    MissingLinkDestination,
+    /// `[text](note.md` without the closing `)`
+    UnclosedLinkDestination,
+    /// `[text]()`
+    EmptyLinkTarget,
+    /// `[^id` without the closing `]`
+    UnclosedFootnote,
+    /// A `---` line at the start of the document that does not open valid frontmatter
+    MalformedFrontmatter,
+    /// Anything the grammar has no dedicated error for
+    Unexpected,
+}
+
+/// Edit that resolves an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickFix {
+    /// Insert the text where the error is reported
+    Insert(&'static str),
+    /// Escape the opening delimiter so the text is no longer parsed as markup
+    EscapeOpening,
+}
+
+impl ParseErrorKind {
+    pub const ALL: [ParseErrorKind; 10] = [
+        ParseErrorKind::UnclosedWikiLink,
+        ParseErrorKind::EmptyWikiLinkTarget,
+        ParseErrorKind::UnclosedLinkText,
+        ParseErrorKind::EmptyLinkText,
+        ParseErrorKind::MissingLinkDestination,
+        ParseErrorKind::UnclosedLinkDestination,
+        ParseErrorKind::EmptyLinkTarget,
+        ParseErrorKind::UnclosedFootnote,
+        ParseErrorKind::MalformedFrontmatter,
+        ParseErrorKind::Unexpected,
+    ];
+
+    /// Stable diagnostic code, e.g. `unclosed-wikilink`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseErrorKind::UnclosedWikiLink => "unclosed-wikilink",
+            ParseErrorKind::EmptyWikiLinkTarget => "empty-wikilink-target",
+            ParseErrorKind::UnclosedLinkText => "unclosed-link-text",
+            ParseErrorKind::EmptyLinkText => "empty-link-text",
+            ParseErrorKind::MissingLinkDestination => "missing-link-destination",
+            ParseErrorKind::UnclosedLinkDestination => "unclosed-link-destination",
+            ParseErrorKind::EmptyLinkTarget => "empty-link-target",
+            ParseErrorKind::UnclosedFootnote => "unclosed-footnote",
+            ParseErrorKind::MalformedFrontmatter => "malformed-frontmatter",
+            ParseErrorKind::Unexpected => "unexpected-input",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+
+    /// The delimiter that opened the construct the error is about
+    pub fn opening(&self) -> Option<&'static str> {
+        match self {
+            ParseErrorKind::UnclosedWikiLink | ParseErrorKind::EmptyWikiLinkTarget => Some("[["),
+            ParseErrorKind::UnclosedLinkText
+            | ParseErrorKind::EmptyLinkText
+            | ParseErrorKind::MissingLinkDestination => Some("["),
+            ParseErrorKind::UnclosedLinkDestination | ParseErrorKind::EmptyLinkTarget => Some("("),
+            ParseErrorKind::UnclosedFootnote => Some("[^"),
+            ParseErrorKind::MalformedFrontmatter => Some("---"),
+            ParseErrorKind::Unexpected => None,
+        }
+    }
+
+    /// Finds the opening delimiter of an error at `span` in `source`, the last
+    /// one on the line up to the end of the error.
+    pub fn opening_span(&self, source: &str, span: SimpleSpan) -> Option<SimpleSpan> {
+        let opening = self.opening()?;
+        let end = span.end.min(source.len());
+        let line_start = source[..span.start.min(end)]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let start = line_start + source[line_start..end].rfind(opening)?;
+
+        Some(SimpleSpan::from(start..start + opening.len()))
+    }
+
+    pub fn quick_fix(&self) -> Option<QuickFix> {
+        match self {
+            ParseErrorKind::UnclosedWikiLink => Some(QuickFix::Insert("]]")),
+            ParseErrorKind::UnclosedLinkDestination => Some(QuickFix::Insert(")")),
+            ParseErrorKind::UnclosedFootnote => Some(QuickFix::Insert("]")),
+            ParseErrorKind::UnclosedLinkText | ParseErrorKind::MissingLinkDestination => {
+                Some(QuickFix::EscapeOpening)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ParseErrorKind::UnclosedWikiLink => "WikiLink is not closed with `]]`",
+            ParseErrorKind::EmptyWikiLinkTarget => "WikiLink has no target note",
+            ParseErrorKind::UnclosedLinkText => "Link text is not closed with `]`",
+            ParseErrorKind::EmptyLinkText => "Link text is empty",
+            ParseErrorKind::MissingLinkDestination => {
+                "Link text is not followed by a `(destination)`"
+            }
+            ParseErrorKind::UnclosedLinkDestination => "Link destination is not closed with `)`",
+            ParseErrorKind::EmptyLinkTarget => "Link destination is empty",
+            ParseErrorKind::UnclosedFootnote => "Footnote reference is not closed with `]`",
+            ParseErrorKind::MalformedFrontmatter => {
+                "Frontmatter is malformed, it must be `key: value` lines closed by `---`"
+            }
+            ParseErrorKind::Unexpected => "Unexpected input",
+        };
+
+        f.write_str(message)
+    }
+}
+
+/// Error of the parsers of this crate, a chumsky [`Rich`] error along with
+/// the kind it was reported as
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownError<'a> {
+    kind: ParseErrorKind,
+    rich: Rich<'a, char>,
+}
+
+impl<'a> MarkdownError<'a> {
+    pub fn new(span: SimpleSpan, kind: ParseErrorKind) -> Self {
+        MarkdownError {
+            kind,
+            rich: Rich::custom(span, kind),
+        }
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    pub fn span(&self) -> &SimpleSpan {
+        self.rich.span()
+    }
+
+    /// The kind's message, or what was expected and found instead
+    pub fn reason(&self) -> &RichReason<'a, char> {
+        self.rich.reason()
+    }
+
+    pub fn into_rich(self) -> Rich<'a, char> {
+        self.rich
+    }
+
+    /// Keeps `kind` as long as chumsky did not replace the [`Rich::custom`]
+    /// error it was reported with
+    fn keeping(kind: ParseErrorKind, rich: Rich<'a, char>) -> Self {
+        let kind = match rich.reason() {
+            RichReason::Custom(_) => kind,
+            RichReason::ExpectedFound { .. } => ParseErrorKind::Unexpected,
+        };
+        MarkdownError { kind, rich }
+    }
+}
+
+impl fmt::Display for MarkdownError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.rich.fmt(f)
+    }
+}
+
+impl<'a> Error<'a, &'a str> for MarkdownError<'a> {
+    fn merge(self, other: Self) -> Self {
+        // Like `Rich`, the first error with a kind wins
+        let kind = match self.kind {
+            ParseErrorKind::Unexpected => other.kind,
+            kind => kind,
+        };
+        MarkdownError {
+            kind,
+            rich: Error::<&'a str>::merge(self.rich, other.rich),
+        }
+    }
+}
+
+impl<'a, L> LabelError<'a, &'a str, L> for MarkdownError<'a>
+where
+    L: Into<RichPattern<'a, char>>,
+{
+    fn expected_found<E: IntoIterator<Item = L>>(
+        expected: E,
+        found: Option<MaybeRef<'a, char>>,
+        span: SimpleSpan,
+    ) -> Self {
+        MarkdownError {
+            kind: ParseErrorKind::Unexpected,
+            rich: LabelError::<&'a str, L>::expected_found(expected, found, span),
+        }
+    }
+
+    fn merge_expected_found<E: IntoIterator<Item = L>>(
+        self,
+        expected: E,
+        found: Option<MaybeRef<'a, char>>,
+        span: SimpleSpan,
+    ) -> Self {
+        let rich = LabelError::<&'a str, L>::merge_expected_found(self.rich, expected, found, span);
+        Self::keeping(self.kind, rich)
+    }
+
+    fn replace_expected_found<E: IntoIterator<Item = L>>(
+        self,
+        expected: E,
+        found: Option<MaybeRef<'a, char>>,
+        span: SimpleSpan,
+    ) -> Self {
+        let rich =
+            LabelError::<&'a str, L>::replace_expected_found(self.rich, expected, found, span);
+        Self::keeping(self.kind, rich)
+    }
+
+    fn label_with(&mut self, label: L) {
+        // `Rich` replaces any reason, custom ones too, with the label
+        LabelError::<&'a str, L>::label_with(&mut self.rich, label);
+        self.kind = ParseErrorKind::Unexpected;
+    }
+
+    fn in_context(&mut self, label: L, span: SimpleSpan) {
+        LabelError::<&'a str, L>::in_context(&mut self.rich, label, span);
+    }
+}
@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+
+use chumsky::prelude::*;
+
+use crate::ParseError;
+
+/// Named character references we decode. Unknown names are left as written.
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("num", '#'),
+    ("lsqb", '['),
+    ("rsqb", ']'),
+    ("lbrack", '['),
+    ("rbrack", ']'),
+    ("lpar", '('),
+    ("rpar", ')'),
+    ("verbar", '|'),
+    ("vert", '|'),
+    ("bsol", '\\'),
+    ("ast", '*'),
+    ("lowbar", '_'),
+    ("excl", '!'),
+    ("colon", ':'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("hellip", '…'),
+    ("mdash", '—'),
+    ("ndash", '–'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("middot", '·'),
+    ("deg", '°'),
+    ("times", '×'),
+    ("rarr", '→'),
+    ("larr", '←'),
+];
+
+/// Matches a CommonMark backslash escape (`\#`) or an entity reference
+/// (`&amp;`, `&#35;`, `&#x23;`) as a single unit, so the escaped character
+/// cannot start a tag, link or other inline construct.
+pub(crate) fn escape_parser<'a>() -> impl Parser<'a, &'a str, (), ParseError<'a>> + Clone {
+    let backslash = just('\\')
+        .then(any().filter(|c: &char| c.is_ascii_punctuation()))
+        .ignored();
+
+    let hex = just('#')
+        .then(one_of("xX"))
+        .then(
+            any()
+                .filter(|c: &char| c.is_ascii_hexdigit())
+                .repeated()
+                .at_least(1)
+                .at_most(6),
+        )
+        .ignored();
+    let decimal = just('#')
+        .then(
+            any()
+                .filter(|c: &char| c.is_ascii_digit())
+                .repeated()
+                .at_least(1)
+                .at_most(7),
+        )
+        .ignored();
+    let named = any()
+        .filter(|c: &char| c.is_ascii_alphanumeric())
+        .repeated()
+        .at_least(1)
+        .at_most(32)
+        .to_slice()
+        // Only names `unescape` decodes, anything else stays plain text
+        .filter(|name: &&str| NAMED_ENTITIES.iter().any(|(entity, _)| entity == name))
+        .ignored();
+
+    let entity = just('&')
+        .then(choice((hex, decimal, named)))
+        .then(just(';'))
+        .ignored();
+
+    choice((backslash, entity)).labelled("Escape")
+}
+
+/// Decodes backslash escapes and entity references in `text`.
+///
+/// The parser keeps raw slices of the source, use this wherever the
+/// literal text matters, e.g. link targets or task descriptions.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['\\', '&']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(index) = rest.find(['\\', '&']) {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        let decoded = match rest.strip_prefix('\\') {
+            Some(escaped) => escaped
+                .chars()
+                .next()
+                .filter(char::is_ascii_punctuation)
+                .map(|c| (c, 2)),
+            None => decode_entity(rest),
+        };
+
+        match decoded {
+            Some((c, len)) => {
+                output.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                output.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+
+    Cow::Owned(output)
+}
+
+/// Decodes the entity at the start of `text`, returning the character and the length consumed
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+
+    let decoded = if let Some(number) = name.strip_prefix('#') {
+        let (digits, radix, max_len) = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16, 6),
+            None => (number, 10, 7),
+        };
+        if digits.is_empty() || digits.len() > max_len || !digits.chars().all(|c| c.is_digit(radix))
+        {
+            return None;
+        }
+
+        // Invalid code points decode to the replacement character, like CommonMark
+        u32::from_str_radix(digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+            .filter(|c| *c != '\0')
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+    } else {
+        NAMED_ENTITIES.iter().find(|(entity, _)| *entity == name)?.1
+    };
+
+    Some((decoded, end + 1))
+}
@@ -0,0 +1,124 @@
+//! Block-level incremental reparsing.
+//!
+//! Blocks are parsed independently of each other once the parser is at their
+//! start, so after an edit only the blocks around it have to be parsed again.
+//! What limits the window is whether the text after a block can change where
+//! it ends: a paragraph runs until a blank line, so a window can only end at a
+//! paragraph followed by one, while a header or list item always ends with its
+//! line. Those safe boundaries are tracked as [`BlockSpan::resync`].
+
+use std::ops::Range;
+
+use crate::MarkdownNode;
+
+/// Where a parsed block lies in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSpan {
+    pub range: Range<usize>,
+    /// Whether parsing can restart right after this block, no matter what the
+    /// block contains, so a reparse window may end here.
+    pub resync: bool,
+}
+
+impl BlockSpan {
+    /// `following` is the source text right after the block, two bytes are enough
+    pub fn new(block: &MarkdownNode, range: Range<usize>, following: &str) -> Self {
+        let resync = match block {
+            MarkdownNode::Paragraph(_) => following.is_empty() || following.starts_with("\n\n"),
+            MarkdownNode::Header { .. } | MarkdownNode::ListItem { .. } => {
+                following.is_empty() || following.starts_with(['\n', '\r'])
+            }
+            // The definition takes its line break along
+            MarkdownNode::FootnoteDefinition { .. } => true,
+            MarkdownNode::Invalid => false,
+        };
+
+        Self { range, resync }
+    }
+}
+
+/// Part of a document to parse again after an edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reparse {
+    /// Indices of the blocks that are replaced
+    pub blocks: Range<usize>,
+    /// Byte range of the replaced text before the edit
+    pub old: Range<usize>,
+    /// Byte range to parse in the edited text, starting at a block start
+    pub new: Range<usize>,
+}
+
+impl Reparse {
+    /// Finds the blocks an edit of `edit` (in old bytes) to `inserted_len`
+    /// bytes can affect. Returns `None` if the whole document must be parsed
+    /// again, e.g. when the frontmatter was edited.
+    pub fn new(
+        blocks: &[BlockSpan],
+        edit: Range<usize>,
+        inserted_len: usize,
+        old_len: usize,
+    ) -> Option<Self> {
+        // Nothing before the first block is a block, frontmatter included
+        if blocks.first()?.range.start > edit.start {
+            return None;
+        }
+
+        let mut first = blocks.partition_point(|block| block.range.start <= edit.start) - 1;
+        // The previous paragraph may look ahead into the first edited block
+        while first > 0 && !blocks[first - 1].resync {
+            first -= 1;
+        }
+
+        let mut last = first.max(blocks.partition_point(|block| block.range.end < edit.end));
+        while last < blocks.len() && !blocks[last].resync {
+            last += 1;
+        }
+
+        let start = blocks[first].range.start;
+        let (replaced, old_end) = match blocks.get(last) {
+            Some(block) => (first..last + 1, block.range.end),
+            None => (first..blocks.len(), old_len),
+        };
+        let new_end = (old_end + inserted_len).checked_sub(edit.len())?;
+
+        Some(Self {
+            blocks: replaced,
+            old: start..old_end,
+            new: start..new_end,
+        })
+    }
+
+    /// Takes in the blocks up to the next resync point, for when the reparsed
+    /// blocks no longer end at one, e.g. a list item became a paragraph that
+    /// continues on the next line. Returns `false` if the window already
+    /// reaches the end of the document.
+    pub fn extend(&mut self, blocks: &[BlockSpan], old_len: usize) -> bool {
+        let mut last = self.blocks.end;
+        if last >= blocks.len() {
+            return false;
+        }
+        while last < blocks.len() && !blocks[last].resync {
+            last += 1;
+        }
+
+        let old_end = blocks.get(last).map_or(old_len, |block| block.range.end);
+        self.new.end += old_end - self.old.end;
+        self.old.end = old_end;
+        self.blocks.end = (last + 1).min(blocks.len());
+
+        true
+    }
+
+    /// Replaces the reparsed blocks with `new_blocks`, whose ranges are
+    /// already in the edited text, and moves the blocks after them.
+    pub fn splice(&self, blocks: &mut Vec<BlockSpan>, new_blocks: Vec<BlockSpan>) {
+        let old_end = self.old.end;
+        let new_end = self.new.end;
+
+        for block in &mut blocks[self.blocks.end..] {
+            block.range =
+                block.range.start - old_end + new_end..block.range.end - old_end + new_end;
+        }
+        blocks.splice(self.blocks.clone(), new_blocks);
+    }
+}
@@ -1,27 +1,37 @@
 use chumsky::prelude::*;
 
+use error::{MarkdownError, ParseErrorKind};
 use markdown::{footnote_definition_parser, header_parser, list_item_parser, paragraph_parser};
 use yaml::{Frontmatter, yaml_parser};
 
-pub use chumsky::Parser;
+pub use chumsky::{Parser, span::SimpleSpan};
+pub use escape::unescape;
 
+pub mod cst;
+pub mod error;
+mod escape;
+pub mod incremental;
 pub mod markdown;
+pub mod visit;
 pub mod yaml;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Spanned<T>(pub T, pub SimpleSpan);
 
-pub type ParseError<'a> = extra::Err<Rich<'a, char>>;
+pub type ParseError<'a> = extra::Err<MarkdownError<'a>>;
 
 pub type MarkdownText<'a> = Vec<Spanned<InlineMarkdownNode<'a>>>;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParsedMarkdown<'a> {
     pub frontmatter: Option<Frontmatter<'a>>,
     pub body: Vec<Spanned<MarkdownNode<'a>>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MarkdownNode<'a> {
     Header {
         level: usize,
@@ -33,13 +43,113 @@ pub enum MarkdownNode<'a> {
         content: MarkdownText<'a>,
     },
     ListItem {
-        checkbox: Option<bool>,
+        checkbox: Option<Spanned<TaskState>>,
         content: MarkdownText<'a>,
     },
     Invalid,
 }
 
+/// State of a task list checkbox, e.g. `- [x] Task`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TaskState {
+    /// `[ ]`
+    Todo,
+    /// `[x]` or `[X]`
+    Done,
+    /// `[/]`
+    InProgress,
+    /// `[-]`
+    Cancelled,
+    /// `[>]`
+    Deferred,
+    /// `[?]`
+    Question,
+    /// Any other single character, as used by custom Obsidian themes
+    Other(char),
+}
+
+impl TaskState {
+    pub fn from_marker(marker: char) -> Self {
+        match marker {
+            ' ' => TaskState::Todo,
+            'x' | 'X' => TaskState::Done,
+            '/' => TaskState::InProgress,
+            '-' => TaskState::Cancelled,
+            '>' => TaskState::Deferred,
+            '?' => TaskState::Question,
+            other => TaskState::Other(other),
+        }
+    }
+
+    /// The character written between the checkbox brackets
+    pub fn marker(&self) -> char {
+        match self {
+            TaskState::Todo => ' ',
+            TaskState::Done => 'x',
+            TaskState::InProgress => '/',
+            TaskState::Cancelled => '-',
+            TaskState::Deferred => '>',
+            TaskState::Question => '?',
+            TaskState::Other(marker) => *marker,
+        }
+    }
+
+    /// Name of the state as used in queries, e.g. `in-progress`. Custom
+    /// states are named by their marker, so [`TaskState::from_name`] reads
+    /// every name back.
+    pub fn name(&self) -> std::borrow::Cow<'static, str> {
+        match self {
+            TaskState::Todo => "todo".into(),
+            TaskState::Done => "done".into(),
+            TaskState::InProgress => "in-progress".into(),
+            TaskState::Cancelled => "cancelled".into(),
+            TaskState::Deferred => "deferred".into(),
+            TaskState::Question => "question".into(),
+            TaskState::Other(marker) => marker.to_string().into(),
+        }
+    }
+
+    /// Parses a state name, or a single checkbox character such as `!`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let state = match name.to_lowercase().as_str() {
+            "todo" => TaskState::Todo,
+            "done" => TaskState::Done,
+            "in-progress" | "inprogress" => TaskState::InProgress,
+            "cancelled" | "canceled" => TaskState::Cancelled,
+            "deferred" => TaskState::Deferred,
+            "question" => TaskState::Question,
+            _ => {
+                let mut chars = name.chars();
+                let marker = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                TaskState::from_marker(marker)
+            }
+        };
+
+        Some(state)
+    }
+
+    /// Whether the task needs no further action
+    pub fn is_closed(&self) -> bool {
+        matches!(self, TaskState::Done | TaskState::Cancelled)
+    }
+
+    /// Next state when cycling a checkbox: `[ ]` -> `[/]` -> `[x]` -> `[ ]`.
+    /// Any other state goes back to todo.
+    pub fn cycle(&self) -> Self {
+        match self {
+            TaskState::Todo => TaskState::InProgress,
+            TaskState::InProgress => TaskState::Done,
+            _ => TaskState::Todo,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum LinkType<'a> {
     InlineLink {
         text: &'a str,
@@ -58,33 +168,90 @@ pub enum LinkType<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum InlineMarkdownNode<'a> {
     PlainText(&'a str),
     Link(LinkType<'a>),
+    /// Tag without the `#`, nested tags keep their full path, e.g. `project/alpha`
     Tag(&'a str),
     Footnote(&'a str),
+    /// Dataview-style inline field, either `key:: value` at the start of a line
+    /// or bracketed `[key:: value]` anywhere in the text.
+    InlineField {
+        key: &'a str,
+        /// Raw, trimmed value text
+        value: &'a str,
+        /// Inline nodes parsed from the value, so links inside fields are still found
+        content: MarkdownText<'a>,
+    },
+}
+
+/// Returns the span of `slice` within `source`, if `slice` is borrowed from it.
+///
+/// Useful for nodes that only keep `&str` slices, such as frontmatter keys and values.
+pub fn slice_span(source: &str, slice: &str) -> Option<SimpleSpan> {
+    let start = (slice.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+    let end = start + slice.len();
+
+    (end <= source.len()).then(|| SimpleSpan::from(start..end))
+}
+
+/// Grammar rules that Markdown flavours and teams disagree on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// Treat purely numeric tags like `#123` as tags rather than issue numbers
+    pub numeric_tags: bool,
 }
 
 pub fn markdown_parser<'a>() -> impl Parser<'a, &'a str, ParsedMarkdown<'a>, ParseError<'a>> {
+    markdown_parser_with(ParseOptions::default())
+}
+
+pub fn markdown_parser_with<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, ParsedMarkdown<'a>, ParseError<'a>> {
     yaml_parser()
         .or_not()
-        .then(
+        .then(blocks_parser(options))
+        .then_ignore(end().or_not())
+        .validate(|(frontmatter, body), e, emitter| {
+            // A document opening with `---` meant to have frontmatter
+            let source: &str = e.slice();
+            let first_line = source.lines().next().unwrap_or_default();
+            if frontmatter.is_none() && first_line.trim_end() == "---" {
+                emitter.emit(MarkdownError::new(
+                    SimpleSpan::from(0..3),
+                    ParseErrorKind::MalformedFrontmatter,
+                ));
+            }
+            ParsedMarkdown { frontmatter, body }
+        })
+}
+
+/// Parses the blocks of a document body, without frontmatter.
+///
+/// Used on its own to reparse the blocks of an [`incremental::Reparse`] window.
+pub fn blocks_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, Vec<Spanned<MarkdownNode<'a>>>, ParseError<'a>> {
+    // Checked first so that recovery, which also stops at the end, always skips something
+    end()
+        .not()
+        .ignore_then(
             choice((
                 header_parser(),
-                footnote_definition_parser(),
-                list_item_parser(),
-                paragraph_parser(),
+                footnote_definition_parser(options),
+                list_item_parser(options),
+                paragraph_parser(options),
             ))
             .recover_with(skip_until(
                 any().ignored(),
-                text::newline().ignored(),
+                text::newline().ignored().or(end()),
                 || MarkdownNode::Invalid,
-            ))
-            .map_with(|block, e| Spanned(block, e.span()))
-            .then_ignore(choice((text::whitespace(), text::newline())))
-            .repeated()
-            .collect(),
+            )),
         )
-        .then_ignore(end().or_not())
-        .map(|(frontmatter, body)| ParsedMarkdown { frontmatter, body })
+        .map_with(|block, e| Spanned(block, e.span()))
+        .then_ignore(choice((text::whitespace(), text::newline())))
+        .repeated()
+        .collect()
 }
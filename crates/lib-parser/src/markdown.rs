@@ -1,6 +1,11 @@
 use chumsky::prelude::*;
 
-use crate::{InlineMarkdownNode, LinkType, MarkdownNode, ParseError, Spanned};
+use crate::{
+    InlineMarkdownNode, LinkType, MarkdownNode, MarkdownText, ParseError, ParseOptions, Spanned,
+    TaskState,
+    error::{MarkdownError, ParseErrorKind},
+    escape::{escape_parser, unescape},
+};
 
 pub fn header_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>> {
     let hashes = just('#')
@@ -34,48 +39,122 @@ pub fn header_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseEr
         .labelled("Header Parser")
 }
 
-pub fn tag_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
+/// Whether `c` can appear in a tag segment: letters, digits and marks of any
+/// script, emoji, `-` and `_`. Whitespace and punctuation end the tag.
+pub fn is_tag_char(c: char) -> bool {
+    if c.is_ascii() {
+        return c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    }
+
+    c.is_alphanumeric()
+        // Zero width joiner, used by emoji sequences
+        || c == '\u{200D}'
+        || !(c.is_whitespace()
+            || c.is_control()
+            || matches!(
+                c,
+                '\u{00A0}'..='\u{00BF}'
+                    | '\u{00D7}'
+                    | '\u{00F7}'
+                    // General and supplemental punctuation
+                    | '\u{2000}'..='\u{206F}'
+                    | '\u{2E00}'..='\u{2E7F}'
+                    // CJK symbols and punctuation, e.g. `、` and `。`
+                    | '\u{3000}'..='\u{303F}'
+                    // Fullwidth punctuation
+                    | '\u{FF00}'..='\u{FF0F}'
+                    | '\u{FF1A}'..='\u{FF20}'
+                    | '\u{FF3B}'..='\u{FF40}'
+                    | '\u{FF5B}'..='\u{FF65}'
+            ))
+}
+
+/// Parses `#tag` and nested `#parent/child` tags, the tag keeps its full path.
+///
+/// Purely numeric tags like `#123` are rejected unless [`ParseOptions::numeric_tags`] is set.
+/// Tags only start after whitespace or at the start of a line, the plain text
+/// parsers make sure `C#` never reaches this parser.
+pub fn tag_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> + Clone {
+    let segment = escaped_char(is_tag_char)
+        .or(any().filter(|c: &char| is_tag_char(*c)).ignored())
+        .repeated()
+        .at_least(1);
+
     just('#')
         .ignore_then(
-            any()
-                .filter(|c: &char| c.is_alphanumeric() || *c == '-' || *c == '_')
-                .repeated()
-                .at_least(1)
+            segment
+                .clone()
+                .then(just('/').then(segment).repeated())
                 .to_slice(),
         )
+        .filter(move |tag: &&str| {
+            options.numeric_tags
+                || unescape(tag)
+                    .chars()
+                    .any(|c| !c.is_ascii_digit() && c != '/')
+        })
         .map(InlineMarkdownNode::Tag)
         .labelled("Tag Parser")
 }
 
+/// A character of plain text. A non-whitespace character takes any `#` right
+/// after it along, so `C#` or `issue#12` never start a tag.
+fn plain_text_char<'a>(
+    stop_condition: impl Parser<'a, &'a str, (), ParseError<'a>> + Clone,
+) -> impl Parser<'a, &'a str, (), ParseError<'a>> {
+    any()
+        .and_is(stop_condition.clone().not())
+        .then_ignore(just('#').repeated())
+        .filter(|c: &char| !c.is_whitespace())
+        .or(any().and_is(stop_condition.not()))
+        .ignored()
+}
+
+/// A backslash escape or entity reference decoding to a character `accept` allows
+fn escaped_char<'a>(
+    accept: fn(char) -> bool,
+) -> impl Parser<'a, &'a str, (), ParseError<'a>> + Clone {
+    escape_parser()
+        .to_slice()
+        .filter(move |raw: &&str| unescape(raw).chars().all(accept))
+        .ignored()
+}
+
+fn is_footnote_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// A footnote id, escapes included, e.g. `note\_1`
+fn footnote_id<'a>() -> impl Parser<'a, &'a str, &'a str, ParseError<'a>> + Clone {
+    escaped_char(is_footnote_id_char)
+        .or(any().filter(|c: &char| is_footnote_id_char(*c)).ignored())
+        .repeated()
+        .at_least(1)
+        .to_slice()
+}
+
 pub fn footnote_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
     just("[^")
-        .ignore_then(
-            any()
-                .filter(|c: &char| c.is_alphanumeric() || *c == '-' || *c == '_')
-                .repeated()
-                .at_least(1)
-                .to_slice(),
-        )
-        .then_ignore(just("]"))
+        .ignore_then(footnote_id())
+        .then_ignore(just("]").map_err(|e: MarkdownError| {
+            MarkdownError::new(*e.span(), ParseErrorKind::UnclosedFootnote)
+        }))
         .map(InlineMarkdownNode::Footnote)
         .labelled("Footnote Parser")
 }
 
-pub fn footnote_definition_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>>
-{
+pub fn footnote_definition_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>> {
     let id = just("[^")
-        .ignore_then(
-            any()
-                .filter(|c: &char| c.is_alphanumeric() || *c == '-' || *c == '_')
-                .repeated()
-                .at_least(1)
-                .to_slice(),
-        )
+        .ignore_then(footnote_id())
         .then_ignore(just("]"))
         .then_ignore(just(":"))
         .labelled("Footnote Def Id");
 
-    let inline_text = inline_parser()
+    let inline_text = inline_parser(options)
         .map_with(|block, e| Spanned(block, e.span()))
         .repeated()
         .at_least(1)
@@ -88,15 +167,17 @@ pub fn footnote_definition_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode
 }
 
 pub fn wikilink_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
-    let alias = any()
-        .filter(|c: &char| *c != ']' && *c != '\n')
+    let alias = escape_parser()
+        .or(any().filter(|c: &char| *c != ']' && *c != '\n').ignored())
         .repeated()
         .to_slice()
         .map(|alias: &'a str| alias.trim())
         .map(|alias| (!alias.is_empty()).then_some(alias));
 
-    let header_content = any()
-        .filter(|c: &char| !['|', ']', '\n'].contains(c))
+    let header_content = escape_parser()
+        .or(any()
+            .filter(|c: &char| !['|', ']', '\n'].contains(c))
+            .ignored())
         .repeated()
         .at_least(1)
         .to_slice()
@@ -112,18 +193,24 @@ pub fn wikilink_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>,
         just("]]").to(None),
     ));
 
-    let target = any()
-        .filter(|c: &char| !['#', ']', '|', '\n'].contains(c))
+    let target = escape_parser()
+        .or(any()
+            .filter(|c: &char| !['#', ']', '|', '\n'].contains(c))
+            .ignored())
         .repeated()
         .at_least(1)
         .to_slice()
         .map(|s: &'a str| s.trim())
-        .then(header.or_not())
-        .then(possible_alias);
+        .map_err(|e: MarkdownError| {
+            MarkdownError::new(*e.span(), ParseErrorKind::EmptyWikiLinkTarget)
+        });
 
     just("[[")
         .ignore_then(target)
-        .map_err(|e: Rich<char>| Rich::custom(*e.span(), "WikiLink format is invalid."))
+        .then(header.or_not())
+        .then(possible_alias.map_err(|e: MarkdownError| {
+            MarkdownError::new(*e.span(), ParseErrorKind::UnclosedWikiLink)
+        }))
         .map(|((target, header), display_text)| {
             InlineMarkdownNode::Link(LinkType::WikiLink {
                 target,
@@ -135,16 +222,16 @@ pub fn wikilink_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>,
 }
 
 pub fn link_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
-    let title = any()
-        .filter(|c: &char| *c != ']' && *c != '\n')
+    let title = escape_parser()
+        .or(any().filter(|c: &char| *c != ']' && *c != '\n').ignored())
         .repeated()
         .at_least(1)
         .to_slice()
         .map(|title: &str| title.trim())
         .labelled("Link Title Parser");
 
-    let header_content = any()
-        .filter(|c: &char| ![')', '\n'].contains(c))
+    let header_content = escape_parser()
+        .or(any().filter(|c: &char| ![')', '\n'].contains(c)).ignored())
         .repeated()
         .at_least(1)
         .to_slice()
@@ -155,23 +242,37 @@ pub fn link_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, Par
         .map(|content| content)
         .labelled("Header Level Parser");
 
-    let uri = any()
-        .filter(|c: &char| !['#', ')', '\n'].contains(c))
+    let uri = escape_parser()
+        .or(any()
+            .filter(|c: &char| !['#', ')', '\n'].contains(c))
+            .ignored())
         .repeated()
         .to_slice()
         .map(|uri: &str| uri.trim())
         .then(header.or_not())
         .labelled("Link URL Parser");
 
+    // Every part after the `[` reports its own error kind
+    let error =
+        |kind: ParseErrorKind| move |e: MarkdownError<'a>| MarkdownError::new(*e.span(), kind);
+
+    // `[[` and `[^` belong to wikilinks and footnotes, which report their own errors
     just('[')
-        .ignore_then(title)
-        .then_ignore(just(']'))
-        // TODO: Build out these errors for for information
-        .map_err(|e: Rich<char>| Rich::custom(*e.span(), "1. Link format is invalid."))
-        .then_ignore(just('('))
+        .and_is(choice((just("[["), just("[^"))).not())
+        .ignore_then(title.map_err(error(ParseErrorKind::EmptyLinkText)))
+        .then_ignore(just(']').map_err(error(ParseErrorKind::UnclosedLinkText)))
+        .then_ignore(just('(').map_err(error(ParseErrorKind::MissingLinkDestination)))
         .then(uri)
-        .map_err(|e: Rich<char>| Rich::custom(*e.span(), "2. Link format is invalid."))
-        .then_ignore(just(')'))
+        .then_ignore(just(')').map_err(error(ParseErrorKind::UnclosedLinkDestination)))
+        .validate(|(text, (uri, header)), e, emitter| {
+            if uri.is_empty() && header.is_none() {
+                emitter.emit(MarkdownError::new(
+                    e.span(),
+                    ParseErrorKind::EmptyLinkTarget,
+                ));
+            }
+            (text, (uri, header))
+        })
         .map(|(text, (uri, header))| {
             InlineMarkdownNode::Link(LinkType::InlineLink { text, uri, header })
         })
@@ -179,13 +280,13 @@ pub fn link_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, Par
 }
 
 pub fn image_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
-    let alt = any()
-        .filter(|c: &char| *c != ']' && *c != '\n')
+    let alt = escape_parser()
+        .or(any().filter(|c: &char| *c != ']' && *c != '\n').ignored())
         .repeated()
         .to_slice();
 
-    let uri = any()
-        .filter(|c: &char| *c != ')' && *c != '\n')
+    let uri = escape_parser()
+        .or(any().filter(|c: &char| *c != ')' && *c != '\n').ignored())
         .repeated()
         .at_least(1)
         .to_slice();
@@ -199,25 +300,130 @@ pub fn image_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, Pa
         .labelled("Image")
 }
 
-pub fn plain_text_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
-    let stop_condition = choice((just("#"), just("["), just("!["), just("\n\n"))).rewind();
-
+fn inline_field_key<'a>() -> impl Parser<'a, &'a str, &'a str, ParseError<'a>> {
     any()
-        .and_is(stop_condition.not())
+        .filter(|c: &char| c.is_alphanumeric())
+        .then(
+            any()
+                .filter(|c: &char| c.is_alphanumeric() || [' ', '-', '_'].contains(c))
+                .repeated(),
+        )
+        .to_slice()
+        .map(|key: &'a str| key.trim_end())
+        .labelled("Inline Field Key")
+}
+
+/// Matches `key::` followed by whitespace or the end of the line, so that
+/// things like `std::vec` are not mistaken for fields.
+fn inline_field_start<'a>() -> impl Parser<'a, &'a str, &'a str, ParseError<'a>> {
+    inline_field_key()
+        .then_ignore(just("::"))
+        .then_ignore(choice((
+            one_of(" \t").ignored(),
+            text::newline().rewind(),
+            end(),
+        )))
+        .labelled("Inline Field Start")
+}
+
+/// Parses the inline nodes of a field value, returning the trimmed raw value alongside them.
+fn inline_field_value<'a>(
+    plain_text: impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>>,
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, (&'a str, MarkdownText<'a>), ParseError<'a>> {
+    choice((
+        tag_parser(options),
+        image_parser(),
+        wikilink_parser(),
+        footnote_parser(),
+        link_parser(),
+        plain_text,
+    ))
+    .map_with(|inline, e| Spanned(inline, e.span()))
+    .repeated()
+    .collect()
+    .map_with(|content, e| (e.slice().trim(), content))
+}
+
+pub fn inline_field_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
+    // Plain text inside `[key:: value]` also has to stop at the closing bracket
+    let stop_condition = choice((
+        tag_parser(options).ignored(),
+        just("[").ignored(),
+        just("![").and_is(just("![[").not()).ignored(),
+        just("]").ignored(),
+        just("\n").ignored(),
+    ))
+    .rewind();
+
+    let bracketed_plain_text = escape_parser()
+        .or(plain_text_char(stop_condition))
         .repeated()
         .at_least(1)
         .to_slice()
+        .map(InlineMarkdownNode::PlainText);
+
+    let bracketed = just('[')
+        .ignore_then(inline_field_key())
+        .then_ignore(just("::"))
+        .then(inline_field_value(bracketed_plain_text, options))
+        .then_ignore(just(']'));
+
+    let line =
+        inline_field_start().then(inline_field_value(line_plain_text_parser(options), options));
+
+    choice((bracketed, line))
+        .map(|(key, (value, content))| InlineMarkdownNode::InlineField {
+            key,
+            value,
+            content,
+        })
+        .labelled("Inline Field")
+}
+
+pub fn plain_text_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
+    let stop_condition = choice((
+        tag_parser(options).ignored(),
+        just("[").ignored(),
+        // The `!` of a `![[note]]` embed is text before the wikilink
+        just("![").and_is(just("![[").not()).ignored(),
+        just("\n\n").ignored(),
+    ))
+    .rewind();
+
+    // A `key:: value` line inside a paragraph ends the text run, but the newline
+    // before it still belongs to the text so the field starts at its key.
+    let newline_before_field = || text::newline().then(inline_field_start()).rewind();
+
+    // Escapes are consumed whole so `\#` or `&#35;` never start a tag
+    escape_parser()
+        .or(plain_text_char(stop_condition).and_is(newline_before_field().not()))
+        .repeated()
+        .then(newline_before_field().then(text::newline()).or_not())
+        .to_slice()
+        .filter(|s: &&str| !s.is_empty())
         .map(InlineMarkdownNode::PlainText)
         .labelled("Plain Text")
 }
 
 // Line-bounded plain text parser for use in list items (stops at single newline)
-pub fn line_plain_text_parser<'a>()
--> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
-    let stop_condition = choice((just("#"), just("["), just("!["), just("\n"))).rewind();
+pub fn line_plain_text_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
+    let stop_condition = choice((
+        tag_parser(options).ignored(),
+        just("[").ignored(),
+        just("![").and_is(just("![[").not()).ignored(),
+        just("\n").ignored(),
+    ))
+    .rewind();
 
-    any()
-        .and_is(stop_condition.not())
+    escape_parser()
+        .or(plain_text_char(stop_condition))
         .repeated()
         .at_least(1)
         .to_slice()
@@ -226,49 +432,63 @@ pub fn line_plain_text_parser<'a>()
 }
 
 // Line-bounded inline parser for use in list items
-pub fn line_inline_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>>
-{
+pub fn line_inline_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
     choice((
-        tag_parser(),
+        tag_parser(options),
         image_parser(),
         wikilink_parser(),
         footnote_parser(),
+        inline_field_parser(options),
         link_parser(),
-        line_plain_text_parser(),
+        line_plain_text_parser(options),
     ))
     .labelled("Line Inline Parser")
 }
 
-pub fn inline_parser<'a>() -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
+pub fn inline_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, InlineMarkdownNode<'a>, ParseError<'a>> {
     choice((
-        tag_parser(),
+        tag_parser(options),
         image_parser(),
         wikilink_parser(),
+        inline_field_parser(options),
         link_parser(),
         footnote_parser(),
-        plain_text_parser(),
+        plain_text_parser(options),
     ))
     .labelled("Inline Parser")
 }
 
-pub fn list_item_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>> {
+pub fn list_item_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>> {
     let marker = choice((just('-'), just('*')))
         .then_ignore(text::inline_whitespace())
         .labelled("list marker");
 
     let checkbox = just('[')
-        .ignore_then(choice((
-            just(' ').to(Some(false)),
-            just('x').to(Some(true)),
-            just('X').to(Some(true)),
-        )))
+        .ignore_then(
+            any()
+                .filter(|c: &char| *c == ' ' || !(c.is_whitespace() || ['[', ']'].contains(c)))
+                .map(TaskState::from_marker),
+        )
         .then_ignore(just(']'))
+        .map_with(|state, e| Spanned(state, e.span()))
+        // Requiring a gap keeps `- [a](link)` a link rather than a task
+        .then_ignore(choice((
+            one_of(" \t").ignored(),
+            text::newline().rewind(),
+            end(),
+        )))
         .then_ignore(text::inline_whitespace())
         .or_not()
         .labelled("checkbox");
 
     // Use line_inline_parser to prevent consuming across newlines
-    let content = line_inline_parser()
+    let content = line_inline_parser(options)
         .map_with(|inline_block, e| Spanned(inline_block, e.span()))
         .repeated()
         .at_least(1)
@@ -277,15 +497,14 @@ pub fn list_item_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode<'a>, Pars
     marker
         .ignore_then(checkbox)
         .then(content)
-        .map(|(checkbox, content)| MarkdownNode::ListItem {
-            checkbox: checkbox.flatten(),
-            content,
-        })
+        .map(|(checkbox, content)| MarkdownNode::ListItem { checkbox, content })
         .labelled("List Item")
 }
 
-pub fn paragraph_parser<'a>() -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>> {
-    inline_parser()
+pub fn paragraph_parser<'a>(
+    options: ParseOptions,
+) -> impl Parser<'a, &'a str, MarkdownNode<'a>, ParseError<'a>> {
+    inline_parser(options)
         .map_with(|inline_block, e| Spanned(inline_block, e.span()))
         .repeated()
         .at_least(1)
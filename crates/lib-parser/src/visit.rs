@@ -0,0 +1,262 @@
+//! Traversal of the parsed AST.
+//!
+//! [`Visitor`] has a callback per node type, each taking the node's fields
+//! and its span. The default callbacks of nodes with children walk into them
+//! through the `walk_*` functions, so an implementation only overrides what
+//! it is interested in, and calls the matching `walk_*` function when it
+//! still wants the children after overriding a parent:
+//!
+//! ```
+//! use lib_parser::{Parser, SimpleSpan, markdown_parser, visit::Visitor};
+//!
+//! #[derive(Default)]
+//! struct Tags<'a>(Vec<&'a str>);
+//!
+//! impl<'a> Visitor<'a> for Tags<'a> {
+//!     fn visit_tag(&mut self, name: &'a str, _span: SimpleSpan) {
+//!         self.0.push(name);
+//!     }
+//! }
+//!
+//! let source = "# Notes\n\n- [ ] #todo [due:: #soon]\n";
+//! let parsed = markdown_parser().parse(source).into_output().unwrap();
+//! let mut tags = Tags::default();
+//! tags.visit_markdown(&parsed);
+//! assert_eq!(tags.0, vec!["todo", "soon"]);
+//! ```
+//!
+//! [`VisitorMut`] is the same over mutable nodes, for rewriting a tree in place.
+
+use chumsky::span::SimpleSpan;
+
+use crate::{
+    InlineMarkdownNode, LinkType, MarkdownNode, MarkdownText, ParsedMarkdown, Spanned, TaskState,
+    yaml::Frontmatter,
+};
+
+pub trait Visitor<'a> {
+    fn visit_markdown(&mut self, markdown: &ParsedMarkdown<'a>) {
+        walk_markdown(self, markdown);
+    }
+
+    fn visit_frontmatter(&mut self, _frontmatter: &Frontmatter<'a>) {}
+
+    fn visit_block(&mut self, block: &Spanned<MarkdownNode<'a>>) {
+        walk_block(self, block);
+    }
+
+    fn visit_header(&mut self, _level: usize, _content: &'a str, _span: SimpleSpan) {}
+
+    fn visit_paragraph(&mut self, content: &MarkdownText<'a>, _span: SimpleSpan) {
+        walk_text(self, content);
+    }
+
+    fn visit_list_item(
+        &mut self,
+        _checkbox: Option<&Spanned<TaskState>>,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text(self, content);
+    }
+
+    fn visit_footnote_definition(
+        &mut self,
+        _id: &'a str,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text(self, content);
+    }
+
+    /// A line the parser skipped to recover from an error
+    fn visit_invalid(&mut self, _span: SimpleSpan) {}
+
+    fn visit_inline(&mut self, inline: &Spanned<InlineMarkdownNode<'a>>) {
+        walk_inline(self, inline);
+    }
+
+    fn visit_plain_text(&mut self, _text: &'a str, _span: SimpleSpan) {}
+
+    fn visit_link(&mut self, _link: &LinkType<'a>, _span: SimpleSpan) {}
+
+    fn visit_tag(&mut self, _name: &'a str, _span: SimpleSpan) {}
+
+    fn visit_footnote(&mut self, _id: &'a str, _span: SimpleSpan) {}
+
+    fn visit_inline_field(
+        &mut self,
+        _key: &'a str,
+        _value: &'a str,
+        content: &MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text(self, content);
+    }
+}
+
+pub fn walk_markdown<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, markdown: &ParsedMarkdown<'a>) {
+    if let Some(frontmatter) = &markdown.frontmatter {
+        visitor.visit_frontmatter(frontmatter);
+    }
+    for block in &markdown.body {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_block<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, block: &Spanned<MarkdownNode<'a>>) {
+    let Spanned(node, span) = block;
+    match node {
+        MarkdownNode::Header { level, content } => visitor.visit_header(*level, content, *span),
+        MarkdownNode::Paragraph(content) => visitor.visit_paragraph(content, *span),
+        MarkdownNode::ListItem { checkbox, content } => {
+            visitor.visit_list_item(checkbox.as_ref(), content, *span)
+        }
+        MarkdownNode::FootnoteDefinition { id, content } => {
+            visitor.visit_footnote_definition(id, content, *span)
+        }
+        MarkdownNode::Invalid => visitor.visit_invalid(*span),
+    }
+}
+
+pub fn walk_text<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, text: &MarkdownText<'a>) {
+    for inline in text {
+        visitor.visit_inline(inline);
+    }
+}
+
+pub fn walk_inline<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    inline: &Spanned<InlineMarkdownNode<'a>>,
+) {
+    let Spanned(node, span) = inline;
+    match node {
+        InlineMarkdownNode::PlainText(text) => visitor.visit_plain_text(text, *span),
+        InlineMarkdownNode::Link(link) => visitor.visit_link(link, *span),
+        InlineMarkdownNode::Tag(name) => visitor.visit_tag(name, *span),
+        InlineMarkdownNode::Footnote(id) => visitor.visit_footnote(id, *span),
+        InlineMarkdownNode::InlineField {
+            key,
+            value,
+            content,
+        } => visitor.visit_inline_field(key, value, content, *span),
+    }
+}
+
+/// [`Visitor`] over mutable nodes. Spans are passed by value, as they point
+/// into the source and editing a node does not move it.
+pub trait VisitorMut<'a> {
+    fn visit_markdown_mut(&mut self, markdown: &mut ParsedMarkdown<'a>) {
+        walk_markdown_mut(self, markdown);
+    }
+
+    fn visit_frontmatter_mut(&mut self, _frontmatter: &mut Frontmatter<'a>) {}
+
+    fn visit_block_mut(&mut self, block: &mut Spanned<MarkdownNode<'a>>) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_header_mut(&mut self, _level: &mut usize, _content: &mut &'a str, _span: SimpleSpan) {}
+
+    fn visit_paragraph_mut(&mut self, content: &mut MarkdownText<'a>, _span: SimpleSpan) {
+        walk_text_mut(self, content);
+    }
+
+    fn visit_list_item_mut(
+        &mut self,
+        _checkbox: &mut Option<Spanned<TaskState>>,
+        content: &mut MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text_mut(self, content);
+    }
+
+    fn visit_footnote_definition_mut(
+        &mut self,
+        _id: &mut &'a str,
+        content: &mut MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text_mut(self, content);
+    }
+
+    fn visit_invalid_mut(&mut self, _span: SimpleSpan) {}
+
+    fn visit_inline_mut(&mut self, inline: &mut Spanned<InlineMarkdownNode<'a>>) {
+        walk_inline_mut(self, inline);
+    }
+
+    fn visit_plain_text_mut(&mut self, _text: &mut &'a str, _span: SimpleSpan) {}
+
+    fn visit_link_mut(&mut self, _link: &mut LinkType<'a>, _span: SimpleSpan) {}
+
+    fn visit_tag_mut(&mut self, _name: &mut &'a str, _span: SimpleSpan) {}
+
+    fn visit_footnote_mut(&mut self, _id: &mut &'a str, _span: SimpleSpan) {}
+
+    fn visit_inline_field_mut(
+        &mut self,
+        _key: &mut &'a str,
+        _value: &mut &'a str,
+        content: &mut MarkdownText<'a>,
+        _span: SimpleSpan,
+    ) {
+        walk_text_mut(self, content);
+    }
+}
+
+pub fn walk_markdown_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    markdown: &mut ParsedMarkdown<'a>,
+) {
+    if let Some(frontmatter) = &mut markdown.frontmatter {
+        visitor.visit_frontmatter_mut(frontmatter);
+    }
+    for block in &mut markdown.body {
+        visitor.visit_block_mut(block);
+    }
+}
+
+pub fn walk_block_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    block: &mut Spanned<MarkdownNode<'a>>,
+) {
+    let Spanned(node, span) = block;
+    let span = *span;
+    match node {
+        MarkdownNode::Header { level, content } => visitor.visit_header_mut(level, content, span),
+        MarkdownNode::Paragraph(content) => visitor.visit_paragraph_mut(content, span),
+        MarkdownNode::ListItem { checkbox, content } => {
+            visitor.visit_list_item_mut(checkbox, content, span)
+        }
+        MarkdownNode::FootnoteDefinition { id, content } => {
+            visitor.visit_footnote_definition_mut(id, content, span)
+        }
+        MarkdownNode::Invalid => visitor.visit_invalid_mut(span),
+    }
+}
+
+pub fn walk_text_mut<'a, V: VisitorMut<'a> + ?Sized>(visitor: &mut V, text: &mut MarkdownText<'a>) {
+    for inline in text {
+        visitor.visit_inline_mut(inline);
+    }
+}
+
+pub fn walk_inline_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    inline: &mut Spanned<InlineMarkdownNode<'a>>,
+) {
+    let Spanned(node, span) = inline;
+    let span = *span;
+    match node {
+        InlineMarkdownNode::PlainText(text) => visitor.visit_plain_text_mut(text, span),
+        InlineMarkdownNode::Link(link) => visitor.visit_link_mut(link, span),
+        InlineMarkdownNode::Tag(name) => visitor.visit_tag_mut(name, span),
+        InlineMarkdownNode::Footnote(id) => visitor.visit_footnote_mut(id, span),
+        InlineMarkdownNode::InlineField {
+            key,
+            value,
+            content,
+        } => visitor.visit_inline_field_mut(key, value, content, span),
+    }
+}
@@ -3,6 +3,7 @@ use chumsky::prelude::*;
 use crate::ParseError;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
 pub enum Yaml<'a> {
     String(&'a str),
     List(Vec<&'a str>),
@@ -11,6 +12,7 @@ pub enum Yaml<'a> {
 type KeyValue<'a> = (&'a str, Yaml<'a>);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Frontmatter<'a>(pub Vec<KeyValue<'a>>);
 
 impl<'a> Frontmatter<'a> {
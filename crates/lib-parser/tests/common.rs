@@ -1,8 +1,8 @@
 use ariadne::{Color, Label, Report, ReportKind, Source};
 use chumsky::prelude::*;
-use lib_parser::{ParseError, ParsedMarkdown, markdown_parser};
+use lib_parser::{ParseError, error::MarkdownError};
 
-pub fn print_parse_errors(src: &str, errs: Vec<Rich<char>>) {
+pub fn print_parse_errors(src: &str, errs: Vec<MarkdownError>) {
     errs.into_iter().for_each(|e| {
         Report::build(ReportKind::Error, ("Test Input", e.span().into_range()))
             .with_message(e.to_string())
@@ -32,14 +32,3 @@ pub fn compare<'a, T: std::fmt::Debug + PartialEq>(
     let output = output.expect("Parser returned no output despite no errors");
     assert_eq!(output, expected);
 }
-
-pub fn parse_unwrap(input: &str) -> ParsedMarkdown<'_> {
-    let (output, errors) = markdown_parser().parse(input).into_output_errors();
-    if !errors.is_empty() {
-        println!("\n=== PARSER FAILED ===");
-        print_parse_errors(input, errors);
-        println!("=====================\n");
-        panic!("Parser failed with errors");
-    }
-    output.expect("Parser returned no output")
-}
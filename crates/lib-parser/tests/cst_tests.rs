@@ -0,0 +1,180 @@
+use lib_parser::{
+    MarkdownNode, Parser,
+    cst::{SyntaxKind, SyntaxNode},
+    markdown_parser,
+};
+
+const SAMPLE: &str = "---\r\ntitle: Notes\r\ntags:\r\n  - a\r\n---\r\n\r\n## Tasks ##  \r\n\
+- [x] Done [[Note#Part|alias]] #tag\r\n\
+* Plain with ![img](a.png) and [^1]\r\n\n\
+Para [text]( dest.md#head) and\nstatus:: done [due:: 2026-01-01]\n\n\
+[^1]: Footnote \\# text\n\n   trailing   \n";
+
+fn tree(source: &str) -> SyntaxNode<'_> {
+    let parsed = markdown_parser().parse(source).into_output().unwrap();
+    parsed.syntax_tree(source)
+}
+
+fn assert_lossless(source: &str) {
+    let tree = tree(source);
+    assert_eq!(tree.to_string(), source);
+
+    let mut offset = 0;
+    for token in tree.tokens() {
+        assert_eq!(token.span.start, offset, "gap or overlap before {token:?}");
+        assert!(!token.text.is_empty(), "empty token {token:?}");
+        offset = token.span.end;
+    }
+    assert_eq!(offset, source.len());
+}
+
+#[test]
+fn test_cst_round_trip() {
+    assert_lossless(SAMPLE);
+    assert_lossless("");
+    assert_lossless("\n\n# Header\n\n- \n");
+    assert_lossless("Broken [[link and [oops\n\nNext");
+
+    for file in ["test_small.md", "test_medium.md", "test_large.md"] {
+        let path = format!("{}/benches/{file}", env!("CARGO_MANIFEST_DIR"));
+        assert_lossless(&std::fs::read_to_string(path).unwrap());
+    }
+}
+
+/// The spec examples, the fuzz seeds and generated mixes of markdown fragments
+#[test]
+fn test_cst_round_trip_property() {
+    for spec in ["commonmark.json", "gfm.json"] {
+        let path = format!("{}/tests/spec/{spec}", env!("CARGO_MANIFEST_DIR"));
+        let examples: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        for example in examples {
+            assert_lossless(example["markdown"].as_str().unwrap());
+        }
+    }
+
+    let seeds = format!("{}/../../fuzz/seeds", env!("CARGO_MANIFEST_DIR"));
+    for entry in std::fs::read_dir(seeds).unwrap() {
+        assert_lossless(&std::fs::read_to_string(entry.unwrap().path()).unwrap());
+    }
+
+    const FRAGMENTS: &[&str] = &[
+        "# ", "## ", "- ", "* ", "[ ] ", "[x] ", "---", "\n", "\r\n", "\t", "  ", "text", "é",
+        "👍", "#tag", "#a/b", "[[", "]]", "|", "[", "](", ")", "![", "[^", "]:", "::", "\\",
+        "&amp;", "&#35;", "`", "*", "_", ">", "1. ", "title: x",
+    ];
+    // A fixed xorshift sequence, so failures reproduce
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+    for _ in 0..2000 {
+        let input: String = (0..next() % 24)
+            .map(|_| FRAGMENTS[next() % FRAGMENTS.len()])
+            .collect();
+        assert_lossless(&input);
+    }
+}
+
+#[test]
+fn test_cst_tokens() {
+    let tree = tree(SAMPLE);
+    let kinds = |kind: SyntaxKind| -> Vec<&str> {
+        tree.tokens()
+            .filter(|token| token.kind == kind)
+            .map(|token| token.text.as_ref())
+            .collect()
+    };
+
+    assert_eq!(kinds(SyntaxKind::FrontmatterDelimiter), vec!["---", "---"]);
+    assert_eq!(
+        kinds(SyntaxKind::FrontmatterContent),
+        vec!["title: Notes\r\ntags:\r\n  - a"]
+    );
+    assert_eq!(kinds(SyntaxKind::HeaderMarker), vec!["##", "##"]);
+    assert_eq!(kinds(SyntaxKind::HeaderText), vec!["Tasks"]);
+    assert_eq!(kinds(SyntaxKind::ListMarker), vec!["-", "*"]);
+    assert_eq!(kinds(SyntaxKind::TaskMarker), vec!["x"]);
+    assert_eq!(kinds(SyntaxKind::WikiTarget), vec!["Note"]);
+    assert_eq!(kinds(SyntaxKind::LinkHeader), vec!["Part", "head"]);
+    assert_eq!(kinds(SyntaxKind::LinkText), vec!["alias", "img", "text"]);
+    assert_eq!(kinds(SyntaxKind::LinkDestination), vec!["a.png", "dest.md"]);
+    assert_eq!(kinds(SyntaxKind::TagName), vec!["tag"]);
+    assert_eq!(kinds(SyntaxKind::FootnoteId), vec!["1", "1"]);
+    assert_eq!(kinds(SyntaxKind::FieldKey), vec!["status", "due"]);
+    assert!(kinds(SyntaxKind::Newline).contains(&"\r\n"));
+    assert!(kinds(SyntaxKind::Error).is_empty());
+
+    let link = tree
+        .descendants()
+        .find(|node| node.kind == SyntaxKind::Link)
+        .unwrap();
+    let punct: Vec<_> = link
+        .tokens()
+        .filter(|token| !token.kind.is_trivia())
+        .map(|token| (token.kind, token.text.as_ref()))
+        .collect();
+    assert_eq!(
+        punct,
+        vec![
+            (SyntaxKind::Punct, "["),
+            (SyntaxKind::LinkText, "text"),
+            (SyntaxKind::Punct, "]("),
+            (SyntaxKind::LinkDestination, "dest.md"),
+            (SyntaxKind::Punct, "#"),
+            (SyntaxKind::LinkHeader, "head"),
+            (SyntaxKind::Punct, ")"),
+        ]
+    );
+}
+
+#[test]
+fn test_cst_is_addressed_by_ast_spans() {
+    let parsed = markdown_parser().parse(SAMPLE).into_output().unwrap();
+    let tree = parsed.syntax_tree(SAMPLE);
+
+    for block in &parsed.body {
+        let node = tree.find_node(block.1).unwrap();
+        let expected = match block.0 {
+            MarkdownNode::Header { .. } => SyntaxKind::Header,
+            MarkdownNode::ListItem { .. } => SyntaxKind::ListItem,
+            MarkdownNode::Paragraph(_) => SyntaxKind::Paragraph,
+            MarkdownNode::FootnoteDefinition { .. } => SyntaxKind::FootnoteDefinition,
+            MarkdownNode::Invalid => SyntaxKind::Error,
+        };
+        assert_eq!(node.kind, expected);
+    }
+
+    let offset = SAMPLE.find("Note#").unwrap();
+    assert_eq!(tree.token_at(offset).unwrap().kind, SyntaxKind::WikiTarget);
+}
+
+#[test]
+fn test_cst_edits_print_back() {
+    let source = "# Title\n\nSee [[Old Note|it]] and [[Other]].\n";
+    let parsed = markdown_parser().parse(source).into_output().unwrap();
+    let mut tree = parsed.syntax_tree(source);
+
+    for node in tree.child_nodes_mut() {
+        if node.kind == SyntaxKind::Header {
+            node.child_token_mut(SyntaxKind::HeaderMarker)
+                .unwrap()
+                .set_text("##");
+        }
+        for link in node.child_nodes_mut() {
+            if let Some(target) = link.child_token_mut(SyntaxKind::WikiTarget)
+                && target.text == "Old Note"
+            {
+                target.set_text("New Note");
+            }
+        }
+    }
+
+    assert_eq!(
+        tree.to_string(),
+        "## Title\n\nSee [[New Note|it]] and [[Other]].\n"
+    );
+}
@@ -0,0 +1,91 @@
+use lib_parser::{
+    ParseOptions, Parser, blocks_parser,
+    incremental::{BlockSpan, Reparse},
+    markdown_parser,
+};
+
+const SOURCE: &str = "# Title\n\nFirst paragraph\nstill first\n\n- one\n- two\n\nLast paragraph\n";
+
+fn blocks(source: &str) -> Vec<BlockSpan> {
+    let parsed = markdown_parser().parse(source).into_output().unwrap();
+    parsed
+        .body
+        .iter()
+        .map(|block| {
+            let range = block.1.into_range();
+            BlockSpan::new(&block.0, range.clone(), &source[range.end..])
+        })
+        .collect()
+}
+
+/// Applies the edit, reparses the window and checks the blocks against a full parse
+fn reparse(old: &str, edit: std::ops::Range<usize>, text: &str) -> Reparse {
+    let mut spans = blocks(old);
+    let mut reparse = Reparse::new(&spans, edit.clone(), text.len(), old.len()).unwrap();
+    let new = format!("{}{text}{}", &old[..edit.start], &old[edit.end..]);
+
+    let new_blocks = loop {
+        let window = &new[reparse.new.clone()];
+        let body = blocks_parser(ParseOptions::default())
+            .parse(window)
+            .into_output()
+            .unwrap();
+        let new_blocks: Vec<_> = body
+            .iter()
+            .map(|block| {
+                let range = block.1.start + reparse.new.start..block.1.end + reparse.new.start;
+                BlockSpan::new(&block.0, range.clone(), &new[range.end..])
+            })
+            .collect();
+
+        if new_blocks.last().is_some_and(|block| !block.resync) && reparse.extend(&spans, old.len())
+        {
+            continue;
+        }
+        break new_blocks;
+    };
+
+    reparse.splice(&mut spans, new_blocks);
+    assert_eq!(spans, blocks(&new), "{new:?}");
+    reparse
+}
+
+#[test]
+fn test_block_resync_points() {
+    let resync: Vec<_> = blocks(SOURCE).iter().map(|block| block.resync).collect();
+    assert_eq!(resync, vec![true, true, true, true, true]);
+
+    // A paragraph followed by a single newline can still grow
+    let spans = blocks("Para\n- item\n\nEnd");
+    assert_eq!(spans.len(), 2);
+    assert!(!blocks("Text [oops\n\nEnd")[0].resync);
+}
+
+#[test]
+fn test_reparse_window() {
+    let offset = SOURCE.find("still").unwrap();
+    let window = reparse(SOURCE, offset..offset, "more ");
+    assert_eq!(window.blocks, 1..2);
+    assert_eq!(&SOURCE[window.old.clone()], "First paragraph\nstill first");
+
+    // Removing the blank line before the list joins it to the paragraph
+    let offset = SOURCE.find("\n\n- one").unwrap();
+    let window = reparse(SOURCE, offset..offset + 1, "");
+    assert_eq!(window.blocks, 1..4);
+
+    // A list item turned into text continues on the next line
+    let offset = SOURCE.find("- one").unwrap();
+    let window = reparse(SOURCE, offset..offset + 2, "");
+    assert_eq!(window.blocks, 2..4);
+
+    let offset = SOURCE.find("Last").unwrap();
+    reparse(SOURCE, offset..SOURCE.len(), "# New\n\nEnd");
+
+    // Only edits before the first block, like frontmatter edits, need a full parse
+    assert!(Reparse::new(&blocks(SOURCE), 0..0, 1, SOURCE.len()).is_some());
+    let with_frontmatter = "---\na: b\n---\n# Title\n";
+    assert_eq!(
+        Reparse::new(&blocks(with_frontmatter), 4..5, 1, with_frontmatter.len()),
+        None
+    );
+}
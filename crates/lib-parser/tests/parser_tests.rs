@@ -1,13 +1,29 @@
-use chumsky::Parser;
+use chumsky::{Parser, span::SimpleSpan};
 use lib_parser::{
-    InlineMarkdownNode, LinkType, MarkdownNode,
-    markdown::{header_parser, list_item_parser},
+    InlineMarkdownNode, LinkType, MarkdownNode, ParseOptions, ParsedMarkdown, Spanned, TaskState,
+    error::{MarkdownError, ParseErrorKind, QuickFix},
+    markdown::{
+        footnote_parser, header_parser, inline_field_parser, link_parser, list_item_parser,
+        tag_parser, wikilink_parser,
+    },
+    markdown_parser, markdown_parser_with, unescape,
 };
 
-use crate::common::{compare, parse_unwrap};
+use crate::common::{compare, print_parse_errors};
 
 mod common;
 
+fn parse_unwrap(input: &str) -> ParsedMarkdown<'_> {
+    let (output, errors) = markdown_parser().parse(input).into_output_errors();
+    if !errors.is_empty() {
+        println!("\n=== PARSER FAILED ===");
+        print_parse_errors(input, errors);
+        println!("=====================\n");
+        panic!("Parser failed with errors");
+    }
+    output.expect("Parser returned no output")
+}
+
 #[test]
 fn test_headers() {
     compare(
@@ -34,12 +50,14 @@ fn test_lists() {
     let input = "- [x] Done";
     let parsed = parse_unwrap(input);
     if let MarkdownNode::ListItem { checkbox, .. } = &parsed.body[0].0 {
-        assert_eq!(*checkbox, Some(true));
+        assert_eq!(checkbox.as_ref().map(|c| c.0), Some(TaskState::Done));
     } else {
         panic!("Expected list item");
     }
 
-    let (output, errors) = list_item_parser().parse("- Item").into_output_errors();
+    let (output, errors) = list_item_parser(ParseOptions::default())
+        .parse("- Item")
+        .into_output_errors();
     assert!(errors.is_empty(), "Parser failed: {:?}", errors);
 
     if let Some(MarkdownNode::ListItem { checkbox, content }) = output {
@@ -94,3 +112,403 @@ fn test_inline_elements() {
         panic!("Expected paragraph");
     }
 }
+
+#[test]
+fn test_wikilink_embeds() {
+    let doc = parse_unwrap("See ![[Note#Part]]\n\n- ![[Other]]\n");
+
+    assert_eq!(
+        doc.body[0],
+        Spanned(
+            MarkdownNode::Paragraph(vec![
+                Spanned(
+                    InlineMarkdownNode::PlainText("See !"),
+                    SimpleSpan::from(0..5)
+                ),
+                Spanned(
+                    InlineMarkdownNode::Link(LinkType::WikiLink {
+                        target: "Note",
+                        display_text: None,
+                        header: Some("Part"),
+                    }),
+                    SimpleSpan::from(5..18),
+                ),
+            ]),
+            SimpleSpan::from(0..18),
+        )
+    );
+    assert!(matches!(doc.body[1].0, MarkdownNode::ListItem { .. }));
+}
+
+#[test]
+fn test_inline_fields() {
+    compare(
+        inline_field_parser(ParseOptions::default()),
+        "[due:: 2026-01-01]",
+        InlineMarkdownNode::InlineField {
+            key: "due",
+            value: "2026-01-01",
+            content: vec![Spanned(
+                InlineMarkdownNode::PlainText(" 2026-01-01"),
+                SimpleSpan::from(6..17),
+            )],
+        },
+    );
+
+    let input = "Some intro text\nstatus:: done\nrelated:: [[Project]]";
+    let doc = parse_unwrap(input);
+
+    let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+        panic!("Expected paragraph");
+    };
+    assert_eq!(
+        nodes[0].0,
+        InlineMarkdownNode::PlainText("Some intro text\n")
+    );
+
+    let InlineMarkdownNode::InlineField { key, value, .. } = &nodes[1].0 else {
+        panic!("Expected inline field, got {:?}", nodes[1].0);
+    };
+    assert_eq!((*key, *value), ("status", "done"));
+    assert_eq!(&input[nodes[1].1.into_range()], "status:: done");
+
+    let InlineMarkdownNode::InlineField { key, content, .. } = &nodes[3].0 else {
+        panic!("Expected inline field, got {:?}", nodes[3].0);
+    };
+    assert_eq!(*key, "related");
+    assert!(content.iter().any(|n| matches!(
+        n.0,
+        InlineMarkdownNode::Link(LinkType::WikiLink {
+            target: "Project",
+            ..
+        })
+    )));
+}
+
+#[test]
+fn test_inline_fields_ignore_paths() {
+    let doc = parse_unwrap("Use std::vec::Vec here");
+    let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+        panic!("Expected paragraph");
+    };
+    assert_eq!(
+        nodes[0].0,
+        InlineMarkdownNode::PlainText("Use std::vec::Vec here")
+    );
+
+    let doc = parse_unwrap("- [ ] Write report [due:: 2026-10-20] #work");
+    let MarkdownNode::ListItem { content, .. } = &doc.body[0].0 else {
+        panic!("Expected list item");
+    };
+    assert!(content.iter().any(|n| matches!(
+        n.0,
+        InlineMarkdownNode::InlineField {
+            key: "due",
+            value: "2026-10-20",
+            ..
+        }
+    )));
+}
+
+#[test]
+fn test_task_states() {
+    let cases = [
+        ("- [ ] Todo", TaskState::Todo),
+        ("- [X] Done", TaskState::Done),
+        ("- [/] Started", TaskState::InProgress),
+        ("- [-] Dropped", TaskState::Cancelled),
+        ("- [>] Later", TaskState::Deferred),
+        ("- [?] Unsure", TaskState::Question),
+        ("- [!] Important", TaskState::Other('!')),
+    ];
+
+    for (input, expected) in cases {
+        let doc = parse_unwrap(input);
+        let MarkdownNode::ListItem { checkbox, .. } = &doc.body[0].0 else {
+            panic!("Expected list item for {input:?}");
+        };
+        let Spanned(state, span) = checkbox.as_ref().unwrap();
+        assert_eq!(*state, expected, "{input:?}");
+        assert_eq!(TaskState::from_name(&state.name()), Some(expected));
+        assert_eq!(
+            &input[span.into_range()],
+            format!("[{}]", input.as_bytes()[3] as char)
+        );
+    }
+
+    // A link at the start of a list item is not a checkbox
+    let doc = parse_unwrap("- [a](note.md)");
+    let MarkdownNode::ListItem { checkbox, content } = &doc.body[0].0 else {
+        panic!("Expected list item");
+    };
+    assert_eq!(*checkbox, None);
+    assert!(matches!(
+        content[0].0,
+        InlineMarkdownNode::Link(LinkType::InlineLink { uri: "note.md", .. })
+    ));
+}
+
+#[test]
+fn test_escapes_are_plain_text() {
+    let inputs = [
+        r"\#notatag and \[not a link\]",
+        "&#35;notatag and &#x5B;not a link&#93;",
+        r"\[^1] is not a footnote",
+        "&num;hash &amp; friends",
+    ];
+
+    for input in inputs {
+        let doc = parse_unwrap(input);
+        let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+            panic!("Expected paragraph for {input:?}");
+        };
+        let nodes: Vec<_> = nodes.iter().map(|node| &node.0).collect();
+        assert_eq!(nodes, vec![&InlineMarkdownNode::PlainText(input)]);
+    }
+
+    // Escapes do not hide the constructs that follow them
+    let doc = parse_unwrap(r"\# #real");
+    let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+        panic!("Expected paragraph");
+    };
+    assert_eq!(nodes[1].0, InlineMarkdownNode::Tag("real"));
+
+    // A lone backslash or ampersand is just text
+    let doc = parse_unwrap(r"C:\path & more");
+    let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+        panic!("Expected paragraph");
+    };
+    assert_eq!(nodes[0].0, InlineMarkdownNode::PlainText(r"C:\path & more"));
+}
+
+#[test]
+fn test_escapes_in_links() {
+    compare(
+        link_parser(),
+        r"[a \] b](my\)file.md#C&#35; notes)",
+        InlineMarkdownNode::Link(LinkType::InlineLink {
+            text: r"a \] b",
+            uri: r"my\)file.md",
+            header: Some("C&#35; notes"),
+        }),
+    );
+
+    compare(
+        wikilink_parser(),
+        r"[[C&#35; notes#Pipes \| bars|Alias \]]]",
+        InlineMarkdownNode::Link(LinkType::WikiLink {
+            target: "C&#35; notes",
+            header: Some(r"Pipes \| bars"),
+            display_text: Some(r"Alias \]"),
+        }),
+    );
+
+    let doc = parse_unwrap(r"- [ ] \#1 priority [[Note]]");
+    let MarkdownNode::ListItem { content, .. } = &doc.body[0].0 else {
+        panic!("Expected list item");
+    };
+    assert_eq!(
+        content[0].0,
+        InlineMarkdownNode::PlainText(r"\#1 priority ")
+    );
+}
+
+#[test]
+fn test_escapes_in_tags_and_footnotes() {
+    let options = ParseOptions::default();
+    compare(
+        tag_parser(options),
+        r"#snake\_case/a&lowbar;b",
+        InlineMarkdownNode::Tag(r"snake\_case/a&lowbar;b"),
+    );
+    // Escapes of characters a tag cannot hold end it, as do unknown entities
+    compare(
+        tag_parser(options).lazy(),
+        r"#end\.",
+        InlineMarkdownNode::Tag("end"),
+    );
+    compare(
+        tag_parser(options).lazy(),
+        "#caf&eacute;",
+        InlineMarkdownNode::Tag("caf"),
+    );
+    // Numeric once unescaped
+    assert!(tag_parser(options).parse("#&#49;2").into_result().is_err());
+
+    compare(
+        footnote_parser(),
+        r"[^note\_1]",
+        InlineMarkdownNode::Footnote(r"note\_1"),
+    );
+}
+
+#[test]
+fn test_unescape() {
+    assert_eq!(unescape("plain"), "plain");
+    assert_eq!(unescape(r"\#tag \[x\] \\ \a"), r"#tag [x] \ \a");
+    assert_eq!(unescape("C&#35; &#x23; &amp; &copy;"), "C# # & ©");
+    assert_eq!(unescape("&unknown; & &#; &#xZZ;"), "&unknown; & &#; &#xZZ;");
+    assert_eq!(unescape("&#0; &#x110000;"), "\u{fffd} \u{fffd}");
+}
+
+#[test]
+fn test_nested_tags() {
+    compare(
+        tag_parser(ParseOptions::default()),
+        "#project/alpha/design",
+        InlineMarkdownNode::Tag("project/alpha/design"),
+    );
+
+    let doc = parse_unwrap("See #project/alpha/ and #a//b #area/");
+    let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+        panic!("Expected paragraph");
+    };
+    let tags: Vec<_> = nodes
+        .iter()
+        .filter_map(|node| match node.0 {
+            InlineMarkdownNode::Tag(tag) => Some(tag),
+            _ => None,
+        })
+        .collect();
+    // Trailing and empty segments are not part of the tag
+    assert_eq!(tags, vec!["project/alpha", "a", "area"]);
+}
+
+fn tags(doc: &lib_parser::ParsedMarkdown<'_>) -> Vec<String> {
+    doc.body
+        .iter()
+        .flat_map(|block| match &block.0 {
+            MarkdownNode::Paragraph(nodes) | MarkdownNode::ListItem { content: nodes, .. } => {
+                nodes.as_slice()
+            }
+            _ => &[],
+        })
+        .filter_map(|node| match node.0 {
+            InlineMarkdownNode::Tag(tag) => Some(tag.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_tags_start_after_whitespace() {
+    let doc = parse_unwrap("Learning C# and F#sharp, see issue#12 and a##b\n#start (#paren)");
+    assert_eq!(tags(&doc), vec!["start"]);
+
+    let MarkdownNode::Paragraph(nodes) = &doc.body[0].0 else {
+        panic!("Expected paragraph");
+    };
+    assert_eq!(
+        nodes[0].0,
+        InlineMarkdownNode::PlainText("Learning C# and F#sharp, see issue#12 and a##b\n")
+    );
+
+    // A lone `#` or `##` is just text
+    let doc = parse_unwrap("Use # or ## here");
+    assert!(tags(&doc).is_empty());
+    assert_eq!(doc.body.len(), 1);
+
+    let doc = parse_unwrap("- [ ] Fix C# build #dev");
+    assert_eq!(tags(&doc), vec!["dev"]);
+}
+
+#[test]
+fn test_numeric_tags() {
+    let doc = parse_unwrap("Fixes #123 and #2024/q1 and #1984y #1/2");
+    assert_eq!(tags(&doc), vec!["2024/q1", "1984y"]);
+
+    let options = ParseOptions { numeric_tags: true };
+    let doc = markdown_parser_with(options)
+        .parse("Fixes #123 and #1/2")
+        .into_result()
+        .unwrap();
+    assert_eq!(tags(&doc), vec!["123", "1/2"]);
+}
+
+#[test]
+fn test_unicode_tags() {
+    let doc = parse_unwrap("#日本語/タグ、次 #中文标签。 #café #हिंदी #🚀launch #👩‍💻 #über-tag_1");
+    assert_eq!(
+        tags(&doc),
+        vec![
+            "日本語/タグ",
+            "中文标签",
+            "café",
+            "हिंदी",
+            "🚀launch",
+            "👩‍💻",
+            "über-tag_1"
+        ]
+    );
+
+    // Punctuation ends a tag
+    let doc = parse_unwrap("#one) #two, #three… #four");
+    assert_eq!(tags(&doc), vec!["one", "two", "three", "four"]);
+}
+
+#[test]
+fn test_recovers_at_end_of_input() {
+    // Without a trailing newline the broken line still becomes a block
+    for input in ["[oops", "Text\n\n[^:", "- [[Note"] {
+        let (output, errors) = markdown_parser().parse(input).into_output_errors();
+        let output = output.unwrap_or_else(|| panic!("no output for {input:?}"));
+        assert!(!errors.is_empty());
+        assert_eq!(output.body.last().unwrap().0, MarkdownNode::Invalid);
+        assert_eq!(output.body.last().unwrap().1.end, input.len());
+    }
+}
+
+#[test]
+fn test_parse_error_kinds() {
+    let cases = [
+        (
+            "See [[Note and more",
+            ParseErrorKind::UnclosedWikiLink,
+            "[[",
+        ),
+        ("See [[Note|alias\n", ParseErrorKind::UnclosedWikiLink, "[["),
+        ("See [[]] here", ParseErrorKind::EmptyWikiLinkTarget, "[["),
+        ("See [text here", ParseErrorKind::UnclosedLinkText, "["),
+        ("See [] here", ParseErrorKind::EmptyLinkText, "["),
+        (
+            "See [text] here",
+            ParseErrorKind::MissingLinkDestination,
+            "[",
+        ),
+        (
+            "See [text](note.md here",
+            ParseErrorKind::UnclosedLinkDestination,
+            "(",
+        ),
+        ("See [text]() here", ParseErrorKind::EmptyLinkTarget, "("),
+        (
+            "---\n: bad\n---\n# H",
+            ParseErrorKind::MalformedFrontmatter,
+            "---",
+        ),
+    ];
+
+    for (input, kind, opening) in cases {
+        let errors = markdown_parser().parse(input).into_errors();
+        let kinds: Vec<_> = errors.iter().map(MarkdownError::kind).collect();
+        assert_eq!(kinds, vec![kind], "{input:?}: {errors:?}");
+
+        let span = kind.opening_span(input, *errors[0].span()).expect(input);
+        assert_eq!(&input[span.into_range()], opening, "{input:?}");
+    }
+
+    assert_eq!(
+        ParseErrorKind::from_code("unclosed-wikilink"),
+        Some(ParseErrorKind::UnclosedWikiLink)
+    );
+    assert_eq!(
+        ParseErrorKind::UnclosedWikiLink.quick_fix(),
+        Some(QuickFix::Insert("]]"))
+    );
+    assert!(
+        markdown_parser()
+            .parse("---\ntitle: ok\n---\n")
+            .into_errors()
+            .is_empty()
+    );
+}
@@ -0,0 +1,346 @@
+//! Conformance of `markdown_parser` with the CommonMark spec and GFM extensions.
+//!
+//! Examples are loaded from `tests/spec/*.json`, in the format of the
+//! `spec.json` published with the specs: the whole CommonMark spec and the GFM
+//! task list extension. Instead of rendering HTML, the block structure of the
+//! expected HTML (headers, paragraphs, list items, links and images) is
+//! compared with the blocks the parser produces.
+//!
+//! Examples the parser deliberately or knowingly handles differently are listed
+//! in `tests/spec/known_divergences.txt`. The test fails when an example outside
+//! that list fails, and when a listed example starts passing, so the list stays
+//! accurate. Run with `--nocapture` for the per-section report.
+
+use std::{collections::BTreeSet, fmt};
+
+use lib_parser::{
+    InlineMarkdownNode, LinkType, MarkdownNode, MarkdownText, Parser, markdown_parser, unescape,
+};
+use serde_json::Value;
+
+const SPECS: [(&str, &str); 2] = [
+    ("commonmark", include_str!("spec/commonmark.json")),
+    ("gfm", include_str!("spec/gfm.json")),
+];
+
+const KNOWN_DIVERGENCES: &str = include_str!("spec/known_divergences.txt");
+
+struct Example {
+    /// `<spec>:<example number>`, as used in the divergence list
+    id: String,
+    section: String,
+    markdown: String,
+    html: String,
+}
+
+fn load_examples() -> Vec<Example> {
+    SPECS
+        .iter()
+        .flat_map(|(spec, json)| {
+            let examples: Vec<Value> = serde_json::from_str(json).expect("spec file is valid JSON");
+            examples.into_iter().map(move |example| {
+                let field = |key: &str| example[key].as_str().unwrap_or_default().to_string();
+                Example {
+                    id: format!("{spec}:{}", example["example"]),
+                    section: field("section"),
+                    markdown: field("markdown"),
+                    html: field("html"),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Example ids from the divergence list, `#` starts a comment
+fn known_divergences() -> Vec<&'static str> {
+    KNOWN_DIVERGENCES
+        .lines()
+        .filter_map(|line| line.split('#').next()?.split_whitespace().next())
+        .collect()
+}
+
+/// A block as both sides see it, with whitespace collapsed
+#[derive(Debug, Default, PartialEq)]
+struct Block {
+    kind: String,
+    text: String,
+    /// `link <href>` or `image <src> <alt>`
+    links: Vec<String>,
+}
+
+impl Block {
+    fn new(kind: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            ..Default::default()
+        }
+    }
+
+    fn normalize(mut self) -> Self {
+        self.text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        self
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}", self.kind, self.text)?;
+        for link in &self.links {
+            write!(f, " ({link})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Blocks of the parsed example
+fn parsed_blocks(markdown: &str) -> Option<Vec<Block>> {
+    let parsed = markdown_parser().parse(markdown).into_output()?;
+
+    let blocks = parsed
+        .body
+        .iter()
+        .map(|node| match &node.0 {
+            MarkdownNode::Header { level, content } => Block {
+                text: unescape(content).into_owned(),
+                ..Block::new(format!("h{level}"))
+            },
+            MarkdownNode::Paragraph(content) => inline_block("p", markdown, content),
+            MarkdownNode::ListItem { checkbox, content } => {
+                let kind = match checkbox {
+                    Some(checkbox) if checkbox.0.is_closed() => "li [x]",
+                    Some(_) => "li [ ]",
+                    None => "li",
+                };
+                inline_block(kind, markdown, content)
+            }
+            MarkdownNode::FootnoteDefinition { content, .. } => {
+                inline_block("footnote", markdown, content)
+            }
+            MarkdownNode::Invalid => Block {
+                text: markdown[node.1.into_range()].to_string(),
+                ..Block::new("invalid")
+            },
+        })
+        .map(Block::normalize)
+        .collect();
+
+    Some(blocks)
+}
+
+fn inline_block(kind: &str, source: &str, content: &MarkdownText) -> Block {
+    let mut block = Block::new(kind);
+    for node in content {
+        match &node.0 {
+            InlineMarkdownNode::PlainText(text) => block.text.push_str(&unescape(text)),
+            InlineMarkdownNode::Link(LinkType::InlineLink { text, uri, header }) => {
+                block.text.push_str(&unescape(text));
+                let anchor = header
+                    .map(|header| format!("#{header}"))
+                    .unwrap_or_default();
+                block.links.push(format!("link {uri}{anchor}"));
+            }
+            InlineMarkdownNode::Link(LinkType::ImageLink { text, uri }) => {
+                block.links.push(format!("image {uri} {text}"));
+            }
+            // Extensions CommonMark reads as plain text
+            InlineMarkdownNode::Link(LinkType::WikiLink { .. })
+            | InlineMarkdownNode::Tag(_)
+            | InlineMarkdownNode::Footnote(_)
+            | InlineMarkdownNode::InlineField { .. } => {
+                block.text.push_str(&source[node.1.into_range()]);
+            }
+        }
+    }
+    block
+}
+
+/// Blocks of the expected HTML, raw HTML the spec passes through is read as tags too
+fn html_blocks(html: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    // Indices into `blocks` of the open blocks that collect text
+    let mut open: Vec<usize> = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        // Raw HTML passed through by the spec may leave a `<` unclosed
+        let Some((start, end)) = rest
+            .find('<')
+            .and_then(|start| Some((start, start + rest[start..].find('>')?)))
+        else {
+            push_text(&mut blocks, &open, rest);
+            break;
+        };
+        push_text(&mut blocks, &open, &rest[..start]);
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        let name = tag
+            .trim_start_matches('/')
+            .split([' ', '/'])
+            .next()
+            .unwrap_or_default();
+        let closing = tag.starts_with('/');
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li" | "pre" | "blockquote" if !closing => {
+                open.push(blocks.len());
+                blocks.push(Block::new(if name == "pre" { "code" } else { name }));
+            }
+            // Paragraphs of loose list items belong to the item
+            "p" if !closing => {
+                let in_item = open
+                    .last()
+                    .is_some_and(|&i| blocks[i].kind.starts_with("li"));
+                if !in_item {
+                    open.push(blocks.len());
+                    blocks.push(Block::new("p"));
+                }
+            }
+            "p" if open.last().is_some_and(|&i| blocks[i].kind == "p") => {
+                open.pop();
+            }
+            "p" => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li" | "pre" | "blockquote" => {
+                open.pop();
+            }
+            "hr" => blocks.push(Block::new("hr")),
+            "br" => push_text(&mut blocks, &open, "\n"),
+            "input" => {
+                if let Some(&i) = open.last() {
+                    blocks[i].kind = if tag.contains("checked") {
+                        "li [x]"
+                    } else {
+                        "li [ ]"
+                    }
+                    .into();
+                }
+            }
+            "a" if !closing => {
+                if let Some(&i) = open.last() {
+                    blocks[i]
+                        .links
+                        .push(format!("link {}", attribute(tag, "href")));
+                }
+            }
+            "img" => {
+                if let Some(&i) = open.last() {
+                    let image =
+                        format!("image {} {}", attribute(tag, "src"), attribute(tag, "alt"));
+                    blocks[i].links.push(image);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    blocks.into_iter().map(Block::normalize).collect()
+}
+
+fn push_text(blocks: &mut [Block], open: &[usize], text: &str) {
+    if let Some(&i) = open.last() {
+        blocks[i].text.push_str(&decode_entities(text));
+    }
+}
+
+fn attribute(tag: &str, name: &str) -> String {
+    let Some(start) = tag.find(&format!("{name}=\"")) else {
+        return String::new();
+    };
+    let value = &tag[start + name.len() + 2..];
+    let value = &value[..value.find('"').unwrap_or(value.len())];
+    percent_decode(&decode_entities(value))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+fn percent_decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail.get(..2).and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn lines(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(|block| format!("\n    {block}"))
+        .collect()
+}
+
+#[test]
+fn test_spec_examples() {
+    let divergences = known_divergences();
+    // Passed and total examples per section, in order of appearance
+    let mut sections: Vec<(String, usize, usize)> = Vec::new();
+    let mut unexpected = Vec::new();
+    let mut ids = BTreeSet::new();
+
+    for example in load_examples() {
+        let expected = html_blocks(&example.html);
+        let actual = parsed_blocks(&example.markdown);
+        let passed = actual.as_ref() == Some(&expected);
+
+        if sections
+            .last()
+            .is_none_or(|(name, ..)| *name != example.section)
+        {
+            sections.push((example.section.clone(), 0, 0));
+        }
+        let section = sections.last_mut().unwrap();
+        section.1 += usize::from(passed);
+        section.2 += 1;
+
+        let known = divergences.contains(&example.id.as_str());
+        ids.insert(example.id.clone());
+        if passed == known {
+            let actual = actual.map_or_else(|| "\n    <no output>".to_string(), |b| lines(&b));
+            unexpected.push(format!(
+                "{} ({}) {}\n  markdown: {:?}\n  expected:{}\n  actual:{actual}",
+                example.id,
+                example.section,
+                if passed {
+                    "passes but is listed as a known divergence"
+                } else {
+                    "fails"
+                },
+                example.markdown,
+                lines(&expected),
+            ));
+        }
+    }
+
+    for (name, passed, total) in &sections {
+        println!("{passed:>3}/{total:<3} {name}");
+    }
+    let (passed, total) = sections
+        .iter()
+        .fold((0, 0), |(p, t), (_, passed, total)| (p + passed, t + total));
+    println!("{passed:>3}/{total:<3} total");
+
+    let stale: Vec<_> = divergences
+        .iter()
+        .filter(|id| !ids.contains(**id))
+        .collect();
+    assert!(
+        stale.is_empty(),
+        "Known divergences without an example: {stale:?}"
+    );
+    assert!(unexpected.is_empty(), "\n{}", unexpected.join("\n\n"));
+}
@@ -1,5 +1,5 @@
 use common::compare;
-use lib_parser::{InlineMarkdownNode, LinkType, MarkdownNode, markdown::*};
+use lib_parser::{InlineMarkdownNode, LinkType, MarkdownNode, ParseOptions, markdown::*};
 
 mod common;
 
@@ -47,5 +47,5 @@ fn test_footnote_parser_basic() {
 fn test_tag_parser() {
     let input = "#tag123";
     let expected = InlineMarkdownNode::Tag("tag123");
-    compare(tag_parser(), input, expected);
+    compare(tag_parser(ParseOptions::default()), input, expected);
 }
@@ -0,0 +1,109 @@
+use lib_parser::{
+    LinkType, MarkdownText, Parser, SimpleSpan, Spanned, TaskState, markdown_parser,
+    visit::{Visitor, VisitorMut, walk_text},
+};
+
+const SOURCE: &str = "---\ntitle: Visit\n---\n# Title\n\n\
+See [[Note]] and [text](other.md) #tag\n\n\
+- [ ] Task [due:: [[Due]]]\n\n\
+[^1]: Footnote [[Foot]]\n";
+
+/// Records every callback with the text at its span
+#[derive(Default)]
+struct Trace(Vec<(&'static str, String)>);
+
+impl Trace {
+    fn push(&mut self, name: &'static str, span: SimpleSpan) {
+        self.0.push((name, SOURCE[span.into_range()].to_string()));
+    }
+}
+
+impl<'a> Visitor<'a> for Trace {
+    fn visit_header(&mut self, _level: usize, _content: &'a str, span: SimpleSpan) {
+        self.push("header", span);
+    }
+
+    fn visit_list_item(
+        &mut self,
+        checkbox: Option<&Spanned<TaskState>>,
+        content: &MarkdownText<'a>,
+        span: SimpleSpan,
+    ) {
+        self.push("list item", span);
+        if let Some(checkbox) = checkbox {
+            self.push("checkbox", checkbox.1);
+        }
+        walk_text(self, content);
+    }
+
+    fn visit_link(&mut self, _link: &LinkType<'a>, span: SimpleSpan) {
+        self.push("link", span);
+    }
+
+    fn visit_tag(&mut self, _name: &'a str, span: SimpleSpan) {
+        self.push("tag", span);
+    }
+}
+
+#[test]
+fn test_visitor_reaches_nested_nodes() {
+    let parsed = markdown_parser().parse(SOURCE).into_output().unwrap();
+    let mut trace = Trace::default();
+    trace.visit_markdown(&parsed);
+
+    let expected = [
+        ("header", "# Title"),
+        ("link", "[[Note]]"),
+        ("link", "[text](other.md)"),
+        ("tag", "#tag"),
+        ("list item", "- [ ] Task [due:: [[Due]]]"),
+        ("checkbox", "[ ]"),
+        ("link", "[[Due]]"),
+        ("link", "[[Foot]]"),
+    ];
+    let trace: Vec<_> = trace
+        .0
+        .iter()
+        .map(|(name, text)| (*name, text.trim_end()))
+        .collect();
+    assert_eq!(trace, expected);
+}
+
+#[test]
+fn test_visitor_mut_rewrites_nodes() {
+    struct Rename;
+
+    impl<'a> VisitorMut<'a> for Rename {
+        fn visit_link_mut(&mut self, link: &mut LinkType<'a>, _span: SimpleSpan) {
+            if let LinkType::WikiLink { target, .. } = link {
+                *target = "Renamed";
+            }
+        }
+
+        fn visit_tag_mut(&mut self, name: &mut &'a str, _span: SimpleSpan) {
+            *name = "renamed";
+        }
+    }
+
+    #[derive(Default)]
+    struct Targets<'a>(Vec<&'a str>);
+
+    impl<'a> Visitor<'a> for Targets<'a> {
+        fn visit_link(&mut self, link: &LinkType<'a>, _span: SimpleSpan) {
+            if let LinkType::WikiLink { target, .. } = link {
+                self.0.push(target);
+            }
+        }
+
+        fn visit_tag(&mut self, name: &'a str, _span: SimpleSpan) {
+            self.0.push(name);
+        }
+    }
+
+    let mut parsed = markdown_parser().parse(SOURCE).into_output().unwrap();
+    Rename.visit_markdown_mut(&mut parsed);
+
+    let mut targets = Targets::default();
+    targets.visit_markdown(&parsed);
+    assert_eq!(targets.0, vec!["Renamed", "renamed", "Renamed", "Renamed"]);
+}
@@ -0,0 +1,147 @@
+//! Invariants the fuzz targets check on arbitrary input.
+//!
+//! Besides not panicking, every span the parsers report must lie on char
+//! boundaries within the input, and every range a [`Document`] stores must
+//! convert back to a byte offset, since the server slices and converts them
+//! without further checks.
+
+use std::path::PathBuf;
+
+use lib_core::{document::Document, text_buffer_conversions::TextBufferConversions};
+use lib_parser::{
+    InlineMarkdownNode, MarkdownNode, Parser, SimpleSpan, Spanned,
+    error::MarkdownError,
+    markdown_parser,
+    slice_span,
+    visit::{Visitor, walk_block, walk_inline},
+    yaml::{Frontmatter, Yaml, yaml_parser},
+};
+
+pub fn check_markdown_parser(input: &str) {
+    let (parsed, errors) = markdown_parser().parse(input).into_output_errors();
+    check_errors(input, &errors);
+
+    if let Some(parsed) = parsed {
+        SpanChecker { input }.visit_markdown(&parsed);
+    }
+}
+
+pub fn check_yaml_parser(input: &str) {
+    let (frontmatter, errors) = yaml_parser().parse(input).into_output_errors();
+    check_errors(input, &errors);
+
+    if let Some(frontmatter) = frontmatter {
+        SpanChecker { input }.visit_frontmatter(&frontmatter);
+    }
+}
+
+pub fn check_document(input: &str) {
+    let Ok(document) = Document::new(PathBuf::from("/fuzz.md"), input, 0) else {
+        return;
+    };
+    let content = document.content.slice(..);
+
+    let ranges = document
+        .references
+        .iter()
+        .map(|reference| reference.range)
+        .chain(
+            document
+                .tasks
+                .iter()
+                .flat_map(|task| [task.range, task.checkbox_range]),
+        )
+        .chain(
+            document
+                .parse_errors
+                .iter()
+                .flat_map(|issue| std::iter::once(issue.range).chain(issue.opening)),
+        );
+    for range in ranges {
+        let start = content.try_position_to_byte_offset(range.start);
+        let end = content.try_position_to_byte_offset(range.end);
+        assert!(
+            start.zip(end).is_some_and(|(start, end)| start <= end),
+            "range {range:?} is outside of {input:?}"
+        );
+    }
+}
+
+fn check_errors(input: &str, errors: &[MarkdownError]) {
+    for error in errors {
+        check_span(input, *error.span());
+    }
+}
+
+fn check_span(input: &str, span: SimpleSpan) {
+    assert!(
+        span.start <= span.end
+            && span.end <= input.len()
+            && input.is_char_boundary(span.start)
+            && input.is_char_boundary(span.end),
+        "span {span:?} is not a valid slice of {input:?}"
+    );
+}
+
+/// Strings in the tree must be borrowed from the input
+fn check_slice(input: &str, slice: &str) {
+    assert!(
+        slice_span(input, slice).is_some(),
+        "{slice:?} is not borrowed from {input:?}"
+    );
+}
+
+struct SpanChecker<'s> {
+    input: &'s str,
+}
+
+impl<'a> Visitor<'a> for SpanChecker<'_> {
+    fn visit_frontmatter(&mut self, frontmatter: &Frontmatter<'a>) {
+        for (key, value) in &frontmatter.0 {
+            check_slice(self.input, key);
+            match value {
+                Yaml::String(value) => check_slice(self.input, value),
+                Yaml::List(items) => items.iter().for_each(|item| check_slice(self.input, item)),
+            }
+        }
+    }
+
+    fn visit_block(&mut self, block: &Spanned<MarkdownNode<'a>>) {
+        check_span(self.input, block.1);
+        if let MarkdownNode::Header { content, .. } = &block.0 {
+            check_slice(self.input, content);
+        }
+        walk_block(self, block);
+    }
+
+    fn visit_inline(&mut self, inline: &Spanned<InlineMarkdownNode<'a>>) {
+        check_span(self.input, inline.1);
+        walk_inline(self, inline);
+    }
+
+    fn visit_plain_text(&mut self, text: &'a str, _span: SimpleSpan) {
+        check_slice(self.input, text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs every check on the inputs in `seeds/`, which also start the fuzz corpus
+    #[test]
+    fn seeds_pass_every_check() {
+        let seeds = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("seeds");
+        let mut count = 0;
+
+        for entry in std::fs::read_dir(seeds).unwrap() {
+            let input = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            check_markdown_parser(&input);
+            check_yaml_parser(&input);
+            check_document(&input);
+            count += 1;
+        }
+
+        assert!(count > 0, "no seeds found");
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, Command, value_parser};
+
+/// What the binary was asked to do
+pub enum Action {
+    /// Run the language server over stdin/stdout
+    Lsp,
+    /// Print the parsed tree of a file
+    Parse { file: PathBuf, format: DumpFormat },
+    /// Write a vault as a static HTML site
+    Export { root: PathBuf, out: PathBuf },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Json,
+    Sexpr,
+}
+
+fn command() -> Command {
+    Command::new(env!("CARGO_PKG_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Markdown language server, runs over stdin/stdout without a subcommand")
+        .arg(
+            Arg::new("stdio")
+                .long("stdio")
+                .action(ArgAction::SetTrue)
+                .help("Communicate over stdin/stdout, the default"),
+        )
+        .subcommand(
+            Command::new("parse")
+                .about("Print the tree the server parses a file into, with line:column positions")
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["json", "sexpr"])
+                        .default_value("json"),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write every note of a vault as HTML, with backlinks and a tag index")
+                .arg(
+                    Arg::new("root")
+                        .long("root")
+                        .value_parser(value_parser!(PathBuf))
+                        .default_value(".")
+                        .help("Vault to export"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Directory the site is written to"),
+                ),
+        )
+}
+
+pub fn parse_args() -> Action {
+    let matches = command().get_matches();
+
+    match matches.subcommand() {
+        Some(("parse", parse)) => Action::Parse {
+            file: parse
+                .get_one::<PathBuf>("file")
+                .cloned()
+                .unwrap_or_default(),
+            format: match parse.get_one::<String>("format").map(String::as_str) {
+                Some("sexpr") => DumpFormat::Sexpr,
+                _ => DumpFormat::Json,
+            },
+        },
+        Some(("export", export)) => Action::Export {
+            root: export
+                .get_one::<PathBuf>("root")
+                .cloned()
+                .unwrap_or_default(),
+            out: export
+                .get_one::<PathBuf>("out")
+                .cloned()
+                .unwrap_or_default(),
+        },
+        _ => Action::Lsp,
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write as _},
+    path::Path,
+};
+
+use lib_core::config::Config;
+use lib_parser::{
+    LinkType, MarkdownText, Parser, SimpleSpan, Spanned, TaskState,
+    error::MarkdownError,
+    markdown_parser_with,
+    visit::{Visitor, walk_text},
+    yaml::{Frontmatter, Yaml},
+};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use serde_json::{Map, Value, json};
+
+use crate::args::DumpFormat;
+
+/// Prints the parsed tree of `path` to stdout, and parse errors to stderr
+///
+/// The file is parsed with the options of the nearest `rust-markdown-lsp.toml`
+/// above it, as the server would in that vault.
+pub fn dump(path: &Path, format: DumpFormat) -> Result<()> {
+    let source = fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not read {}", path.display()))?;
+    let lines = LineIndex::new(&source);
+    let config = vault_config(path);
+
+    let (parsed, errors) = markdown_parser_with(config.markdown.parse_options())
+        .parse(&source)
+        .into_output_errors();
+    for error in &errors {
+        let (line, column) = lines.position(error.span().start);
+        eprintln!(
+            "{}:{line}:{column}: {} [{}]",
+            path.display(),
+            error.reason(),
+            error.kind().code()
+        );
+    }
+    let Some(parsed) = parsed else {
+        miette::bail!("Could not parse {}", path.display());
+    };
+
+    let output = match format {
+        DumpFormat::Json => {
+            let mut tree = serde_json::to_value(&parsed).into_diagnostic()?;
+            lines.add_positions(&mut tree);
+            if let Value::Object(tree) = &mut tree {
+                tree.insert("errors".to_owned(), lines.errors_json(&errors));
+            }
+            serde_json::to_string_pretty(&tree).into_diagnostic()?
+        }
+        DumpFormat::Sexpr => {
+            let mut printer = SexprPrinter {
+                lines: &lines,
+                out: String::from("(document"),
+                depth: 1,
+            };
+            printer.visit_markdown(&parsed);
+            printer.out.push(')');
+            printer.out
+        }
+    };
+    writeln!(io::stdout().lock(), "{output}").into_diagnostic()
+}
+
+/// Byte offsets to 1-based line and column numbers, columns count characters
+struct LineIndex<'s> {
+    source: &'s str,
+    line_starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    fn new(source: &'s str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let column = self.source[self.line_starts[line]..offset].chars().count();
+
+        (line + 1, column + 1)
+    }
+
+    fn span(&self, span: SimpleSpan) -> String {
+        let (start_line, start_column) = self.position(span.start);
+        let (end_line, end_column) = self.position(span.end);
+
+        format!("{start_line}:{start_column}-{end_line}:{end_column}")
+    }
+
+    fn position_json(&self, offset: usize) -> Value {
+        let (line, column) = self.position(offset);
+        json!({ "offset": offset, "line": line, "column": column })
+    }
+
+    /// Replaces the serialized spans in `value` with start and end positions
+    fn add_positions(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                if let Some((start, end)) = span_offsets(object) {
+                    *value = json!({
+                        "start": self.position_json(start),
+                        "end": self.position_json(end),
+                    });
+                    return;
+                }
+                object
+                    .values_mut()
+                    .for_each(|value| self.add_positions(value));
+            }
+            Value::Array(values) => values
+                .iter_mut()
+                .for_each(|value| self.add_positions(value)),
+            _ => {}
+        }
+    }
+
+    fn errors_json(&self, errors: &[MarkdownError]) -> Value {
+        errors
+            .iter()
+            .map(|error| {
+                json!({
+                    "code": error.kind().code(),
+                    "message": error.reason().to_string(),
+                    "start": self.position_json(error.span().start),
+                    "end": self.position_json(error.span().end),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The config of the vault `path` is in, the defaults outside of one
+fn vault_config(path: &Path) -> Config {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    path.ancestors()
+        .skip(1)
+        .map(|dir| dir.join("rust-markdown-lsp.toml"))
+        .find(|config| config.is_file())
+        .map_or_else(Config::default, Config::from_file_or_default)
+}
+
+/// A serialized [`SimpleSpan`] is the only object with `start` and `end` offsets
+fn span_offsets(object: &Map<String, Value>) -> Option<(usize, usize)> {
+    let offset = |key| object.get(key)?.as_u64().map(|offset| offset as usize);
+    Some((offset("start")?, offset("end")?))
+}
+
+/// Prints the tree as indented s-expressions, e.g.
+/// `(wikilink 3:5-3:13 :target "Note")`
+struct SexprPrinter<'l, 's> {
+    lines: &'l LineIndex<'s>,
+    out: String,
+    depth: usize,
+}
+
+impl SexprPrinter<'_, '_> {
+    fn line(&mut self, text: impl std::fmt::Display) {
+        let _ = write!(self.out, "\n{:indent$}{text}", "", indent = self.depth * 2);
+    }
+
+    /// Opens a node, callers close it with [`Self::close`]
+    fn open(&mut self, name: &str, span: SimpleSpan, attributes: &str) {
+        let span = self.lines.span(span);
+        self.line(format_args!("({name} {span}{attributes}"));
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.out.push(')');
+        self.depth -= 1;
+    }
+
+    fn leaf(&mut self, name: &str, span: SimpleSpan, attributes: &str) {
+        self.open(name, span, attributes);
+        self.close();
+    }
+}
+
+fn attribute(key: &str, value: impl std::fmt::Debug) -> String {
+    format!(" :{key} {value:?}")
+}
+
+impl<'a> Visitor<'a> for SexprPrinter<'_, '_> {
+    fn visit_frontmatter(&mut self, frontmatter: &Frontmatter<'a>) {
+        self.line("(frontmatter");
+        self.depth += 1;
+        for (key, value) in &frontmatter.0 {
+            match value {
+                Yaml::String(value) => self.line(format_args!("({key:?} {value:?})")),
+                Yaml::List(items) => self.line(format_args!("({key:?} {items:?})")),
+            }
+        }
+        self.close();
+    }
+
+    fn visit_header(&mut self, level: usize, content: &'a str, span: SimpleSpan) {
+        let attributes = attribute("level", level) + &attribute("content", content);
+        self.leaf("header", span, &attributes);
+    }
+
+    fn visit_paragraph(&mut self, content: &MarkdownText<'a>, span: SimpleSpan) {
+        self.open("paragraph", span, "");
+        walk_text(self, content);
+        self.close();
+    }
+
+    fn visit_list_item(
+        &mut self,
+        checkbox: Option<&Spanned<TaskState>>,
+        content: &MarkdownText<'a>,
+        span: SimpleSpan,
+    ) {
+        let attributes = checkbox.map_or_else(String::new, |Spanned(state, _)| {
+            attribute("task", state.name())
+        });
+        self.open("list-item", span, &attributes);
+        walk_text(self, content);
+        self.close();
+    }
+
+    fn visit_footnote_definition(
+        &mut self,
+        id: &'a str,
+        content: &MarkdownText<'a>,
+        span: SimpleSpan,
+    ) {
+        self.open("footnote-definition", span, &attribute("id", id));
+        walk_text(self, content);
+        self.close();
+    }
+
+    fn visit_invalid(&mut self, span: SimpleSpan) {
+        self.leaf("invalid", span, "");
+    }
+
+    fn visit_plain_text(&mut self, text: &'a str, span: SimpleSpan) {
+        self.leaf("text", span, &format!(" {text:?}"));
+    }
+
+    fn visit_link(&mut self, link: &LinkType<'a>, span: SimpleSpan) {
+        let (name, mut attributes) = match link {
+            LinkType::InlineLink { text, uri, .. } => {
+                ("link", attribute("text", text) + &attribute("uri", uri))
+            }
+            LinkType::WikiLink {
+                target,
+                display_text,
+                ..
+            } => (
+                "wikilink",
+                attribute("target", target)
+                    + &display_text.map_or_else(String::new, |alias| attribute("alias", alias)),
+            ),
+            LinkType::ImageLink { text, uri } => {
+                ("image", attribute("text", text) + &attribute("uri", uri))
+            }
+        };
+        if let LinkType::InlineLink {
+            header: Some(header),
+            ..
+        }
+        | LinkType::WikiLink {
+            header: Some(header),
+            ..
+        } = link
+        {
+            attributes += &attribute("header", header);
+        }
+
+        self.leaf(name, span, &attributes);
+    }
+
+    fn visit_tag(&mut self, name: &'a str, span: SimpleSpan) {
+        self.leaf("tag", span, &attribute("name", name));
+    }
+
+    fn visit_footnote(&mut self, id: &'a str, span: SimpleSpan) {
+        self.leaf("footnote", span, &attribute("id", id));
+    }
+
+    fn visit_inline_field(
+        &mut self,
+        key: &'a str,
+        value: &'a str,
+        content: &MarkdownText<'a>,
+        span: SimpleSpan,
+    ) {
+        let attributes = attribute("key", key) + &attribute("value", value);
+        self.open("inline-field", span, &attributes);
+        walk_text(self, content);
+        self.close();
+    }
+}
@@ -0,0 +1,26 @@
+use std::{fs, path::Path};
+
+use lib_core::{config::Config, export::SiteExporter, vault::Vault};
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+/// Writes the vault at `root` as a static HTML site to `out`
+pub fn export(root: &Path, out: &Path) -> Result<()> {
+    let root = fs::canonicalize(root)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not open {}", root.display()))?;
+    let config = Config::from_file_or_default(root.join("rust-markdown-lsp.toml"));
+
+    let mut vault = Vault::default();
+    vault.set_parse_options(config.markdown.parse_options())?;
+    vault.index_directory(&root)?;
+
+    let summary = SiteExporter::new(&vault, &config.links, &root, out).export()?;
+    eprintln!(
+        "Exported {} pages and {} images to {}",
+        summary.pages,
+        summary.images,
+        out.display()
+    );
+
+    Ok(())
+}
@@ -5,12 +5,22 @@ use miette::{IntoDiagnostic, Result};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
+use args::{Action, parse_args};
 use lib_lsp::run_lsp;
 
-fn main() -> Result<()> {
-    let _guard = init_tracing()?;
+mod args;
+mod dump;
+mod export;
 
-    run_lsp()?;
+fn main() -> Result<()> {
+    match parse_args() {
+        Action::Lsp => {
+            let _guard = init_tracing()?;
+            run_lsp()?;
+        }
+        Action::Parse { file, format } => dump::dump(&file, format)?,
+        Action::Export { root, out } => export::export(&root, &out)?,
+    }
 
     Ok(())
 }